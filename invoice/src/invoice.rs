@@ -29,7 +29,7 @@ use invoice::{AddressNetwork, AddressPayload, Network};
 use rgb::{AttachId, ContractId, Layer1, SecretSeal};
 use strict_encoding::{FieldName, TypeName};
 
-use crate::{Amount, NonFungible};
+use crate::{Amount, AuthToken, NonFungible, LIB_NAME_RGB_CONTRACT};
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 #[non_exhaustive]
@@ -49,15 +49,18 @@ pub enum InvoiceStateError {
     ParseError(String),
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, From)]
 pub enum InvoiceState {
     #[display("")]
     Void,
     #[display("{0}")]
+    #[from]
     Amount(Amount),
     #[display(inner)]
+    #[from]
     Data(NonFungible),
     #[display(inner)]
+    #[from]
     Attach(AttachId),
 }
 
@@ -79,20 +82,24 @@ impl FromStr for InvoiceState {
 }
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_CONTRACT, tags = repr, into_u8, try_from_u8)]
+#[repr(u8)]
 #[non_exhaustive]
 pub enum ChainNet {
     #[display("bc")]
-    BitcoinMainnet,
+    #[strict_type(dumb)]
+    BitcoinMainnet = 0x00,
     #[display("tb")]
-    BitcoinTestnet,
+    BitcoinTestnet = 0x01,
     #[display("sb")]
-    BitcoinSignet,
+    BitcoinSignet = 0x02,
     #[display("bcrt")]
-    BitcoinRegtest,
+    BitcoinRegtest = 0x03,
     #[display("lq")]
-    LiquidMainnet,
+    LiquidMainnet = 0x10,
     #[display("tl")]
-    LiquidTestnet,
+    LiquidTestnet = 0x11,
 }
 
 impl ChainNet {
@@ -242,6 +249,17 @@ pub enum Beneficiary {
     WitnessVout(Pay2Vout),
 }
 
+impl Beneficiary {
+    /// Returns authentication token for the beneficiary if it is represented
+    /// by a blinded seal.
+    pub fn auth_token(&self) -> Option<AuthToken> {
+        match self {
+            Beneficiary::BlindedSeal(seal) => Some(AuthToken::from(*seal)),
+            Beneficiary::WitnessVout(_) => None,
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 #[non_exhaustive]
 pub struct RgbInvoice {
@@ -262,4 +280,36 @@ impl RgbInvoice {
     pub fn address_network(&self) -> AddressNetwork { self.beneficiary.address_network() }
     pub fn layer1(&self) -> Layer1 { self.beneficiary.layer1() }
     pub fn is_prod(&self) -> bool { self.beneficiary.is_prod() }
+
+    /// Detects whether the invoice leaves the choice of the contract to the
+    /// payer (for instance, a donation invoice accepting any asset). The
+    /// contract must be set with [`Self::set_contract`] before paying.
+    pub fn requires_contract(&self) -> bool { self.contract.is_none() }
+
+    /// Detects whether the invoice leaves the amount (or other state) to the
+    /// payer ("pay any amount" invoices). The state must be set with
+    /// [`Self::set_state`] before paying.
+    pub fn requires_state(&self) -> bool { self.owned_state == InvoiceState::Void }
+
+    /// Sets the contract chosen by the payer for an invoice which doesn't
+    /// specify it. Returns `false` and leaves the invoice unchanged if the
+    /// invoice already specifies a contract.
+    pub fn set_contract(&mut self, contract_id: ContractId) -> bool {
+        if self.contract.is_some() {
+            return false;
+        }
+        self.contract = Some(contract_id);
+        true
+    }
+
+    /// Sets the amount (or other state) chosen by the payer for an invoice
+    /// which doesn't specify it. Returns `false` and leaves the invoice
+    /// unchanged if the invoice already specifies the state.
+    pub fn set_state(&mut self, state: impl Into<InvoiceState>) -> bool {
+        if !self.requires_state() {
+            return false;
+        }
+        self.owned_state = state.into();
+        true
+    }
 }
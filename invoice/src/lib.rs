@@ -36,11 +36,13 @@ mod parse;
 mod builder;
 mod amount;
 mod data;
+mod token;
 
 pub use amount::{Amount, AmountParseError, CoinAmount, Precision, PrecisionError};
 pub use builder::RgbInvoiceBuilder;
 pub use data::{Allocation, NonFungible, OwnedFraction, TokenIndex};
-pub use parse::{InvoiceParseError, TransportParseError};
+pub use parse::{AuthTokenDisplay, InvoiceParseError, TransportParseError};
+pub use token::{AuthToken, AuthTokenError, AUTH_TOKEN_PREFIX};
 
 pub use crate::invoice::{
     Beneficiary, ChainNet, InvoiceState, Pay2Vout, Pay2VoutError, RgbInvoice, RgbTransport,
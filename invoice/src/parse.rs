@@ -36,6 +36,7 @@ use strict_encoding::{InvalidRString, TypeName};
 use crate::invoice::{
     Beneficiary, ChainNet, InvoiceState, Pay2Vout, RgbInvoice, RgbTransport, XChainNet,
 };
+use crate::{AuthToken, AuthTokenError, AUTH_TOKEN_PREFIX};
 
 const OMITTED: &str = "~";
 const EXPIRY: &str = "expiry";
@@ -121,6 +122,10 @@ pub enum InvoiceParseError {
     /// or a blinded UTXO seal.
     Beneficiary(String),
 
+    #[from]
+    #[display(inner)]
+    AuthToken(AuthTokenError),
+
     #[from]
     #[display(inner)]
     Num(ParseIntError),
@@ -282,6 +287,10 @@ impl FromStr for XChainNet<Beneficiary> {
             return Err(InvoiceParseError::Beneficiary(s.to_owned()));
         };
         let cn = ChainNet::from_str(cn)?;
+        if beneficiary.to_uppercase().starts_with(AUTH_TOKEN_PREFIX) {
+            let token = AuthToken::from_str(beneficiary)?;
+            return Ok(XChainNet::with(cn, Beneficiary::BlindedSeal(token.secret_seal())));
+        }
         if let Ok(seal) = SecretSeal::from_str(beneficiary) {
             return Ok(XChainNet::with(cn, Beneficiary::BlindedSeal(seal)));
         }
@@ -291,8 +300,22 @@ impl FromStr for XChainNet<Beneficiary> {
     }
 }
 
-impl Display for RgbInvoice {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+/// Invoice displayed with a blinded seal beneficiary encoded as
+/// [`AuthToken`]. Constructed by [`RgbInvoice::display_auth_token`].
+#[derive(Copy, Clone, Debug)]
+pub struct AuthTokenDisplay<'a>(&'a RgbInvoice);
+
+impl Display for AuthTokenDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result { self.0.fmt_with(f, true) }
+}
+
+impl RgbInvoice {
+    /// Returns an object displaying the invoice with a blinded seal
+    /// beneficiary encoded as a checksummed [`AuthToken`], which detects
+    /// typos when the invoice is parsed back.
+    pub fn display_auth_token(&self) -> AuthTokenDisplay { AuthTokenDisplay(self) }
+
+    fn fmt_with(&self, f: &mut Formatter, auth_token: bool) -> fmt::Result {
         let amt = self.owned_state.to_string();
         if let Some(contract) = self.contract {
             let id = if f.alternate() {
@@ -318,11 +341,13 @@ impl Display for RgbInvoice {
         if !amt.is_empty() {
             write!(f, "{amt}+")?;
         }
-        let beneficiary = if f.alternate() {
-            self.beneficiary.to_string().replace('-', "")
-        } else {
-            self.beneficiary.to_string()
+        let beneficiary = match self.beneficiary.into_inner().auth_token() {
+            Some(token) if auth_token => {
+                format!("{}:{token}", self.beneficiary.chain_network())
+            }
+            _ => self.beneficiary.to_string(),
         };
+        let beneficiary = if f.alternate() { beneficiary.replace('-', "") } else { beneficiary };
         f.write_str(&beneficiary)?;
         if self.has_params() {
             f.write_str("?")?;
@@ -348,6 +373,10 @@ impl Display for RgbInvoice {
     }
 }
 
+impl Display for RgbInvoice {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result { self.fmt_with(f, false) }
+}
+
 impl FromStr for RgbInvoice {
     type Err = InvoiceParseError;
 
@@ -741,6 +770,55 @@ mod test {
         assert!(matches!(result, Err(TransportParseError::InvalidTransport(_))));
     }
 
+    #[test]
+    fn payer_defined() {
+        let invoice_str =
+            "rgb:~/RGB20/bc:utxob:zlVS28Rb-amM5lih-ONXGACC-IUWD0Y$-0JXcnWZ-MQn8VEI-B39!F";
+        let mut invoice = RgbInvoice::from_str(invoice_str).unwrap();
+        assert!(invoice.requires_contract());
+        assert!(invoice.requires_state());
+
+        let contract_id =
+            ContractId::from_str("rgb:11Fa!$Dk-rUWXhy8-7H35qXm-pLGGLOo-txBWUgj-tbOaSbI").unwrap();
+        assert!(invoice.set_contract(contract_id));
+        assert!(invoice.set_state(Amount::from(100u64)));
+        assert!(!invoice.requires_contract());
+        assert!(!invoice.requires_state());
+        assert_eq!(
+            invoice.to_string(),
+            "rgb:11Fa!$Dk-rUWXhy8-7H35qXm-pLGGLOo-txBWUgj-tbOaSbI/RGB20/BF+bc:utxob:\
+             zlVS28Rb-amM5lih-ONXGACC-IUWD0Y$-0JXcnWZ-MQn8VEI-B39!F"
+        );
+
+        // values defined by the beneficiary can't be overridden
+        assert!(!invoice.set_contract(contract_id));
+        assert!(!invoice.set_state(Amount::from(1u64)));
+        assert_eq!(invoice.owned_state, InvoiceState::Amount(Amount::from(100u64)));
+    }
+
+    #[test]
+    fn auth_token_beneficiary() {
+        let invoice_str = "rgb:11Fa!$Dk-rUWXhy8-7H35qXm-pLGGLOo-txBWUgj-tbOaSbI/RGB20/BF+bc:utxob:\
+                           zlVS28Rb-amM5lih-ONXGACC-IUWD0Y$-0JXcnWZ-MQn8VEI-B39!F";
+        let invoice = RgbInvoice::from_str(invoice_str).unwrap();
+
+        let token_str = invoice.display_auth_token().to_string();
+        assert!(token_str.contains(&format!("+bc:{AUTH_TOKEN_PREFIX}")));
+        assert_eq!(RgbInvoice::from_str(&token_str).unwrap(), invoice);
+        let compact = format!("{:#}", invoice.display_auth_token());
+        assert_eq!(compact, token_str.replace('-', ""));
+        assert_eq!(RgbInvoice::from_str(&compact).unwrap(), invoice);
+
+        let pos = token_str.find(AUTH_TOKEN_PREFIX).unwrap() + AUTH_TOKEN_PREFIX.len() + 2;
+        let c = if &token_str[pos..=pos] == "0" { "1" } else { "0" };
+        let mut typo = token_str.clone();
+        typo.replace_range(pos..=pos, c);
+        assert!(matches!(
+            RgbInvoice::from_str(&typo),
+            Err(InvoiceParseError::AuthToken(AuthTokenError::Checksum))
+        ));
+    }
+
     #[test]
     fn pay2vout_parse() {
         let p = Pay2Vout {
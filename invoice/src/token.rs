@@ -0,0 +1,147 @@
+// RGB wallet library for smart contracts on Bitcoin & Lightning network
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Authentication tokens represent blinded seals used as invoice beneficiaries
+//! in a compact form which is easy to read, type and encode into QR codes.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::ByteArray;
+use commit_verify::{DigestExt, Sha256};
+use rgb::SecretSeal;
+
+/// Crockford base32 alphabet: uppercase-only and thus fitting into QR code
+/// alphanumeric mode.
+pub const ENC_BASE32_QR: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+fast32::make_base32_alpha!(BASE32_QR, DEC_BASE32_QR, ENC_BASE32_QR);
+
+pub const AUTH_TOKEN_PREFIX: &str = "AT:";
+const AUTH_TOKEN_CHECKSUM_LEN: usize = 4;
+const AUTH_TOKEN_CHUNK_LEN: usize = 8;
+const AUTH_TOKEN_CHUNK_SEP: char = '-';
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AuthTokenError {
+    /// auth token must start with 'AT:' prefix.
+    NoPrefix,
+
+    /// auth token contains invalid characters.
+    Encoding,
+
+    /// auth token has invalid length of {0} bytes.
+    InvalidLength(usize),
+
+    /// auth token checksum mismatch; most probably the token contains a typo.
+    Checksum,
+}
+
+/// Authentication token for an invoice, which is a blinded seal (secret seal)
+/// displayed with checksummed QR-alphanumeric-friendly encoding.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref)]
+pub struct AuthToken(#[from] SecretSeal);
+
+impl AuthToken {
+    fn checksum(payload: &[u8]) -> [u8; AUTH_TOKEN_CHECKSUM_LEN] {
+        let mut hasher = Sha256::default();
+        hasher.input_raw(payload);
+        let hash = hasher.finish();
+        let mut checksum = [0u8; AUTH_TOKEN_CHECKSUM_LEN];
+        checksum.copy_from_slice(&hash[..AUTH_TOKEN_CHECKSUM_LEN]);
+        checksum
+    }
+
+    pub fn secret_seal(&self) -> SecretSeal { self.0 }
+}
+
+impl Display for AuthToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut data = self.0.to_byte_array().to_vec();
+        data.extend(Self::checksum(&data));
+        let s = BASE32_QR.encode(&data);
+        f.write_str(AUTH_TOKEN_PREFIX)?;
+        for (no, chunk) in s.as_bytes().chunks(AUTH_TOKEN_CHUNK_LEN).enumerate() {
+            if no > 0 {
+                write!(f, "{AUTH_TOKEN_CHUNK_SEP}")?;
+            }
+            f.write_str(std::str::from_utf8(chunk).expect("base32 is ASCII"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AuthToken {
+    type Err = AuthTokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_uppercase();
+        let s = s
+            .strip_prefix(AUTH_TOKEN_PREFIX)
+            .ok_or(AuthTokenError::NoPrefix)?
+            .replace(AUTH_TOKEN_CHUNK_SEP, "");
+        let data = BASE32_QR
+            .decode_str(&s)
+            .map_err(|_| AuthTokenError::Encoding)?;
+        if data.len() != 32 + AUTH_TOKEN_CHECKSUM_LEN {
+            return Err(AuthTokenError::InvalidLength(data.len()));
+        }
+        let (payload, checksum) = data.split_at(32);
+        if Self::checksum(payload) != checksum {
+            return Err(AuthTokenError::Checksum);
+        }
+        let mut seal = [0u8; 32];
+        seal.copy_from_slice(payload);
+        Ok(AuthToken(SecretSeal::from(seal)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn auth_token_round_trip() {
+        let token = AuthToken::from(SecretSeal::from([0xA5u8; 32]));
+        let s = token.to_string();
+        assert!(s.starts_with(AUTH_TOKEN_PREFIX));
+        assert!(s
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || c == ':' || c == '-'));
+        assert_eq!(AuthToken::from_str(&s).unwrap(), token);
+        assert_eq!(AuthToken::from_str(&s.to_lowercase()).unwrap(), token);
+    }
+
+    #[test]
+    fn auth_token_typo() {
+        let token = AuthToken::from(SecretSeal::from([0x17u8; 32]));
+        let mut s = token.to_string();
+        let pos = AUTH_TOKEN_PREFIX.len() + 2;
+        let c = if &s[pos..=pos] == "0" { "1" } else { "0" };
+        s.replace_range(pos..=pos, c);
+        assert_eq!(AuthToken::from_str(&s), Err(AuthTokenError::Checksum));
+        assert_eq!(
+            AuthToken::from_str(&s[AUTH_TOKEN_PREFIX.len()..]),
+            Err(AuthTokenError::NoPrefix)
+        );
+    }
+}
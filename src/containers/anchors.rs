@@ -25,13 +25,14 @@ use std::vec;
 use amplify::ByteArray;
 use bp::dbc::opret::OpretProof;
 use bp::dbc::tapret::TapretProof;
-use bp::dbc::{anchor, Anchor};
+use bp::dbc::{anchor, Anchor, Method, Proof};
 use bp::{dbc, Tx, Txid};
 use commit_verify::mpc;
-use rgb::validation::{DbcProof, EAnchor};
+use rgb::validation::{DbcProof, EAnchor, ResolveWitness, WitnessResolverError};
+use rgb::vm::WitnessOrd;
 use rgb::{
-    BundleId, DiscloseHash, OpId, Operation, Transition, TransitionBundle, XChain, XGraphSeal,
-    XWitnessId,
+    BundleId, ContractId, DiscloseHash, OpId, Operation, Transition, TransitionBundle, TxoSeal,
+    XChain, XGraphSeal, XOutpoint, XOutputSeal, XWitnessId,
 };
 use strict_encoding::StrictDumb;
 
@@ -73,6 +74,96 @@ impl SealWitness {
     }
 
     pub fn witness_id(&self) -> XWitnessId { self.public.to_witness_id() }
+
+    /// Verifies that the witness transaction closes the given seal and commits
+    /// to the bundle of the given contract, returning the witness position in
+    /// the blockchain.
+    ///
+    /// Allows checking a single witness received out-of-band without
+    /// consuming a consignment.
+    pub fn verify(
+        &self,
+        seal: XOutputSeal,
+        contract_id: ContractId,
+        bundle_id: BundleId,
+        resolver: &impl ResolveWitness,
+    ) -> Result<WitnessOrd, WitnessVerifyError> {
+        let witness_id = self.witness_id();
+        let outpoint = seal.to_outpoint();
+        let tx = resolver.resolve_pub_witness(witness_id)?;
+        if seal.layer1() != witness_id.layer1()
+            || !tx
+                .as_reduced_unsafe()
+                .inputs
+                .iter()
+                .any(|input| &input.prev_output == outpoint.as_reduced_unsafe())
+        {
+            return Err(WitnessVerifyError::SealNotClosed(witness_id, outpoint));
+        }
+
+        let tx = tx.as_reduced_unsafe();
+        match (&self.anchors, seal.method()) {
+            (AnchorSet::Tapret(tapret) | AnchorSet::Double { tapret, .. }, Method::TapretFirst) => {
+                verify_anchor(tapret, witness_id, contract_id, bundle_id, tx)?
+            }
+            (AnchorSet::Opret(opret) | AnchorSet::Double { opret, .. }, Method::OpretFirst) => {
+                verify_anchor(opret, witness_id, contract_id, bundle_id, tx)?
+            }
+            _ => return Err(WitnessVerifyError::NoAnchor(witness_id, bundle_id, contract_id)),
+        }
+
+        Ok(resolver.resolve_pub_witness_ord(witness_id)?)
+    }
+}
+
+/// Verifies that the witness transaction commits to the anchor, and the anchor
+/// MPC tree contains the bundle under the contract protocol id.
+fn verify_anchor<D: dbc::Proof>(
+    anchor: &Anchor<mpc::MerkleBlock, D>,
+    witness_id: XWitnessId,
+    contract_id: ContractId,
+    bundle_id: BundleId,
+    tx: &Tx,
+) -> Result<(), WitnessVerifyError> {
+    let protocol_id = mpc::ProtocolId::from(contract_id);
+    let message = mpc::Message::from(bundle_id);
+    if anchor.mpc_proof.to_known_message_map().get(&protocol_id) != Some(&message) {
+        return Err(WitnessVerifyError::NoAnchor(witness_id, bundle_id, contract_id));
+    }
+    let proof = anchor
+        .to_merkle_proof(protocol_id)
+        .map_err(|_| WitnessVerifyError::InvalidMpc(witness_id, bundle_id))?;
+    proof
+        .verify(protocol_id, message, tx)
+        .map_err(|err| match err {
+            anchor::VerifyError::Mpc(_) => WitnessVerifyError::InvalidMpc(witness_id, bundle_id),
+            anchor::VerifyError::Dbc(err) => {
+                WitnessVerifyError::InvalidDbc(witness_id, err.to_string())
+            }
+        })?;
+    Ok(())
+}
+
+/// Errors happening during [`SealWitness::verify`].
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum WitnessVerifyError {
+    /// witness {0} doesn't close seal {1}.
+    SealNotClosed(XWitnessId, XOutpoint),
+
+    /// witness {0} doesn't contain an anchor for bundle {1} of contract {2}.
+    NoAnchor(XWitnessId, BundleId, ContractId),
+
+    /// multi-protocol commitment proof for bundle {1} in witness {0} is
+    /// invalid.
+    InvalidMpc(XWitnessId, BundleId),
+
+    /// witness transaction {0} doesn't contain a valid commitment: {1}
+    InvalidDbc(XWitnessId, String),
+
+    #[from]
+    #[display(inner)]
+    Resolver(WitnessResolverError),
 }
 
 pub type XPubWitness = XChain<PubWitness>;
@@ -468,6 +559,23 @@ impl AnchorSet {
             .map(|msg| BundleId::from_byte_array(msg.to_byte_array()))
     }
 
+    /// Returns ids of the bundles of the contract committed by the anchors,
+    /// as long as the anchors reveal them.
+    pub fn contract_bundle_ids(&self, contract_id: ContractId) -> impl Iterator<Item = BundleId> {
+        let protocol_id = mpc::ProtocolId::from(contract_id);
+        let proofs = match self {
+            AnchorSet::Tapret(tapret) => vec![&tapret.mpc_proof],
+            AnchorSet::Opret(opret) => vec![&opret.mpc_proof],
+            AnchorSet::Double { tapret, opret } => vec![&tapret.mpc_proof, &opret.mpc_proof],
+        };
+        proofs
+            .into_iter()
+            .filter_map(|proof| proof.to_known_message_map().get(&protocol_id).copied())
+            .map(|msg| BundleId::from_byte_array(msg.to_byte_array()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
     pub fn has_tapret(&self) -> bool { matches!(self, Self::Tapret(_) | Self::Double { .. }) }
 
     pub fn has_opret(&self) -> bool { matches!(self, Self::Opret(_) | Self::Double { .. }) }
@@ -503,3 +611,131 @@ impl AnchorSet {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bp::seals::txout::ExplicitSeal;
+    use bp::{LockTime, Sats, ScriptPubkey, SeqNo, SigScript, TxIn, TxOut, TxVer, Witness};
+    use commit_verify::{CommitId, TryCommitVerify};
+    use rgb::vm::XWitnessTx;
+
+    use super::*;
+
+    struct TxResolver(Tx);
+
+    impl ResolveWitness for TxResolver {
+        fn resolve_pub_witness(
+            &self,
+            witness_id: XWitnessId,
+        ) -> Result<XWitnessTx, WitnessResolverError> {
+            if witness_id != XChain::Bitcoin(self.0.txid()) {
+                return Err(WitnessResolverError::Unknown(witness_id));
+            }
+            Ok(XChain::Bitcoin(self.0.clone()))
+        }
+
+        fn resolve_pub_witness_ord(
+            &self,
+            witness_id: XWitnessId,
+        ) -> Result<WitnessOrd, WitnessResolverError> {
+            if witness_id != XChain::Bitcoin(self.0.txid()) {
+                return Err(WitnessResolverError::Unknown(witness_id));
+            }
+            Ok(WitnessOrd::Tentative)
+        }
+    }
+
+    fn contract_id() -> ContractId { ContractId::from_byte_array([0x11; 32]) }
+
+    fn bundle_id() -> BundleId { BundleId::from_byte_array([0x22; 32]) }
+
+    fn seal(method: Method, vout: u32) -> XOutputSeal {
+        XChain::Bitcoin(ExplicitSeal::with(method, Txid::from_byte_array([0x33; 32]), vout))
+    }
+
+    fn mpc_block(entropy: u64) -> mpc::MerkleBlock {
+        let source = mpc::MultiSource {
+            messages: Confined::try_from_iter([(contract_id().into(), bundle_id().into())])
+                .unwrap(),
+            static_entropy: Some(entropy),
+            ..default!()
+        };
+        let tree = mpc::MerkleTree::try_commit(&source).unwrap();
+        mpc::MerkleBlock::from(&tree)
+    }
+
+    // Witness transaction spending `seal(Method::OpretFirst, 0)` and committing
+    // to the bundle with opret.
+    fn witness() -> (SealWitness, Tx) {
+        let mpc_proof = mpc_block(1);
+        let commitment = mpc_proof.commit_id();
+        let tx = Tx {
+            version: TxVer::V2,
+            inputs: Confined::from_checked(vec![TxIn {
+                prev_output: seal(Method::OpretFirst, 0)
+                    .as_reduced_unsafe()
+                    .to_outpoint(),
+                sig_script: SigScript::default(),
+                sequence: SeqNo::ZERO,
+                witness: Witness::default(),
+            }]),
+            outputs: Confined::from_checked(vec![TxOut::new(
+                ScriptPubkey::op_return(commitment.as_slice()),
+                Sats::ZERO,
+            )]),
+            lock_time: LockTime::ZERO,
+        };
+        let anchors = AnchorSet::Opret(Anchor::new(mpc_proof, OpretProof::default()));
+        (SealWitness::new(XChain::Bitcoin(PubWitness::Tx(tx.clone())), anchors), tx)
+    }
+
+    #[test]
+    fn verify_valid() {
+        let (witness, tx) = witness();
+        let resolver = TxResolver(tx);
+        assert_eq!(
+            witness
+                .verify(seal(Method::OpretFirst, 0), contract_id(), bundle_id(), &resolver)
+                .unwrap(),
+            WitnessOrd::Tentative
+        );
+    }
+
+    #[test]
+    fn verify_invalid() {
+        let (witness, tx) = witness();
+        let resolver = TxResolver(tx);
+
+        // Seal is not spent by the witness
+        assert!(matches!(
+            witness.verify(seal(Method::OpretFirst, 1), contract_id(), bundle_id(), &resolver),
+            Err(WitnessVerifyError::SealNotClosed(..))
+        ));
+
+        // Seal requires tapret commitment
+        assert!(matches!(
+            witness.verify(seal(Method::TapretFirst, 0), contract_id(), bundle_id(), &resolver),
+            Err(WitnessVerifyError::NoAnchor(..))
+        ));
+
+        // Bundle is not a part of the anchor
+        assert!(matches!(
+            witness.verify(
+                seal(Method::OpretFirst, 0),
+                contract_id(),
+                BundleId::from_byte_array([0x44; 32]),
+                &resolver
+            ),
+            Err(WitnessVerifyError::NoAnchor(..))
+        ));
+
+        // Tampered anchor, which doesn't match the commitment in the transaction
+        let mut tampered = witness.clone();
+        tampered.anchors = AnchorSet::Opret(Anchor::new(mpc_block(2), OpretProof::default()));
+        assert!(matches!(
+            tampered.verify(seal(Method::OpretFirst, 0), contract_id(), bundle_id(), &resolver),
+            Err(WitnessVerifyError::InvalidDbc(..))
+        ));
+    }
+}
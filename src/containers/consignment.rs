@@ -19,9 +19,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::io::Read;
 use std::ops::Deref;
 use std::str::FromStr;
 
@@ -29,31 +30,163 @@ use aluvm::library::Lib;
 use amplify::confinement::{
     Confined, LargeOrdSet, MediumBlob, SmallOrdMap, SmallOrdSet, TinyOrdMap, TinyOrdSet,
 };
-use amplify::{ByteArray, Bytes32};
+use amplify::{ByteArray, Bytes32, Wrapper};
 use armor::{ArmorHeader, AsciiArmor, StrictArmor, StrictArmorError};
 use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+use bp::Txid;
 use commit_verify::{CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, Sha256};
-use rgb::validation::{ResolveWitness, Validator, Validity, Warning, CONSIGNMENT_MAX_LIBS};
+use rgb::validation::{
+    ResolveWitness, Validator, Validity, Warning, WitnessResolverError, CONSIGNMENT_MAX_LIBS,
+};
+use rgb::vm::{WitnessOrd, XWitnessTx};
 use rgb::{
-    impl_serde_baid64, validation, AttachId, BundleId, ContractId, Extension, Genesis, GraphSeal,
-    Operation, Schema, SchemaId, XChain,
+    impl_serde_baid64, validation, AttachId, BundleId, ContractId, DiscloseHash, Extension,
+    Genesis, GraphSeal, OpId, Operation, Schema, SchemaId, Transition, XChain, XWitnessId,
 };
 use rgbcore::validation::ConsignmentApi;
-use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize, TypeName};
 use strict_types::TypeSystem;
 
 use super::{
-    ContainerVer, ContentId, ContentSigs, IndexedConsignment, Supplement, WitnessBundle,
-    ASCII_ARMOR_CONSIGNMENT_TYPE, ASCII_ARMOR_CONTRACT, ASCII_ARMOR_IFACE, ASCII_ARMOR_SCHEMA,
-    ASCII_ARMOR_TERMINAL, ASCII_ARMOR_VERSION,
+    ContainerVer, ContentId, ContentSigs, FileContent, HeaderSource, IndexedConsignment, LoadError,
+    SpvProof, Supplement, ToWitnessId, WitnessBundle, ASCII_ARMOR_CONSIGNMENT_TYPE,
+    ASCII_ARMOR_CONTRACT, ASCII_ARMOR_IFACE, ASCII_ARMOR_SCHEMA, ASCII_ARMOR_TERMINAL,
+    ASCII_ARMOR_VERSION,
 };
 use crate::interface::{Iface, IfaceImpl};
 use crate::persistence::{MemContract, MemContractState};
-use crate::{SecretSeal, LIB_NAME_RGB_STD};
+use crate::{SecretSeal, LIB_NAME_RGB_STD, LIB_NAME_RGB_STORAGE};
 
 pub type Transfer = Consignment<true>;
 pub type Contract = Consignment<false>;
 
+/// Unix timestamp of the RGB epoch (2019-01-01 00:00:00 UTC). No valid RGB
+/// contract can be issued before this date.
+pub const RGB_EPOCH: i64 = 1_546_300_800;
+
+/// Maximal tolerated drift of the genesis timestamp into the future (in
+/// seconds), accounting for clock skew between the issuer and the validator.
+pub const TIMESTAMP_MAX_DRIFT: i64 = 2 * 60 * 60;
+
+/// Defines how the timestamp sanity failures are handled when a contract is
+/// issued or consumed.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STORAGE, tags = repr, into_u8, try_from_u8)]
+#[display(lowercase)]
+#[repr(u8)]
+pub enum TimestampPolicy {
+    /// Insane timestamps are reported as validation warnings.
+    #[default]
+    #[strict_type(dumb)]
+    Warn = 0,
+    /// Insane timestamps result in an error.
+    Strict = 1,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TimestampError {
+    /// genesis timestamp {0} lies in the future.
+    Future(i64),
+
+    /// genesis timestamp {0} precedes RGB epoch.
+    BeforeEpoch(i64),
+}
+
+/// Checks that the timestamp is neither ahead of `now` (accounting for
+/// [`TIMESTAMP_MAX_DRIFT`]) nor before [`RGB_EPOCH`].
+///
+/// The current time is provided by the caller, such that the check stays
+/// deterministic.
+pub fn check_timestamp(timestamp: i64, now: i64) -> Result<(), TimestampError> {
+    if timestamp < RGB_EPOCH {
+        return Err(TimestampError::BeforeEpoch(timestamp));
+    }
+    if timestamp > now + TIMESTAMP_MAX_DRIFT {
+        return Err(TimestampError::Future(timestamp));
+    }
+    Ok(())
+}
+
+/// operations contain a dependency cycle involving operation {0}.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct CyclicDependency(pub OpId);
+
+/// Errors detected by [`Consignment::verify_terminals`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TerminalError {
+    /// terminal bundle {0} is absent from the consignment.
+    BundleAbsent(BundleId),
+
+    /// terminal seal {1} is not assigned by any of the known transitions of
+    /// bundle {0}.
+    SealAbsent(BundleId, XChain<SecretSeal>),
+
+    /// terminal bundle {0} is not reachable from genesis since operation {1}
+    /// is absent from the consignment.
+    Unreachable(BundleId, OpId),
+}
+
+/// Orders operations such that each one follows all operations it depends
+/// on. Operations which do not depend on each other are ordered by their id,
+/// so the result is the same regardless of the order in which operations are
+/// provided. Dependencies on operations outside of the provided set are
+/// ignored.
+pub fn topological_order<I: IntoIterator<Item = OpId>>(
+    ops: impl IntoIterator<Item = (OpId, I)>,
+) -> Result<Vec<OpId>, CyclicDependency> {
+    sort_topologically(ops).map_err(CyclicDependency)
+}
+
+/// Implementation of [`topological_order`] for any kind of graph nodes.
+/// Returns one of the nodes of a dependency cycle, if there is any.
+fn sort_topologically<T: Copy + Ord, I: IntoIterator<Item = T>>(
+    nodes: impl IntoIterator<Item = (T, I)>,
+) -> Result<Vec<T>, T> {
+    let mut deps = BTreeMap::<T, BTreeSet<T>>::new();
+    for (id, parents) in nodes {
+        deps.entry(id).or_default().extend(parents);
+    }
+    let mut children = BTreeMap::<T, BTreeSet<T>>::new();
+    let known = deps.keys().copied().collect::<BTreeSet<_>>();
+    for (id, parents) in &mut deps {
+        parents.retain(|parent| parent != id && known.contains(parent));
+        for parent in parents.iter() {
+            children.entry(*parent).or_default().insert(*id);
+        }
+    }
+
+    let mut ready = deps
+        .iter()
+        .filter(|(_, parents)| parents.is_empty())
+        .map(|(id, _)| *id)
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(deps.len());
+    while let Some(id) = ready.pop_first() {
+        order.push(id);
+        for child in children.remove(&id).unwrap_or_default() {
+            let parents = deps.get_mut(&child).expect("children are always known");
+            parents.remove(&id);
+            if parents.is_empty() {
+                ready.insert(child);
+            }
+        }
+    }
+
+    if order.len() < deps.len() {
+        let id = deps
+            .into_iter()
+            .find(|(_, parents)| !parents.is_empty())
+            .map(|(id, _)| id)
+            .expect("unordered operations always have dependencies");
+        return Err(id);
+    }
+    Ok(order)
+}
+
 pub trait ConsignmentExt {
     fn contract_id(&self) -> ContractId;
     fn schema_id(&self) -> SchemaId;
@@ -150,6 +283,24 @@ impl<const TRANSFER: bool> ValidConsignment<TRANSFER> {
     pub fn split(self) -> (Consignment<TRANSFER>, validation::Status) {
         (self.consignment, self.validation_status)
     }
+
+    /// Adds warning produced by a local check performed on top of the
+    /// consensus validation.
+    pub(crate) fn add_warning(&mut self, warning: validation::Warning) {
+        self.validation_status.add_warning(warning);
+    }
+}
+
+#[cfg(test)]
+impl<const TRANSFER: bool> ValidConsignment<TRANSFER> {
+    /// Wraps the consignment without validating it, for testing code which
+    /// processes already validated consignments.
+    pub(crate) fn assume_valid(consignment: Consignment<TRANSFER>) -> Self {
+        ValidConsignment {
+            validation_status: validation::Status::new(),
+            consignment,
+        }
+    }
 }
 
 impl<const TRANSFER: bool> Deref for ValidConsignment<TRANSFER> {
@@ -279,10 +430,308 @@ impl<const TRANSFER: bool> ConsignmentExt for Consignment<TRANSFER> {
     fn bundled_witnesses(&self) -> impl Iterator<Item = &WitnessBundle> { self.bundles.iter() }
 }
 
+/// Read-only summary of a consignment, which can be displayed to the user for
+/// review before the consignment is validated and accepted.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ConsignmentSummary {
+    pub consignment_id: ConsignmentId,
+    pub version: ContainerVer,
+    pub transfer: bool,
+    pub contract_id: ContractId,
+    pub schema_id: SchemaId,
+    pub ifaces: Vec<TypeName>,
+    pub terminals: BTreeMap<BundleId, XChain<SecretSeal>>,
+    /// Number of operations, including genesis, extensions and all known
+    /// state transitions.
+    pub operations: usize,
+    /// Witness transactions in the order they are present in the consignment.
+    pub witnesses: Vec<XWitnessId>,
+}
+
+/// Structural difference between two consignments, computed with
+/// [`Consignment::diff`]. The "left" side is the consignment on which the
+/// method was called, and the "right" side is the other consignment.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ConsignmentDiff {
+    /// Contract ids of both consignments, if they differ.
+    pub contracts: Option<(ContractId, ContractId)>,
+    /// Operations present only in the left consignment.
+    pub left_operations: BTreeSet<OpId>,
+    /// Operations present only in the right consignment.
+    pub right_operations: BTreeSet<OpId>,
+    /// Witnesses present only in the left consignment.
+    pub left_witnesses: BTreeSet<XWitnessId>,
+    /// Witnesses present only in the right consignment.
+    pub right_witnesses: BTreeSet<XWitnessId>,
+    /// Witnesses present in both consignments, but with different anchors or
+    /// bundles.
+    pub changed_witnesses: BTreeSet<XWitnessId>,
+    /// Terminals which differ between the consignments, with the left and
+    /// right seals (if present).
+    pub terminals: BTreeMap<BundleId, (Option<XChain<SecretSeal>>, Option<XChain<SecretSeal>>)>,
+}
+
+impl ConsignmentDiff {
+    /// Detects whether consignments are structurally identical.
+    pub fn is_empty(&self) -> bool { *self == Self::default() }
+}
+
+impl Display for ConsignmentDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some((left, right)) = self.contracts {
+            writeln!(f, "- contract {left}")?;
+            writeln!(f, "+ contract {right}")?;
+        }
+        for opid in &self.left_operations {
+            writeln!(f, "- operation {opid}")?;
+        }
+        for opid in &self.right_operations {
+            writeln!(f, "+ operation {opid}")?;
+        }
+        for witness_id in &self.left_witnesses {
+            writeln!(f, "- witness {witness_id}")?;
+        }
+        for witness_id in &self.right_witnesses {
+            writeln!(f, "+ witness {witness_id}")?;
+        }
+        for witness_id in &self.changed_witnesses {
+            writeln!(f, "~ witness {witness_id}")?;
+        }
+        for (bundle_id, (left, right)) in &self.terminals {
+            if let Some(seal) = left {
+                writeln!(f, "- terminal {bundle_id} {seal}")?;
+            }
+            if let Some(seal) = right {
+                writeln!(f, "+ terminal {bundle_id} {seal}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<const TRANSFER: bool> Consignment<TRANSFER> {
+    /// Returns consignment identifier.
+    ///
+    /// All consignment collections are ordered, so the same set of data always
+    /// produces the same binary serialization and the same identifier,
+    /// regardless of the order in which the data were added. Thus the id can
+    /// be used to reference the consignment by both its sender and receiver.
     #[inline]
     pub fn consignment_id(&self) -> ConsignmentId { self.commit_id() }
 
+    /// Decodes consignment from a reader and returns its summary, without
+    /// validating the consignment or adding it to a stock.
+    pub fn parse(data: impl Read) -> Result<ConsignmentSummary, LoadError>
+    where Self: FileContent {
+        Self::load(data).map(|consignment| consignment.summary())
+    }
+
+    /// Returns ids of all operations in the consignment (genesis, extensions
+    /// and state transitions) in deterministic topological order (see
+    /// [`topological_order`]).
+    pub fn operation_order(&self) -> Vec<OpId> {
+        let genesis = [(self.genesis.id(), vec![])];
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|ext| (ext.id(), ext.redeemed.as_inner().values().copied().collect()));
+        let transitions = self.bundles.iter().flat_map(|wb| {
+            wb.known_transitions().map(|transition| {
+                let inputs = transition.inputs().iter().map(|input| input.prev_out.op);
+                (transition.id(), inputs.collect())
+            })
+        });
+        topological_order(genesis.into_iter().chain(extensions).chain(transitions))
+            .expect("operations commit to their dependencies and thus can't form cycles")
+    }
+
+    /// Orders witness bundles such that each of them follows the bundles
+    /// containing the operations it depends on (see [`topological_order`]).
+    /// Bundles which do not depend on each other are ordered by their witness
+    /// id, so the order is the same regardless of the original order of the
+    /// bundles.
+    ///
+    /// Fails if the operations of some bundles depend on each other, which may
+    /// happen only in invalid consignments.
+    pub fn sort_bundles(&mut self) -> Result<(), CyclicDependency> {
+        let mut witnesses = BTreeMap::<OpId, XWitnessId>::new();
+        for wb in &self.bundles {
+            let witness_id = wb.witness_id();
+            witnesses.extend(wb.known_transitions().map(|t| (t.id(), witness_id)));
+        }
+        let mut deps = BTreeMap::<XWitnessId, BTreeSet<XWitnessId>>::new();
+        for wb in &self.bundles {
+            let parents = deps.entry(wb.witness_id()).or_default();
+            for transition in wb.known_transitions() {
+                parents.extend(
+                    transition
+                        .inputs()
+                        .iter()
+                        .filter_map(|input| witnesses.get(&input.prev_out.op))
+                        .copied(),
+                );
+            }
+        }
+        let order = sort_topologically(deps).map_err(|witness_id| {
+            let opid = witnesses
+                .iter()
+                .find(|(_, id)| **id == witness_id)
+                .map(|(opid, _)| *opid)
+                .expect("bundles with dependencies always have known transitions");
+            CyclicDependency(opid)
+        })?;
+
+        let mut bundles = BTreeMap::<XWitnessId, Vec<WitnessBundle>>::new();
+        for wb in self.bundles.iter().cloned() {
+            bundles.entry(wb.witness_id()).or_default().push(wb);
+        }
+        let bundles = order
+            .into_iter()
+            .flat_map(|witness_id| bundles.remove(&witness_id).unwrap_or_default());
+        self.bundles = Confined::from_iter_checked(bundles);
+        Ok(())
+    }
+
+    /// Checks that every terminal seal is assigned by a transition from the
+    /// terminal bundle, and that the bundle is reachable from genesis through
+    /// the operations included into the consignment.
+    pub fn verify_terminals(&self) -> Result<(), TerminalError> {
+        let mut ops = BTreeMap::<OpId, Vec<OpId>>::new();
+        ops.insert(self.genesis.id(), vec![]);
+        for ext in &self.extensions {
+            ops.insert(ext.id(), ext.redeemed.as_inner().values().copied().collect());
+        }
+        for transition in self.bundles.iter().flat_map(|wb| wb.known_transitions()) {
+            let inputs = transition.inputs().iter().map(|input| input.prev_out.op);
+            ops.insert(transition.id(), inputs.collect());
+        }
+
+        for (bundle_id, secret) in &self.terminals {
+            let bundle_id = *bundle_id;
+            let transitions = self
+                .bundles
+                .iter()
+                .flat_map(|wb| wb.anchored_bundles.bundles())
+                .filter(|bundle| bundle.bundle_id() == bundle_id)
+                .flat_map(|bundle| bundle.known_transitions.values())
+                .collect::<Vec<_>>();
+            if transitions.is_empty() {
+                return Err(TerminalError::BundleAbsent(bundle_id));
+            }
+            if !transitions.iter().any(|transition| {
+                transition
+                    .assignments
+                    .values()
+                    .any(|assigns| assigns.to_confidential_seals().contains(secret))
+            }) {
+                return Err(TerminalError::SealAbsent(bundle_id, *secret));
+            }
+
+            let mut visited = BTreeSet::new();
+            let mut queue = transitions.iter().map(|t| t.id()).collect::<Vec<_>>();
+            while let Some(id) = queue.pop() {
+                if !visited.insert(id) {
+                    continue;
+                }
+                let parents = ops
+                    .get(&id)
+                    .ok_or(TerminalError::Unreachable(bundle_id, id))?;
+                queue.extend(parents);
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the consignment with some other consignment structurally:
+    /// which operations, witnesses and terminals are present in one of them,
+    /// but not the other.
+    pub fn diff<const TRANSFER2: bool>(&self, other: &Consignment<TRANSFER2>) -> ConsignmentDiff {
+        fn operations(
+            genesis: &Genesis,
+            extensions: &LargeOrdSet<Extension>,
+            bundles: &LargeOrdSet<WitnessBundle>,
+        ) -> BTreeSet<OpId> {
+            bundles
+                .iter()
+                .flat_map(|wb| wb.known_transitions().map(Transition::id))
+                .chain(extensions.iter().map(Extension::id))
+                .chain([genesis.id()])
+                .collect()
+        }
+        fn witnesses(bundles: &LargeOrdSet<WitnessBundle>) -> BTreeMap<XWitnessId, DiscloseHash> {
+            bundles
+                .iter()
+                .map(|wb| (wb.pub_witness.to_witness_id(), wb.commit_id()))
+                .collect()
+        }
+
+        let left_ops = operations(&self.genesis, &self.extensions, &self.bundles);
+        let right_ops = operations(&other.genesis, &other.extensions, &other.bundles);
+        let left_witnesses = witnesses(&self.bundles);
+        let right_witnesses = witnesses(&other.bundles);
+
+        let mut terminals = BTreeMap::new();
+        for bundle_id in self.terminals.keys().chain(other.terminals.keys()) {
+            let left = self.terminals.get(bundle_id).copied();
+            let right = other.terminals.get(bundle_id).copied();
+            if left != right {
+                terminals.insert(*bundle_id, (left, right));
+            }
+        }
+
+        ConsignmentDiff {
+            contracts: (self.contract_id() != other.contract_id())
+                .then(|| (self.contract_id(), other.contract_id())),
+            left_operations: left_ops.difference(&right_ops).copied().collect(),
+            right_operations: right_ops.difference(&left_ops).copied().collect(),
+            left_witnesses: left_witnesses
+                .keys()
+                .filter(|id| !right_witnesses.contains_key(id))
+                .copied()
+                .collect(),
+            right_witnesses: right_witnesses
+                .keys()
+                .filter(|id| !left_witnesses.contains_key(id))
+                .copied()
+                .collect(),
+            changed_witnesses: left_witnesses
+                .iter()
+                .filter(|(id, commit)| matches!(right_witnesses.get(id), Some(c) if c != *commit))
+                .map(|(id, _)| *id)
+                .collect(),
+            terminals,
+        }
+    }
+
+    /// Constructs read-only summary of the consignment.
+    pub fn summary(&self) -> ConsignmentSummary {
+        ConsignmentSummary {
+            consignment_id: self.consignment_id(),
+            version: self.version,
+            transfer: self.transfer,
+            contract_id: self.contract_id(),
+            schema_id: self.schema_id(),
+            ifaces: self.ifaces.keys().map(|iface| iface.name.clone()).collect(),
+            terminals: self
+                .terminals
+                .iter()
+                .map(|(bundle_id, seal)| (*bundle_id, *seal))
+                .collect(),
+            operations: 1
+                + self.extensions.len()
+                + self
+                    .bundles
+                    .iter()
+                    .map(|wb| wb.known_transitions().count())
+                    .sum::<usize>(),
+            witnesses: self
+                .bundles
+                .iter()
+                .map(|wb| wb.pub_witness.to_witness_id())
+                .collect(),
+        }
+    }
+
     #[inline]
     pub fn schema_id(&self) -> SchemaId { self.schema.schema_id() }
 
@@ -322,6 +771,37 @@ impl<const TRANSFER: bool> Consignment<TRANSFER> {
         }
     }
 
+    /// Validates consignment without access to the blockchain, as needed for
+    /// air-gapped signers.
+    ///
+    /// Witness transactions are taken from the consignment itself, thus it
+    /// must contain full transactions for all witnesses. Position of each
+    /// witness in the blockchain is verified with its SPV proof against the
+    /// header chain provided by `headers`. Witnesses without a proof, or with
+    /// a proof not matching the header chain, are reported as unresolved.
+    pub fn validate_offline(
+        self,
+        proofs: &BTreeMap<Txid, SpvProof>,
+        headers: &impl HeaderSource,
+        testnet: bool,
+    ) -> Result<ValidConsignment<TRANSFER>, (validation::Status, Consignment<TRANSFER>)> {
+        let txes = self
+            .bundles
+            .iter()
+            .filter_map(|wb| {
+                let layer1 = wb.pub_witness.layer1();
+                let tx = wb.pub_witness.as_reduced_unsafe().tx()?.clone();
+                Some((wb.witness_id(), XChain::with(layer1, tx)))
+            })
+            .collect();
+        let resolver = OfflineResolver {
+            txes,
+            proofs,
+            headers,
+        };
+        self.validate(&resolver, testnet)
+    }
+
     pub fn validate(
         self,
         resolver: &impl ResolveWitness,
@@ -378,6 +858,41 @@ impl<const TRANSFER: bool> Consignment<TRANSFER> {
     }
 }
 
+/// Witness resolver used by [`Consignment::validate_offline`].
+struct OfflineResolver<'a, H: HeaderSource> {
+    txes: BTreeMap<XWitnessId, XWitnessTx>,
+    proofs: &'a BTreeMap<Txid, SpvProof>,
+    headers: &'a H,
+}
+
+impl<H: HeaderSource> ResolveWitness for OfflineResolver<'_, H> {
+    fn resolve_pub_witness(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<XWitnessTx, WitnessResolverError> {
+        self.txes
+            .get(&witness_id)
+            .cloned()
+            .ok_or(WitnessResolverError::Unknown(witness_id))
+    }
+
+    fn resolve_pub_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        let XChain::Bitcoin(txid) = witness_id else {
+            return Err(WitnessResolverError::Unknown(witness_id));
+        };
+        let proof = self
+            .proofs
+            .get(&txid)
+            .ok_or(WitnessResolverError::Unknown(witness_id))?;
+        proof
+            .verify(txid, self.headers)
+            .map_err(|err| WitnessResolverError::Other(witness_id, err.to_string()))
+    }
+}
+
 impl<const TRANSFER: bool> StrictArmor for Consignment<TRANSFER> {
     type Id = ConsignmentId;
     const PLATE_TITLE: &'static str = "RGB CONSIGNMENT";
@@ -448,8 +963,24 @@ impl<const TRANSFER: bool> FromStr for Consignment<TRANSFER> {
 
 #[cfg(test)]
 mod test {
+    use commit_verify::Conceal;
+
     use super::*;
 
+    fn transfer() -> Transfer {
+        Transfer::from_str(include_str!("../../asset/armored_transfer.default")).unwrap()
+    }
+
+    fn terminal() -> (BundleId, XChain<SecretSeal>) {
+        let bundle_id = BundleId::from_byte_array([0xAA; 32]);
+        let seal = XChain::Bitcoin(GraphSeal::new_random_vout(
+            bp::dbc::Method::OpretFirst,
+            bp::Vout::from_u32(0),
+        ))
+        .conceal();
+        (bundle_id, seal)
+    }
+
     #[test]
     fn contract_str_round_trip() {
         let s = include_str!("../../asset/armored_contract.default");
@@ -507,6 +1038,261 @@ Check-SHA256: aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
         assert_eq!(transfer.to_string(), s.replace('\r', ""), "transfer string round trip fails");
     }
 
+    #[test]
+    fn transfer_summary() {
+        let s = include_str!("../../asset/armored_transfer.default");
+        let transfer = Transfer::from_str(s).unwrap();
+        let summary = transfer.summary();
+        assert!(summary.transfer);
+        assert_eq!(summary.contract_id, transfer.contract_id());
+        assert_eq!(summary.consignment_id, transfer.consignment_id());
+        assert_eq!(summary.operations, 1);
+        assert!(summary.witnesses.is_empty());
+
+        let mut data = Vec::new();
+        transfer.save(&mut data).unwrap();
+        assert_eq!(Transfer::parse(data.as_slice()).unwrap(), summary);
+    }
+
+    #[test]
+    fn topological_sort() {
+        let [a, b, c, d] = [1u8, 2, 3, 4].map(|n| OpId::from([n; 32]));
+        let ops = [(d, vec![b, c]), (c, vec![a]), (b, vec![a]), (a, vec![])];
+        let order = topological_order(ops.clone()).unwrap();
+        assert_eq!(order, vec![a, b, c, d]);
+
+        let mut reversed = ops.to_vec();
+        reversed.reverse();
+        assert_eq!(topological_order(reversed).unwrap(), order);
+
+        // Dependencies outside of the set are ignored
+        let external = OpId::from([0xFF; 32]);
+        assert_eq!(topological_order([(d, vec![external]), (c, vec![d])]).unwrap(), vec![d, c]);
+
+        assert_eq!(topological_order([(a, vec![b]), (b, vec![a])]), Err(CyclicDependency(a)));
+    }
+
+    #[test]
+    fn timestamp_bounds() {
+        let now = RGB_EPOCH + 1_000_000;
+        assert_eq!(check_timestamp(RGB_EPOCH, now), Ok(()));
+        assert_eq!(check_timestamp(now, now), Ok(()));
+        assert_eq!(check_timestamp(now + TIMESTAMP_MAX_DRIFT, now), Ok(()));
+        assert_eq!(
+            check_timestamp(RGB_EPOCH - 1, now),
+            Err(TimestampError::BeforeEpoch(RGB_EPOCH - 1))
+        );
+        assert_eq!(check_timestamp(0, now), Err(TimestampError::BeforeEpoch(0)));
+        assert_eq!(
+            check_timestamp(now + TIMESTAMP_MAX_DRIFT + 1, now),
+            Err(TimestampError::Future(now + TIMESTAMP_MAX_DRIFT + 1))
+        );
+    }
+
+    #[test]
+    fn transfer_operation_order() {
+        let s = include_str!("../../asset/armored_transfer.default");
+        let transfer = Transfer::from_str(s).unwrap();
+        assert_eq!(transfer.operation_order(), vec![transfer.genesis.id()]);
+    }
+
+    // Transition spending the first output of each of the `parents`.
+    fn transition(nonce: u64, parents: &[OpId]) -> Transition {
+        use rgb::{AssignmentType, Input, Inputs, Opout};
+
+        let mut transition = Transition::strict_dumb();
+        transition.nonce = nonce;
+        transition.inputs = Inputs::from_inner(Confined::from_iter_checked(
+            parents
+                .iter()
+                .map(|op| Input::with(Opout::new(*op, AssignmentType::with(1), 0))),
+        ));
+        transition
+    }
+
+    fn witness_bundle(witness: u8, transitions: Vec<Transition>) -> WitnessBundle {
+        use bp::dbc::opret::OpretProof;
+        use commit_verify::mpc;
+        use rgb::validation::DbcProof;
+        use rgb::TransitionBundle;
+
+        use crate::containers::{ClientBundle, PubWitness};
+
+        let mut bundle = TransitionBundle::strict_dumb();
+        bundle.known_transitions =
+            Confined::from_iter_checked(transitions.into_iter().map(|t| (t.id(), t)));
+        WitnessBundle::with(
+            XChain::Bitcoin(PubWitness::new(Txid::from([witness; 32]))),
+            ClientBundle::new(
+                mpc::MerkleProof::default(),
+                DbcProof::Opret(OpretProof::default()),
+                bundle,
+            ),
+        )
+    }
+
+    #[test]
+    fn transfer_sort_bundles() {
+        let first = transition(1, &[]);
+        let second = transition(2, &[first.id()]);
+        let third = transition(3, &[second.id()]);
+        let opids = [first.id(), second.id(), third.id()];
+
+        let mut transfer = transfer();
+        transfer.bundles = Confined::from_checked(vec![
+            witness_bundle(1, vec![third]),
+            witness_bundle(2, vec![second]),
+            witness_bundle(3, vec![first]),
+        ]);
+        let order = transfer.operation_order();
+        assert_eq!(order.len(), 4);
+        assert!(order.contains(&transfer.genesis.id()));
+        let position = |opid| order.iter().position(|id| *id == opid).unwrap();
+        assert!(position(opids[0]) < position(opids[1]));
+        assert!(position(opids[1]) < position(opids[2]));
+
+        transfer.sort_bundles().unwrap();
+        let witnesses = transfer
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+        assert_eq!(witnesses, [3u8, 2, 1].map(|n| XChain::Bitcoin(Txid::from([n; 32]))));
+        // Sorting doesn't depend on the original order
+        let sorted = transfer.bundles.clone();
+        transfer.bundles = Confined::from_iter_checked(sorted.iter().rev().cloned());
+        transfer.sort_bundles().unwrap();
+        assert_eq!(transfer.bundles, sorted);
+
+        // Bundles depending on each other can't be ordered
+        let first = transition(4, &[]);
+        let second = transition(5, &[first.id()]);
+        let third = transition(6, &[second.id()]);
+        transfer.bundles = Confined::from_checked(vec![
+            witness_bundle(1, vec![first, third]),
+            witness_bundle(2, vec![second]),
+        ]);
+        assert!(transfer.sort_bundles().is_err());
+    }
+
+    #[test]
+    fn transfer_canonical_id() {
+        let transfer = transfer();
+        let data = transfer
+            .to_strict_serialized::<{ u32::MAX as usize }>()
+            .unwrap();
+        let decoded =
+            Transfer::from_strict_serialized::<{ u32::MAX as usize }>(data.clone()).unwrap();
+        assert_eq!(decoded.consignment_id(), transfer.consignment_id());
+        assert_eq!(
+            decoded
+                .to_strict_serialized::<{ u32::MAX as usize }>()
+                .unwrap(),
+            data
+        );
+
+        let mut other = transfer.clone();
+        let (bundle_id, seal) = terminal();
+        other.terminals.insert(bundle_id, seal).unwrap();
+        assert_ne!(other.consignment_id(), transfer.consignment_id());
+    }
+
+    #[test]
+    fn transfer_verify_terminals() {
+        let mut transfer = transfer();
+        transfer.terminals = none!();
+        assert_eq!(transfer.verify_terminals(), Ok(()));
+
+        let (bundle_id, seal) = terminal();
+        transfer.terminals.insert(bundle_id, seal).unwrap();
+        assert_eq!(transfer.verify_terminals(), Err(TerminalError::BundleAbsent(bundle_id)));
+    }
+
+    #[test]
+    fn transfer_verify_terminals_seal_absent() {
+        let mut transfer = transfer();
+        transfer.terminals = none!();
+
+        // The bundle is present, but its only transition assigns no state
+        let bundle = WitnessBundle::strict_dumb();
+        let bundle_id = bundle
+            .anchored_bundles
+            .bundles()
+            .next()
+            .unwrap()
+            .bundle_id();
+        transfer.bundles.push(bundle).unwrap();
+
+        let (_, seal) = terminal();
+        transfer.terminals.insert(bundle_id, seal).unwrap();
+        assert_eq!(transfer.verify_terminals(), Err(TerminalError::SealAbsent(bundle_id, seal)));
+    }
+
+    #[test]
+    fn transfer_diff() {
+        let transfer = transfer();
+        let diff = transfer.diff(&transfer);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "");
+
+        let mut other = transfer.clone();
+        let (bundle_id, seal) = terminal();
+        other.terminals.insert(bundle_id, seal).unwrap();
+        let diff = transfer.diff(&other);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.terminals.get(&bundle_id), Some(&(None, Some(seal))));
+        assert!(diff.left_operations.is_empty() && diff.right_operations.is_empty());
+    }
+
+    #[test]
+    fn offline_witness_ord() {
+        use std::num::NonZeroU32;
+
+        use bp::{BlockHash, BlockHeader};
+        use rgb::vm::WitnessPos;
+
+        // Block with a single transaction, which id is the block merkle root
+        let txid = Txid::from_byte_array([0x11; 32]);
+        let header = BlockHeader {
+            version: 0x20000000,
+            prev_block_hash: BlockHash::from_byte_array([0xEE; 32]),
+            merkle_root: txid.to_byte_array().into(),
+            time: 1_713_571_767,
+            bits: 0x17034219,
+            nonce: 0,
+        };
+        let height = NonZeroU32::new(840_000).unwrap();
+        let headers = bmap! { header.block_hash() => height };
+        let proofs = bmap! { txid => SpvProof { header, pos: 0, path: vec![] } };
+
+        let resolver = OfflineResolver {
+            txes: none!(),
+            proofs: &proofs,
+            headers: &headers,
+        };
+        let pos = WitnessPos::bitcoin(height, header.time as i64).unwrap();
+        assert_eq!(
+            resolver.resolve_pub_witness_ord(XChain::Bitcoin(txid)),
+            Ok(WitnessOrd::Mined(pos))
+        );
+
+        let unknown = XChain::Bitcoin(Txid::from_byte_array([0x22; 32]));
+        assert_eq!(
+            resolver.resolve_pub_witness_ord(unknown),
+            Err(WitnessResolverError::Unknown(unknown))
+        );
+
+        let resolver = OfflineResolver {
+            txes: none!(),
+            proofs: &proofs,
+            headers: &BTreeMap::<BlockHash, NonZeroU32>::new(),
+        };
+        assert!(matches!(
+            resolver.resolve_pub_witness_ord(XChain::Bitcoin(txid)),
+            Err(WitnessResolverError::Other(..))
+        ));
+    }
+
     #[test]
     fn error_transfer_strs() {
         let s = include_str!("../../asset/armored_transfer.default");
@@ -19,18 +19,39 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeSet;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::io::{self, Read, Write};
+use std::str::FromStr;
 
-use amplify::confinement::U32 as FILE_MAX_LEN;
+use amplify::confinement::{MediumBlob, SmallVec, U24, U32 as FILE_MAX_LEN};
 use armor::{AsciiArmor, StrictArmor};
+use commit_verify::{DigestExt, Sha256};
 use strict_encoding::{StreamReader, StreamWriter, StrictDecode, StrictEncode};
 
-use crate::containers::{Contract, Kit, Transfer};
+use crate::containers::{ConsignmentParseError, Contract, Kit, Transfer};
+use crate::LIB_NAME_RGB_STD;
 
 const RGB_PREFIX: [u8; 4] = *b"RGB\x00";
 const MAGIC_LEN: usize = 3;
 
+/// Magic bytes used by multi-contract transfer files.
+pub const MULTI_TRANSFER_MAGIC: [u8; MAGIC_LEN] = *b"TFM";
+/// Version of the multi-contract transfer file format, which follows the magic
+/// bytes.
+pub const MULTI_TRANSFER_VER: u8 = 1;
+/// Flag byte following the magic bytes in files with deflate-compressed
+/// content. All containers start with a version byte, which is much lower, so
+/// the flag never clashes with uncompressed data.
+pub const DEFLATE_FLAG: u8 = 0xDF;
+/// Magic bytes used by files containing a single chunk of a chunked container.
+pub const CHUNK_MAGIC: [u8; MAGIC_LEN] = *b"CHK";
+/// Magic bytes used by manifest files of chunked containers.
+pub const CHUNK_MANIFEST_MAGIC: [u8; MAGIC_LEN] = *b"CMF";
+/// Version of the chunk and chunk manifest file formats, which follows the
+/// magic bytes.
+pub const CHUNK_VER: u8 = 1;
+
 #[derive(Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum LoadError {
@@ -45,6 +66,64 @@ pub enum LoadError {
     #[display(inner)]
     #[from]
     Armor(armor::StrictArmorError),
+
+    /// unsupported version {0} of the file format.
+    UnsupportedVersion(u8),
+
+    /// file content is compressed, but compression support is not enabled.
+    CompressionUnsupported,
+
+    /// chunk {0} is absent.
+    ChunkMissing(u16),
+
+    /// chunk {0} doesn't belong to the chunked file.
+    ChunkForeign(u16),
+
+    /// checksum of chunk {0} doesn't match the one in the manifest.
+    ChunkChecksum(u16),
+
+    /// reassembled data don't match the checksum in the chunk manifest.
+    ManifestChecksum,
+}
+
+fn read_magic(data: &mut impl Read, expected: [u8; MAGIC_LEN]) -> Result<(), LoadError> {
+    let mut rgb = [0u8; 4];
+    let mut magic = [0u8; MAGIC_LEN];
+    data.read_exact(&mut rgb)?;
+    data.read_exact(&mut magic)?;
+    if rgb != RGB_PREFIX || magic != expected {
+        return Err(LoadError::InvalidMagic);
+    }
+    Ok(())
+}
+
+fn read_ver(data: &mut impl Read, expected: u8) -> Result<(), LoadError> {
+    let mut ver = [0u8; 1];
+    data.read_exact(&mut ver)?;
+    if ver[0] != expected {
+        return Err(LoadError::UnsupportedVersion(ver[0]));
+    }
+    Ok(())
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut engine = Sha256::default();
+    engine.input_raw(data);
+    engine.finish()
+}
+
+/// Returns reader for the file content following the magic bytes, which
+/// decompresses the content if it is prefixed with [`DEFLATE_FLAG`].
+fn content_reader<'r>(mut data: impl Read + 'r) -> Result<Box<dyn Read + 'r>, LoadError> {
+    let mut flag = [0u8; 1];
+    data.read_exact(&mut flag)?;
+    if flag[0] != DEFLATE_FLAG {
+        return Ok(Box::new(io::Cursor::new(flag).chain(data)));
+    }
+    #[cfg(feature = "compression")]
+    return Ok(Box::new(flate2::read::DeflateDecoder::new(data)));
+    #[cfg(not(feature = "compression"))]
+    Err(LoadError::CompressionUnsupported)
 }
 
 pub trait FileContent: StrictArmor {
@@ -60,12 +139,24 @@ pub trait FileContent: StrictArmor {
             return Err(LoadError::InvalidMagic);
         }
 
-        let reader = StreamReader::new::<FILE_MAX_LEN>(data);
+        let reader = StreamReader::new::<FILE_MAX_LEN>(content_reader(data)?);
         let me = Self::strict_read(reader)?;
 
         Ok(me)
     }
 
+    /// Loads the container from an asynchronous reader (like a network socket)
+    /// without blocking the thread while the data are arriving.
+    ///
+    /// Strict decoding is synchronous, thus the data are buffered in memory (up
+    /// to the maximal container size) and decoded once all of them arrive.
+    #[cfg(feature = "async")]
+    fn load_async(
+        data: impl tokio::io::AsyncRead + Unpin + Send,
+    ) -> impl std::future::Future<Output = Result<Self, LoadError>> + Send {
+        async move { Self::load(read_async(data).await?.as_slice()) }
+    }
+
     fn save(&self, mut writer: impl Write) -> Result<(), io::Error> {
         writer.write_all(&RGB_PREFIX)?;
         writer.write_all(&Self::MAGIC)?;
@@ -76,6 +167,45 @@ pub trait FileContent: StrictArmor {
         Ok(())
     }
 
+    /// Saves the container compressing its content with deflate. Files
+    /// produced by this method are read by [`FileContent::load`] when the
+    /// `compression` feature is enabled.
+    #[cfg(feature = "compression")]
+    fn save_compressed(&self, mut writer: impl Write) -> Result<(), io::Error> {
+        writer.write_all(&RGB_PREFIX)?;
+        writer.write_all(&Self::MAGIC)?;
+        writer.write_all(&[DEFLATE_FLAG])?;
+
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(writer, flate2::Compression::default());
+        let stream = StreamWriter::new::<FILE_MAX_LEN>(&mut encoder);
+        self.strict_write(stream)?;
+        encoder.finish()?;
+
+        Ok(())
+    }
+
+    /// Saves the container split into numbered chunks of at most `chunk_size`
+    /// bytes each, for the transports limiting the size of a single message.
+    /// The returned manifest is required to reassemble the chunks with
+    /// [`FileContent::load_chunked`].
+    fn save_chunked(&self, chunk_size: usize) -> Result<(ChunkManifest, Vec<Chunk>), io::Error> {
+        let mut data = vec![];
+        self.save(&mut data)?;
+        ChunkManifest::split(&data, chunk_size)
+    }
+
+    /// Loads the container from chunks produced by [`FileContent::save_chunked`],
+    /// which may come in any order. Each chunk is verified against the
+    /// manifest before the data are decoded.
+    fn load_chunked(
+        manifest: &ChunkManifest,
+        chunks: impl IntoIterator<Item = Chunk>,
+    ) -> Result<Self, LoadError> {
+        let data = manifest.assemble(chunks)?;
+        Self::load(data.as_slice())
+    }
+
     #[cfg(feature = "fs")]
     fn load_file(path: impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
         let file = std::fs::File::open(path)?;
@@ -116,6 +246,286 @@ impl FileContent for Transfer {
 // TODO: Add disclosure
 // TODO: Add batch and fascia
 
+/// Transfers of multiple contracts (for instance, several assets allocated to
+/// the same UTXO) shipped to a beneficiary as a single file.
+#[derive(Wrapper, WrapperMut, Clone, PartialEq, Eq, Debug, Default, From)]
+#[wrapper(Deref)]
+#[wrapper_mut(DerefMut)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct MultiTransfer(Vec<Transfer>);
+
+impl FromIterator<Transfer> for MultiTransfer {
+    fn from_iter<T: IntoIterator<Item = Transfer>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for MultiTransfer {
+    type Item = Transfer;
+    type IntoIter = std::vec::IntoIter<Transfer>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.into_iter() }
+}
+
+impl MultiTransfer {
+    pub fn load(mut data: impl Read) -> Result<Self, LoadError> {
+        read_magic(&mut data, MULTI_TRANSFER_MAGIC)?;
+        Self::load_content(data)
+    }
+
+    fn load_content(mut data: impl Read) -> Result<Self, LoadError> {
+        read_ver(&mut data, MULTI_TRANSFER_VER)?;
+        let reader = StreamReader::new::<FILE_MAX_LEN>(data);
+        let transfers = SmallVec::<Transfer>::strict_read(reader)?;
+        Ok(Self(transfers.release()))
+    }
+
+    pub fn save(&self, mut writer: impl Write) -> Result<(), io::Error> {
+        writer.write_all(&RGB_PREFIX)?;
+        writer.write_all(&MULTI_TRANSFER_MAGIC)?;
+        self.save_content(writer)
+    }
+
+    fn save_content(&self, mut writer: impl Write) -> Result<(), io::Error> {
+        let transfers = SmallVec::try_from(self.0.clone()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "too many transfers in a single file")
+        })?;
+        writer.write_all(&[MULTI_TRANSFER_VER])?;
+        let writer = StreamWriter::new::<FILE_MAX_LEN>(writer);
+        transfers.strict_write(writer)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn load_file(path: impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
+        let file = std::fs::File::open(path)?;
+        Self::load(file)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), io::Error> {
+        let file = std::fs::File::create(path)?;
+        self.save(file)
+    }
+}
+
+/// Displays the transfers as a sequence of ASCII-armored consignments, which
+/// can be parsed back with [`MultiTransfer::from_str`].
+impl Display for MultiTransfer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for transfer in &self.0 {
+            Display::fmt(&transfer.display_ascii_armored(), f)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MultiTransfer {
+    type Err = ConsignmentParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let last = format!("-----END {}-----", <Transfer as StrictArmor>::PLATE_TITLE);
+        let mut transfers = vec![];
+        let mut armored = String::new();
+        for line in s.lines() {
+            armored.push_str(line);
+            armored.push('\n');
+            if line == last {
+                transfers.push(Transfer::from_str(&armored)?);
+                armored.clear();
+            }
+        }
+        if !armored.trim().is_empty() {
+            transfers.push(Transfer::from_str(&armored)?);
+        }
+        Ok(Self(transfers))
+    }
+}
+
+/// Manifest of a container split into chunks, listing checksums of each of
+/// the chunks.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STD)]
+pub struct ChunkManifest {
+    /// SHA256 hash of the whole container data.
+    pub checksum: [u8; 32],
+    /// Length of the whole container data, in bytes.
+    pub len: u64,
+    /// SHA256 hashes of the chunks, in order.
+    pub chunks: SmallVec<[u8; 32]>,
+}
+
+/// Numbered chunk of a container split with [`FileContent::save_chunked`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STD)]
+pub struct Chunk {
+    /// SHA256 hash of the whole container data, linking the chunk to its
+    /// manifest.
+    pub checksum: [u8; 32],
+    pub index: u16,
+    pub count: u16,
+    pub data: MediumBlob,
+}
+
+impl ChunkManifest {
+    /// Splits data into chunks of at most `chunk_size` bytes each.
+    pub fn split(data: &[u8], chunk_size: usize) -> Result<(Self, Vec<Chunk>), io::Error> {
+        if chunk_size == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "zero chunk size"));
+        }
+        let checksum = sha256(data);
+        let parts = data.chunks(chunk_size.min(U24)).collect::<Vec<_>>();
+        let hashes =
+            SmallVec::try_from_iter(parts.iter().map(|part| sha256(part))).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "too many chunks for a single file")
+            })?;
+        let manifest = ChunkManifest {
+            checksum,
+            len: data.len() as u64,
+            chunks: hashes,
+        };
+        let count = manifest.count();
+        let chunks = parts
+            .into_iter()
+            .enumerate()
+            .map(|(index, part)| Chunk {
+                checksum,
+                index: index as u16,
+                count,
+                data: MediumBlob::from_checked(part.to_vec()),
+            })
+            .collect();
+        Ok((manifest, chunks))
+    }
+
+    pub fn count(&self) -> u16 { self.chunks.len() as u16 }
+
+    /// Returns indexes of the chunks which are not yet present in `chunks`.
+    pub fn missing<'c>(&self, chunks: impl IntoIterator<Item = &'c Chunk>) -> BTreeSet<u16> {
+        let mut missing = (0..self.count()).collect::<BTreeSet<_>>();
+        for chunk in chunks {
+            if chunk.checksum == self.checksum {
+                missing.remove(&chunk.index);
+            }
+        }
+        missing
+    }
+
+    /// Verifies chunks against the manifest and reassembles them into the
+    /// original data. Chunks may come in any order and may repeat.
+    ///
+    /// The data length declared in the manifest is not trusted: it must not
+    /// exceed the maximal container size and must match the total length of
+    /// the verified chunks before any memory for the data is allocated.
+    pub fn assemble(&self, chunks: impl IntoIterator<Item = Chunk>) -> Result<Vec<u8>, LoadError> {
+        let mut parts = vec![None; self.chunks.len()];
+        for chunk in chunks {
+            if chunk.checksum != self.checksum
+                || chunk.count != self.count()
+                || chunk.index >= self.count()
+            {
+                return Err(LoadError::ChunkForeign(chunk.index));
+            }
+            if sha256(&chunk.data) != self.chunks[chunk.index as usize] {
+                return Err(LoadError::ChunkChecksum(chunk.index));
+            }
+            parts[chunk.index as usize] = Some(chunk.data);
+        }
+        let parts = parts
+            .into_iter()
+            .enumerate()
+            .map(|(index, part)| part.ok_or(LoadError::ChunkMissing(index as u16)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = parts.iter().map(|part| part.len() as u64).sum::<u64>();
+        if self.len > FILE_MAX_LEN as u64 || len != self.len {
+            return Err(LoadError::ManifestChecksum);
+        }
+        let mut data = Vec::with_capacity(len as usize);
+        for part in parts {
+            data.extend(part.release());
+        }
+        if sha256(&data) != self.checksum {
+            return Err(LoadError::ManifestChecksum);
+        }
+        Ok(data)
+    }
+
+    pub fn load(mut data: impl Read) -> Result<Self, LoadError> {
+        read_magic(&mut data, CHUNK_MANIFEST_MAGIC)?;
+        read_ver(&mut data, CHUNK_VER)?;
+        let reader = StreamReader::new::<FILE_MAX_LEN>(data);
+        Ok(Self::strict_read(reader)?)
+    }
+
+    pub fn save(&self, mut writer: impl Write) -> Result<(), io::Error> {
+        writer.write_all(&RGB_PREFIX)?;
+        writer.write_all(&CHUNK_MANIFEST_MAGIC)?;
+        writer.write_all(&[CHUNK_VER])?;
+        let writer = StreamWriter::new::<FILE_MAX_LEN>(writer);
+        self.strict_write(writer)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn load_file(path: impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
+        let file = std::fs::File::open(path)?;
+        Self::load(file)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), io::Error> {
+        let file = std::fs::File::create(path)?;
+        self.save(file)
+    }
+}
+
+impl Chunk {
+    pub fn load(mut data: impl Read) -> Result<Self, LoadError> {
+        read_magic(&mut data, CHUNK_MAGIC)?;
+        read_ver(&mut data, CHUNK_VER)?;
+        let reader = StreamReader::new::<FILE_MAX_LEN>(data);
+        Ok(Self::strict_read(reader)?)
+    }
+
+    pub fn save(&self, mut writer: impl Write) -> Result<(), io::Error> {
+        writer.write_all(&RGB_PREFIX)?;
+        writer.write_all(&CHUNK_MAGIC)?;
+        writer.write_all(&[CHUNK_VER])?;
+        let writer = StreamWriter::new::<FILE_MAX_LEN>(writer);
+        self.strict_write(writer)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn load_file(path: impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
+        let file = std::fs::File::open(path)?;
+        Self::load(file)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), io::Error> {
+        let file = std::fs::File::create(path)?;
+        self.save(file)
+    }
+}
+
+#[cfg(feature = "async")]
+async fn read_async(data: impl tokio::io::AsyncRead + Unpin) -> Result<Vec<u8>, io::Error> {
+    use tokio::io::AsyncReadExt;
+
+    let max_len = (RGB_PREFIX.len() + MAGIC_LEN) as u64 + FILE_MAX_LEN as u64;
+    let mut buf = Vec::new();
+    data.take(max_len + 1).read_to_end(&mut buf).await?;
+    if buf.len() as u64 > max_len {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "container data are too large"));
+    }
+    Ok(buf)
+}
+
 #[derive(Clone, Debug, From)]
 #[cfg_attr(
     feature = "serde",
@@ -131,6 +541,9 @@ pub enum UniversalFile {
 
     #[from]
     Transfer(Transfer),
+
+    #[from]
+    MultiTransfer(MultiTransfer),
     // TODO: Add disclosure
     // TODO: Add batch and fascia
 }
@@ -144,7 +557,10 @@ impl UniversalFile {
         if rgb != RGB_PREFIX {
             return Err(LoadError::InvalidMagic);
         }
-        let mut reader = StreamReader::new::<FILE_MAX_LEN>(data);
+        if magic == MULTI_TRANSFER_MAGIC {
+            return MultiTransfer::load_content(data).map(Self::from);
+        }
+        let mut reader = StreamReader::new::<FILE_MAX_LEN>(content_reader(data)?);
         Ok(match magic {
             x if x == Kit::MAGIC => Kit::strict_read(&mut reader)?.into(),
             x if x == Contract::MAGIC => Contract::strict_read(&mut reader)?.into(),
@@ -153,21 +569,19 @@ impl UniversalFile {
         })
     }
 
-    pub fn save(&self, mut writer: impl Write) -> Result<(), io::Error> {
-        writer.write_all(&RGB_PREFIX)?;
-        let magic = match self {
-            UniversalFile::Kit(_) => Kit::MAGIC,
-            UniversalFile::Contract(_) => Contract::MAGIC,
-            UniversalFile::Transfer(_) => Transfer::MAGIC,
-        };
-        writer.write_all(&magic)?;
-
-        let writer = StreamWriter::new::<FILE_MAX_LEN>(writer);
+    /// Loads the container from an asynchronous reader (like a network socket)
+    /// without blocking the thread while the data are arriving.
+    #[cfg(feature = "async")]
+    pub async fn load_async(data: impl tokio::io::AsyncRead + Unpin) -> Result<Self, LoadError> {
+        Self::load(read_async(data).await?.as_slice())
+    }
 
+    pub fn save(&self, writer: impl Write) -> Result<(), io::Error> {
         match self {
-            UniversalFile::Kit(content) => content.strict_write(writer),
-            UniversalFile::Contract(content) => content.strict_write(writer),
-            UniversalFile::Transfer(content) => content.strict_write(writer),
+            UniversalFile::Kit(content) => content.save(writer),
+            UniversalFile::Contract(content) => content.save(writer),
+            UniversalFile::Transfer(content) => content.save(writer),
+            UniversalFile::MultiTransfer(content) => content.save(writer),
         }
     }
 
@@ -190,6 +604,7 @@ impl Display for UniversalFile {
             UniversalFile::Kit(content) => Display::fmt(&content.display_ascii_armored(), f),
             UniversalFile::Contract(content) => Display::fmt(&content.display_ascii_armored(), f),
             UniversalFile::Transfer(content) => Display::fmt(&content.display_ascii_armored(), f),
+            UniversalFile::MultiTransfer(content) => Display::fmt(content, f),
         }
     }
 }
@@ -422,6 +837,75 @@ mod test {
         assert_eq!(&transfer, &default_transfer, "transfer roudtrip does not work");
     }
 
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compressed_transfer_save_load_round_trip() {
+        let transfer = almost_default_transfer();
+        let mut plain = vec![];
+        transfer.save(&mut plain).expect("fail to save transfer");
+        let mut compressed = vec![];
+        transfer
+            .save_compressed(&mut compressed)
+            .expect("fail to save compressed transfer");
+        assert_eq!(compressed[7], DEFLATE_FLAG);
+
+        let loaded = Transfer::load(compressed.as_slice()).expect("fail to load compressed");
+        assert_eq!(loaded, transfer, "compressed transfer roundtrip does not work");
+        let loaded = Transfer::load(plain.as_slice()).expect("fail to load uncompressed");
+        assert_eq!(loaded, transfer, "uncompressed transfer roundtrip does not work");
+    }
+
+    #[test]
+    fn chunked_transfer_save_load_round_trip() {
+        let transfer = almost_default_transfer();
+        let (manifest, mut chunks) = transfer.save_chunked(64).expect("fail to split transfer");
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.data.len() <= 64));
+
+        let mut data = vec![];
+        manifest.save(&mut data).unwrap();
+        let manifest = ChunkManifest::load(data.as_slice()).expect("fail to load manifest");
+        chunks = chunks
+            .into_iter()
+            .map(|chunk| {
+                let mut data = vec![];
+                chunk.save(&mut data).unwrap();
+                Chunk::load(data.as_slice()).expect("fail to load chunk")
+            })
+            .collect();
+
+        chunks.reverse();
+        let last = chunks.pop().unwrap();
+        assert_eq!(manifest.missing(&chunks), bset![0]);
+        assert!(matches!(
+            Transfer::load_chunked(&manifest, chunks.clone()),
+            Err(LoadError::ChunkMissing(0))
+        ));
+        let mut corrupted = last.clone();
+        corrupted.data[0] ^= 0xFF;
+        assert!(matches!(
+            Transfer::load_chunked(&manifest, [corrupted]),
+            Err(LoadError::ChunkChecksum(0))
+        ));
+
+        chunks.push(last);
+        let loaded = Transfer::load_chunked(&manifest, chunks).expect("fail to reassemble");
+        assert_eq!(loaded, transfer, "chunked transfer roundtrip does not work");
+    }
+
+    #[test]
+    fn chunk_manifest_untrusted_len() {
+        let data = vec![0xAAu8; 100];
+        let (mut manifest, chunks) = ChunkManifest::split(&data, 32).unwrap();
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(manifest.assemble(chunks.clone()).unwrap(), data);
+
+        manifest.len = u64::MAX;
+        assert!(matches!(manifest.assemble(chunks.clone()), Err(LoadError::ManifestChecksum)));
+        manifest.len = FILE_MAX_LEN as u64;
+        assert!(matches!(manifest.assemble(chunks), Err(LoadError::ManifestChecksum)));
+    }
+
     #[cfg(feature = "fs")]
     #[test]
     fn armored_transfer_save_load_round_trip() {
@@ -439,4 +923,72 @@ mod test {
             Transfer::load_armored(ARMORED_TRANSFER_PATH).expect("fail to export armored transfer");
         assert_eq!(transfer, default_transfer, "armored transfer roudtrip does not work");
     }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn load_async() {
+        use crate::interface::resolver::block_on;
+
+        fn assert_send<T: Send>(val: T) -> T { val }
+
+        let transfer = almost_default_transfer();
+        let mut data = Vec::new();
+        transfer.save(&mut data).expect("fail to save transfer");
+        let loaded = block_on(assert_send(Transfer::load_async(data.as_slice())))
+            .expect("fail to load transfer");
+        assert_eq!(loaded, transfer);
+
+        let UniversalFile::Transfer(loaded) =
+            block_on(assert_send(UniversalFile::load_async(data.as_slice())))
+                .expect("fail to load universal file")
+        else {
+            panic!("transfer is not recognized as such");
+        };
+        assert_eq!(loaded, transfer);
+    }
+
+    #[test]
+    fn multi_transfer_save_load_round_trip() {
+        let multi =
+            MultiTransfer::from_iter([almost_default_transfer(), almost_default_transfer()]);
+
+        let mut data = Vec::new();
+        multi.save(&mut data).expect("fail to save multi-transfer");
+        let loaded = MultiTransfer::load(data.as_slice()).expect("fail to load multi-transfer");
+        assert_eq!(loaded, multi, "multi-transfer roundtrip does not work");
+
+        let UniversalFile::MultiTransfer(loaded) =
+            UniversalFile::load(data.as_slice()).expect("fail to load universal file")
+        else {
+            panic!("multi-transfer is not recognized as such");
+        };
+        assert_eq!(loaded, multi);
+
+        let mut saved = Vec::new();
+        UniversalFile::from(multi)
+            .save(&mut saved)
+            .expect("fail to save universal file");
+        assert_eq!(saved, data, "universal file encodes multi-transfer differently");
+
+        data[RGB_PREFIX.len() + MAGIC_LEN] = MULTI_TRANSFER_VER + 1;
+        assert!(matches!(
+            MultiTransfer::load(data.as_slice()),
+            Err(LoadError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn multi_transfer_armor_round_trip() {
+        let multi =
+            MultiTransfer::from_iter([almost_default_transfer(), almost_default_transfer()]);
+        let armored = multi.to_string();
+        assert_eq!(armored, UniversalFile::from(multi.clone()).to_string());
+        assert_eq!(MultiTransfer::from_str(&armored).unwrap(), multi);
+
+        assert_eq!(MultiTransfer::from_str("").unwrap(), MultiTransfer::default());
+
+        // Truncated last transfer
+        let truncated = &armored[..armored.len() - 40];
+        assert!(MultiTransfer::from_str(truncated).is_err());
+    }
 }
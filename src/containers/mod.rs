@@ -37,31 +37,41 @@ mod indexed;
 mod file;
 mod kit;
 mod suppl;
+mod notary;
+mod spv;
 
 pub use anchors::{
     AnchorSet, AnchoredBundleMismatch, AnchoredBundles, ClientBundle, PubWitness, SealWitness,
-    ToWitnessId, UnrelatedTransition, WitnessBundle, XPubWitness,
+    ToWitnessId, UnrelatedTransition, WitnessBundle, WitnessVerifyError, XPubWitness,
 };
 pub use consignment::{
-    Consignment, ConsignmentExt, ConsignmentId, ConsignmentParseError, Contract, Transfer,
-    ValidConsignment, ValidContract, ValidTransfer,
+    check_timestamp, topological_order, Consignment, ConsignmentDiff, ConsignmentExt,
+    ConsignmentId, ConsignmentParseError, ConsignmentSummary, Contract, CyclicDependency,
+    TerminalError, TimestampError, TimestampPolicy, Transfer, ValidConsignment, ValidContract,
+    ValidTransfer, RGB_EPOCH, TIMESTAMP_MAX_DRIFT,
 };
 pub use disclosure::Disclosure;
-pub use file::{FileContent, LoadError, UniversalFile};
+pub use file::{
+    Chunk, ChunkManifest, FileContent, LoadError, MultiTransfer, UniversalFile, CHUNK_MAGIC,
+    CHUNK_MANIFEST_MAGIC, CHUNK_VER, DEFLATE_FLAG, MULTI_TRANSFER_MAGIC, MULTI_TRANSFER_VER,
+};
 pub use indexed::IndexedConsignment;
 pub use kit::{Kit, KitId, ValidKit};
+pub use notary::{DocCommitError, DocCommitment, DocProof, DocVerifyError};
 pub use partials::{
     Batch, BundleDichotomy, CloseMethodSet, Dichotomy, Fascia, TransitionDichotomy, TransitionInfo,
     TransitionInfoError,
 };
 pub use seal::{BuilderSeal, VoutSeal};
+pub use spv::{HeaderSource, SpvError, SpvProof};
 pub use suppl::{
-    AnnotationName, Annotations, ContentRef, SupplId, SupplItem, SupplMap, SupplSub, Supplement,
-    TickerSuppl, VelocityHint, SUPPL_ANNOT_IFACE_CLASS, SUPPL_ANNOT_IFACE_FEATURES,
-    SUPPL_ANNOT_VELOCITY,
+    AnnotationName, Annotations, ContentRef, ContractTags, SupplId, SupplItem, SupplMap, SupplSub,
+    Supplement, TickerSuppl, VelocityHint, SUPPL_ANNOT_IFACE_CLASS, SUPPL_ANNOT_IFACE_FEATURES,
+    SUPPL_ANNOT_TAGS, SUPPL_ANNOT_VELOCITY,
 };
 pub use util::{
-    ContainerVer, ContentId, ContentSigs, DumbValidator, SigBlob, SigValidator, TrustLevel,
+    ContainerVer, ContentId, ContentSigner, ContentSigs, DumbValidator, SigBlob, SigValidator,
+    TrustLevel,
 };
 
 pub const ASCII_ARMOR_NAME: &str = "Name";
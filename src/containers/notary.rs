@@ -0,0 +1,283 @@
+// RGB standard library for working with smart contracts on Bitcoin & Lightning
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Anchoring of arbitrary documents (like term sheets or legal agreements) in
+//! the same deterministic bitcoin commitment as RGB operations.
+//!
+//! A document is committed using a separate multi-protocol commitment (MPC)
+//! protocol, such that the same witness transaction both closes the RGB seals
+//! and timestamps the document, without affecting the RGB consensus.
+
+use amplify::{confinement, ByteArray};
+use bp::dbc::{anchor, Anchor};
+use commit_verify::{mpc, DigestExt, Sha256};
+use rgb::validation::{DbcError, DbcProof};
+use rgb::vm::XWitnessTx;
+use rgb::XWitnessId;
+
+use crate::containers::AnchorSet;
+
+/// Errors adding a document commitment to a multi-protocol commitment.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DocCommitError {
+    /// multi-protocol commitment already contains a different message for the
+    /// protocol {0}.
+    ProtocolCollision(mpc::ProtocolId),
+
+    /// too many messages in the multi-protocol commitment.
+    #[from]
+    TooManyMessages(confinement::Error),
+}
+
+/// Errors happening during [`DocProof::verify`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DocVerifyError {
+    /// the proof is made for witness {0}, while transaction {1} was provided.
+    WitnessMismatch(XWitnessId, XWitnessId),
+
+    /// witness {0} doesn't commit to the document: {1}
+    Commitment(XWitnessId, anchor::VerifyError<DbcError>),
+}
+
+/// MPC protocol id and message committing to a document hash, which should be
+/// added to the MPC tree of a witness transaction alongside RGB bundles.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct DocCommitment {
+    pub protocol_id: mpc::ProtocolId,
+    pub message: mpc::Message,
+}
+
+impl DocCommitment {
+    /// Constructs commitment to a document with a given hash under the
+    /// application-specific protocol tag. Protocol id is derived from both the
+    /// tag and the document hash, such that multiple documents can be
+    /// anchored within a single witness transaction.
+    pub fn with(protocol_tag: &str, doc_hash: [u8; 32]) -> Self {
+        let mut engine = Sha256::from_tag(protocol_tag);
+        engine.input_raw(&doc_hash);
+        DocCommitment {
+            protocol_id: mpc::ProtocolId::from_byte_array(engine.finish()),
+            message: mpc::Message::from_byte_array(doc_hash),
+        }
+    }
+
+    pub fn doc_hash(&self) -> [u8; 32] { self.message.to_byte_array() }
+
+    /// Adds the document commitment to the multi-protocol commitment source,
+    /// which is then committed by the wallet into the witness transaction (PSBT)
+    /// together with the bundles of the transfer.
+    pub fn insert_into(&self, source: &mut mpc::MultiSource) -> Result<(), DocCommitError> {
+        match source.messages.get(&self.protocol_id) {
+            Some(message) if *message == self.message => Ok(()),
+            Some(_) => Err(DocCommitError::ProtocolCollision(self.protocol_id)),
+            None => {
+                source.messages.insert(self.protocol_id, self.message)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Proof that a document was anchored in a given witness transaction.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DocProof {
+    pub doc: DocCommitment,
+    pub witness_id: XWitnessId,
+    pub anchor: Anchor<mpc::MerkleProof, DbcProof>,
+}
+
+impl DocProof {
+    /// Computes MPC commitment from the proof, which must match the commitment
+    /// in the witness transaction verifiable with the DBC proof of the anchor.
+    pub fn mpc_commitment(&self) -> Result<mpc::Commitment, mpc::InvalidProof> {
+        self.anchor
+            .mpc_proof
+            .convolve(self.doc.protocol_id, self.doc.message)
+    }
+
+    /// Verifies that the witness transaction commits to the document, returning
+    /// the MPC commitment contained in the transaction.
+    pub fn verify(&self, witness: &XWitnessTx) -> Result<mpc::Commitment, DocVerifyError> {
+        let witness_id = witness.map_ref(|tx| tx.txid());
+        if witness_id != self.witness_id {
+            return Err(DocVerifyError::WitnessMismatch(self.witness_id, witness_id));
+        }
+        self.anchor
+            .verify(self.doc.protocol_id, self.doc.message, witness.as_reduced_unsafe())
+            .map_err(|err| DocVerifyError::Commitment(witness_id, err))
+    }
+}
+
+impl AnchorSet {
+    /// Extracts proof of the document commitment from the anchors, if the
+    /// document is known to the MPC trees of the anchors.
+    pub fn doc_proof(&self, witness_id: XWitnessId, doc: DocCommitment) -> Option<DocProof> {
+        let (mpc_block, dbc_proof) = match self {
+            AnchorSet::Tapret(tapret) => {
+                (&tapret.mpc_proof, DbcProof::Tapret(tapret.dbc_proof.clone()))
+            }
+            AnchorSet::Opret(opret) => (&opret.mpc_proof, DbcProof::Opret(opret.dbc_proof.clone())),
+            AnchorSet::Double { tapret, opret } => {
+                if tapret
+                    .mpc_proof
+                    .to_known_message_map()
+                    .get(&doc.protocol_id)
+                    == Some(&doc.message)
+                {
+                    (&tapret.mpc_proof, DbcProof::Tapret(tapret.dbc_proof.clone()))
+                } else {
+                    (&opret.mpc_proof, DbcProof::Opret(opret.dbc_proof.clone()))
+                }
+            }
+        };
+        if mpc_block.to_known_message_map().get(&doc.protocol_id) != Some(&doc.message) {
+            return None;
+        }
+        let mpc_proof = mpc_block.to_merkle_proof(doc.protocol_id).ok()?;
+        Some(DocProof {
+            doc,
+            witness_id,
+            anchor: Anchor::new(mpc_proof, dbc_proof),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bp::dbc::opret::OpretProof;
+    use bp::{
+        LockTime, Outpoint, Sats, ScriptPubkey, SeqNo, SigScript, Tx, TxIn, TxOut, TxVer, Txid,
+        Witness,
+    };
+    use commit_verify::{CommitId, TryCommitVerify};
+    use rgb::{BundleId, ContractId, XChain};
+
+    use super::*;
+
+    const TAG: &str = "urn:example:term-sheet#2024-10-16";
+
+    fn doc() -> DocCommitment { DocCommitment::with(TAG, [0xAA; 32]) }
+
+    fn source(entropy: u64) -> mpc::MultiSource {
+        mpc::MultiSource {
+            messages: Confined::try_from_iter([(
+                ContractId::from_byte_array([0x11; 32]).into(),
+                BundleId::from_byte_array([0x22; 32]).into(),
+            )])
+            .unwrap(),
+            static_entropy: Some(entropy),
+            ..default!()
+        }
+    }
+
+    fn mpc_block(entropy: u64) -> mpc::MerkleBlock {
+        let mut source = source(entropy);
+        doc().insert_into(&mut source).unwrap();
+        let tree = mpc::MerkleTree::try_commit(&source).unwrap();
+        mpc::MerkleBlock::from(&tree)
+    }
+
+    // Witness transaction committing with opret to both the bundle and the
+    // document.
+    fn witness(lock_time: LockTime) -> (AnchorSet, XWitnessTx) {
+        let mpc_proof = mpc_block(1);
+        let commitment = mpc_proof.commit_id();
+        let tx = Tx {
+            version: TxVer::V2,
+            inputs: Confined::from_checked(vec![TxIn {
+                prev_output: Outpoint::new(Txid::from_byte_array([0x33; 32]), 0),
+                sig_script: SigScript::default(),
+                sequence: SeqNo::ZERO,
+                witness: Witness::default(),
+            }]),
+            outputs: Confined::from_checked(vec![TxOut::new(
+                ScriptPubkey::op_return(commitment.as_slice()),
+                Sats::ZERO,
+            )]),
+            lock_time,
+        };
+        (AnchorSet::Opret(Anchor::new(mpc_proof, OpretProof::default())), XChain::Bitcoin(tx))
+    }
+
+    #[test]
+    fn protocol_id() {
+        assert_eq!(doc(), DocCommitment::with(TAG, [0xAA; 32]));
+        assert_eq!(doc().doc_hash(), [0xAA; 32]);
+        assert_ne!(doc().protocol_id, DocCommitment::with(TAG, [0xBB; 32]).protocol_id);
+        assert_ne!(
+            doc().protocol_id,
+            DocCommitment::with("urn:example:other#2024-10-16", [0xAA; 32]).protocol_id
+        );
+    }
+
+    #[test]
+    fn insert() {
+        let mut source = source(1);
+        doc().insert_into(&mut source).unwrap();
+        assert_eq!(source.messages.get(&doc().protocol_id), Some(&doc().message));
+        assert_eq!(source.messages.len(), 2);
+
+        // Repeated insertion of the same document is a no-op
+        doc().insert_into(&mut source).unwrap();
+        assert_eq!(source.messages.len(), 2);
+
+        let mut colliding = doc();
+        colliding.message = mpc::Message::from_byte_array([0xBB; 32]);
+        assert_eq!(
+            colliding.insert_into(&mut source),
+            Err(DocCommitError::ProtocolCollision(doc().protocol_id))
+        );
+    }
+
+    #[test]
+    fn proof_verify() {
+        let (anchors, tx) = witness(LockTime::ZERO);
+        let witness_id = tx.map_ref(|tx| tx.txid());
+        let proof = anchors.doc_proof(witness_id, doc()).unwrap();
+        let commitment = proof.verify(&tx).unwrap();
+        assert_eq!(proof.mpc_commitment(), Ok(commitment));
+
+        // Document is not committed in the witness
+        assert_eq!(anchors.doc_proof(witness_id, DocCommitment::with(TAG, [0xBB; 32])), None);
+    }
+
+    #[test]
+    fn proof_invalid() {
+        let (anchors, tx) = witness(LockTime::ZERO);
+        let witness_id = tx.map_ref(|tx| tx.txid());
+        let proof = anchors.doc_proof(witness_id, doc()).unwrap();
+
+        // Proof doesn't match the transaction
+        let (_, other) = witness(LockTime::from_height(1).unwrap());
+        assert!(matches!(proof.verify(&other), Err(DocVerifyError::WitnessMismatch(..))));
+
+        // Tampered anchor, which doesn't match the commitment in the transaction
+        let tampered = AnchorSet::Opret(Anchor::new(mpc_block(2), OpretProof::default()));
+        let proof = tampered.doc_proof(witness_id, doc()).unwrap();
+        assert!(matches!(
+            proof.verify(&tx),
+            Err(DocVerifyError::Commitment(_, anchor::VerifyError::Dbc(_)))
+        ));
+    }
+}
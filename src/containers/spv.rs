@@ -0,0 +1,233 @@
+// RGB standard library for working with smart contracts on Bitcoin & Lightning
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simplified payment verification (SPV) of witness transactions, allowing
+//! validation of consignments without access to the blockchain (for instance,
+//! by air-gapped signers).
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+
+use amplify::ByteArray;
+use bp::{BlockHash, BlockHeader, Txid};
+use commit_verify::{DigestExt, Sha256};
+use rgb::vm::{WitnessOrd, WitnessPos};
+
+/// Maximal depth of a block merkle tree.
+const MERKLE_MAX_DEPTH: usize = 32;
+
+/// Source of block headers trusted by the validating party, like a locally
+/// stored header chain.
+pub trait HeaderSource {
+    /// Returns height of a block with the given hash, or `None` if the block
+    /// is not a part of the known chain.
+    fn block_height(&self, block_hash: BlockHash) -> Option<NonZeroU32>;
+}
+
+impl HeaderSource for BTreeMap<BlockHash, NonZeroU32> {
+    fn block_height(&self, block_hash: BlockHash) -> Option<NonZeroU32> {
+        self.get(&block_hash).copied()
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SpvError {
+    /// merkle path for transaction {0} doesn't match its position in the
+    /// block.
+    InvalidPath(Txid),
+
+    /// transaction {0} is not included into the block {1}.
+    NotIncluded(Txid, BlockHash),
+
+    /// block {0} is not a part of the known header chain.
+    UnknownBlock(BlockHash),
+
+    /// block {0} has invalid timestamp.
+    InvalidTimestamp(BlockHash),
+}
+
+/// Proof of inclusion of a witness transaction into a block.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SpvProof {
+    /// Header of the block containing the transaction.
+    pub header: BlockHeader,
+    /// Position of the transaction in the block.
+    pub pos: u32,
+    /// Hashes of the merkle tree nodes required to compute the merkle root,
+    /// starting from the bottom of the tree.
+    pub path: Vec<[u8; 32]>,
+}
+
+impl SpvProof {
+    /// Computes the block merkle root from the transaction id and the merkle
+    /// path.
+    pub fn merkle_root(&self, txid: Txid) -> Result<[u8; 32], SpvError> {
+        let depth = self.path.len();
+        if depth > MERKLE_MAX_DEPTH || (depth < MERKLE_MAX_DEPTH && self.pos >> depth != 0) {
+            return Err(SpvError::InvalidPath(txid));
+        }
+        let mut node = txid.to_byte_array();
+        for (level, sibling) in self.path.iter().enumerate() {
+            node = if (self.pos >> level) & 1 == 0 {
+                merkle_node(&node, sibling)
+            } else {
+                merkle_node(sibling, &node)
+            };
+        }
+        Ok(node)
+    }
+
+    /// Verifies that the transaction is included into a block from the known
+    /// header chain, returning the transaction position in the chain.
+    pub fn verify(&self, txid: Txid, headers: &impl HeaderSource) -> Result<WitnessOrd, SpvError> {
+        let block_hash = self.header.block_hash();
+        if self.merkle_root(txid)? != self.header.merkle_root.to_byte_array() {
+            return Err(SpvError::NotIncluded(txid, block_hash));
+        }
+        let height = headers
+            .block_height(block_hash)
+            .ok_or(SpvError::UnknownBlock(block_hash))?;
+        let pos = WitnessPos::bitcoin(height, self.header.time as i64)
+            .ok_or(SpvError::InvalidTimestamp(block_hash))?;
+        Ok(WitnessOrd::Mined(pos))
+    }
+}
+
+fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut engine = Sha256::default();
+    engine.input_raw(left);
+    engine.input_raw(right);
+    let mut double = Sha256::default();
+    double.input_raw(&engine.finish());
+    double.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn height() -> NonZeroU32 { NonZeroU32::new(840_000).unwrap() }
+
+    fn txid(no: u8) -> Txid { Txid::from_byte_array([no; 32]) }
+
+    // Block with three transactions, where the last one gets duplicated to
+    // complete the merkle tree.
+    fn block() -> (BlockHeader, [[u8; 32]; 4]) {
+        let [a, b, c] = [1u8, 2, 3].map(|no| txid(no).to_byte_array());
+        let ab = merkle_node(&a, &b);
+        let cc = merkle_node(&c, &c);
+        let header = BlockHeader {
+            version: 0x20000000,
+            prev_block_hash: BlockHash::from_byte_array([0xEE; 32]),
+            merkle_root: merkle_node(&ab, &cc).into(),
+            time: 1_713_571_767,
+            bits: 0x17034219,
+            nonce: 0,
+        };
+        (header, [a, b, c, ab])
+    }
+
+    fn headers(header: &BlockHeader) -> BTreeMap<BlockHash, NonZeroU32> {
+        bmap! { header.block_hash() => height() }
+    }
+
+    #[test]
+    fn valid_proof() {
+        let (header, [a, b, c, ab]) = block();
+        let headers = headers(&header);
+        let cc = merkle_node(&c, &c);
+        let expected =
+            WitnessOrd::Mined(WitnessPos::bitcoin(height(), header.time as i64).unwrap());
+
+        let proof = SpvProof {
+            header,
+            pos: 0,
+            path: vec![b, cc],
+        };
+        assert_eq!(proof.verify(txid(1), &headers), Ok(expected));
+
+        let proof = SpvProof {
+            header,
+            pos: 1,
+            path: vec![a, cc],
+        };
+        assert_eq!(proof.verify(txid(2), &headers), Ok(expected));
+
+        let proof = SpvProof {
+            header,
+            pos: 2,
+            path: vec![c, ab],
+        };
+        assert_eq!(proof.verify(txid(3), &headers), Ok(expected));
+    }
+
+    #[test]
+    fn invalid_proof() {
+        let (header, [_, b, c, ab]) = block();
+        let headers = headers(&header);
+        let block_hash = header.block_hash();
+        let cc = merkle_node(&c, &c);
+
+        // Wrong transaction
+        let proof = SpvProof {
+            header,
+            pos: 0,
+            path: vec![b, cc],
+        };
+        assert_eq!(
+            proof.verify(txid(4), &headers),
+            Err(SpvError::NotIncluded(txid(4), block_hash))
+        );
+
+        // Wrong position
+        let proof = SpvProof {
+            header,
+            pos: 1,
+            path: vec![b, cc],
+        };
+        assert_eq!(
+            proof.verify(txid(1), &headers),
+            Err(SpvError::NotIncluded(txid(1), block_hash))
+        );
+
+        // Position outside of the tree
+        let proof = SpvProof {
+            header,
+            pos: 6,
+            path: vec![c, ab],
+        };
+        assert_eq!(proof.verify(txid(3), &headers), Err(SpvError::InvalidPath(txid(3))));
+
+        // Block outside of the known chain
+        let proof = SpvProof {
+            header,
+            pos: 0,
+            path: vec![b, cc],
+        };
+        assert_eq!(proof.verify(txid(1), &bmap! {}), Err(SpvError::UnknownBlock(block_hash)));
+    }
+}
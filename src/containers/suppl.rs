@@ -23,7 +23,7 @@ use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-use amplify::confinement::{SmallBlob, TinyOrdMap};
+use amplify::confinement::{SmallBlob, TinyOrdMap, TinyString};
 use amplify::{ByteArray, Bytes32};
 use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
 use chrono::Utc;
@@ -42,6 +42,8 @@ use crate::LIB_NAME_RGB_STD;
 pub const SUPPL_ANNOT_VELOCITY: &str = "Velocity";
 pub const SUPPL_ANNOT_IFACE_CLASS: &str = "Standard";
 pub const SUPPL_ANNOT_IFACE_FEATURES: &str = "Features";
+/// Annotation for the application-defined contract tags (see [`ContractTags`]).
+pub const SUPPL_ANNOT_TAGS: &str = "Tags";
 
 /// Contract supplement identifier.
 ///
@@ -184,6 +186,28 @@ pub struct SupplMap(TinyOrdMap<SupplItem, Annotations>);
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
 pub struct Annotations(TinyOrdMap<AnnotationName, SmallBlob>);
 
+/// Opaque key-value tags which may be attached to a contract by the issuing
+/// application (like internal ticket or customer ids).
+///
+/// Tags are not a part of the contract consensus data and are kept in a
+/// separate unsigned contract genesis supplement under [`SUPPL_ANNOT_TAGS`]
+/// annotation. Tags are local to the stock: they are never exported with
+/// consignments or kits and are never imported from transfers.
+#[derive(Wrapper, WrapperMut, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, From)]
+#[wrapper(Deref)]
+#[wrapper_mut(DerefMut)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STD)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ContractTags(TinyOrdMap<TinyString, TinyString>);
+
+impl StrictSerialize for ContractTags {}
+impl StrictDeserialize for ContractTags {}
+
 /// Contract supplement, providing non-consensus information about standard
 /// way of working with the contract data. Each contract can have only a single
 /// valid supplement; the supplement is attached to the contract via trusted
@@ -221,6 +245,11 @@ impl Supplement {
         }
     }
 
+    /// Returns application-defined contract tags, if the supplement has them.
+    pub fn tags(&self) -> Option<ContractTags> {
+        self.get_default_opt(SupplSub::Itself, SUPPL_ANNOT_TAGS)
+    }
+
     pub fn get_default_opt<T: StrictDeserialize>(
         &self,
         sub: SupplSub,
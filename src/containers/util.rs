@@ -20,6 +20,8 @@
 // limitations under the License.
 
 use std::collections::btree_map;
+use std::fmt::Debug;
+use std::sync::Arc;
 
 use amplify::confinement::{NonEmptyBlob, NonEmptyOrdMap};
 use commit_verify::StrictHash;
@@ -47,6 +49,22 @@ pub enum ContainerVer {
     V2 = 2,
 }
 
+/// Signer producing signatures over container content (like contract genesis
+/// or supplements), identified by its [`ContentId`].
+pub trait ContentSigner: Debug + Send + Sync {
+    /// Identity of the signer, under which the signatures are stored.
+    fn identity(&self) -> Identity;
+
+    /// Signs the content with a given id.
+    fn sign(&self, content_id: ContentId) -> SigBlob;
+}
+
+impl<T: ContentSigner + ?Sized> ContentSigner for Arc<T> {
+    fn identity(&self) -> Identity { self.as_ref().identity() }
+
+    fn sign(&self, content_id: ContentId) -> SigBlob { self.as_ref().sign(content_id) }
+}
+
 pub trait SigValidator {
     fn validate_sig(&self, identity: &Identity, sig: SigBlob) -> bool;
 }
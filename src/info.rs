@@ -30,7 +30,7 @@ use strict_encoding::stl::{AlphaCapsLodash, AlphaNumLodash};
 use strict_encoding::{FieldName, RString, StrictDeserialize, StrictSerialize, TypeName};
 
 use crate::containers::{
-    SupplSub, Supplement, SUPPL_ANNOT_IFACE_CLASS, SUPPL_ANNOT_IFACE_FEATURES,
+    ContractTags, SupplSub, Supplement, SUPPL_ANNOT_IFACE_CLASS, SUPPL_ANNOT_IFACE_FEATURES,
 };
 use crate::interface::{Iface, IfaceId, IfaceImpl, IfaceRef, ImplId, VerNo};
 use crate::persistence::SchemaIfaces;
@@ -283,6 +283,8 @@ pub struct ContractInfo {
     pub issued_at: DateTime<Utc>,
     pub testnet: bool,
     pub alt_layers1: AltLayer1Set,
+    /// Application-defined tags local to the stock.
+    pub tags: ContractTags,
 }
 
 impl ContractInfo {
@@ -297,8 +299,15 @@ impl ContractInfo {
                 .unwrap_or_else(Utc::now),
             testnet: genesis.testnet,
             alt_layers1: genesis.alt_layers1.clone(),
+            tags: none!(),
         }
     }
+
+    pub fn with_tags(genesis: &Genesis, tags: ContractTags) -> Self {
+        let mut info = Self::with(genesis);
+        info.tags = tags;
+        info
+    }
 }
 
 impl Display for ContractInfo {
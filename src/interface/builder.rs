@@ -22,26 +22,35 @@
 #![allow(clippy::result_large_err)]
 
 use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
 
-use amplify::confinement::{Confined, SmallOrdSet, TinyOrdMap, U16};
+use amplify::confinement::{
+    Confined, MediumBlob, SmallOrdMap, SmallOrdSet, TinyOrdMap, TinyOrdSet, TinyString, U16,
+};
 use amplify::{confinement, Wrapper};
 use chrono::Utc;
-use invoice::{Allocation, Amount};
+use commit_verify::{DigestExt, Sha256};
+use invoice::{Allocation, Amount, ChainNet, Precision};
 use rgb::validation::Scripts;
 use rgb::{
     validation, AltLayer1, AltLayer1Set, AssetTag, AssetTags, Assign, AssignmentType, Assignments,
-    AttachState, BlindingFactor, ContractId, DataState, ExposedSeal, FungibleType, Genesis,
-    GenesisSeal, GlobalState, GraphSeal, Identity, Input, Layer1, MetadataError, Opout,
-    OwnedStateSchema, RevealedAttach, RevealedData, RevealedValue, Schema, Transition,
-    TransitionType, TypedAssigns, XChain, XOutpoint,
+    AttachId, AttachState, BlindingFactor, ContractId, DataState, ExposedSeal, Extension,
+    ExtensionType, FungibleType, Genesis, GenesisSeal, GlobalState, GraphSeal, Identity, Input,
+    Layer1, MetadataError, Occurrences, OpId, OpSchema, Opout, OwnedStateSchema, Redeemed,
+    RevealedAttach, RevealedData, RevealedValue, Schema, Transition, TransitionType, TypedAssigns,
+    XChain, XOutpoint,
 };
 use rgbcore::{GlobalStateSchema, GlobalStateType, MetaType, Metadata, ValencyType};
 use strict_encoding::{FieldName, SerializeError, StrictSerialize};
 use strict_types::{decode, SemId, TypeSystem};
 
-use crate::containers::{BuilderSeal, ContainerVer, Contract, ValidConsignment};
+use crate::containers::{
+    check_timestamp, BuilderSeal, ContainerVer, ContentId, ContentRef, ContentSigner, ContentSigs,
+    Contract, ContractTags, Supplement, TimestampError, TimestampPolicy, ValidConsignment,
+    SUPPL_ANNOT_TAGS,
+};
 use crate::interface::resolver::DumbResolver;
-use crate::interface::{Iface, IfaceImpl, TransitionIface};
+use crate::interface::{CoinSelect, Iface, IfaceImpl, TransitionIface};
 use crate::persistence::PersistedState;
 use crate::Outpoint;
 
@@ -67,6 +76,22 @@ pub enum BuilderError {
     /// transition `{0}` is not known to the schema.
     TransitionNotFound(FieldName),
 
+    /// state extension `{0}` is not known to the schema.
+    ExtensionNotFound(FieldName),
+
+    /// valency `{0}` is not known to the schema.
+    ValencyNotFound(FieldName),
+
+    /// valency `{0}` can't be redeemed by the state extension.
+    ValencyNotRedeemable(FieldName),
+
+    /// insufficient state: payment requires {required}, while only {available}
+    /// is available for spending.
+    InsufficientState { required: Amount, available: Amount },
+
+    /// total amount of the payment exceeds the maximal possible amount.
+    AmountOverflow,
+
     /// unknown owned state name `{0}`.
     InvalidStateField(FieldName),
 
@@ -95,6 +120,32 @@ pub enum BuilderError {
     /// {0} is not supported by the contract genesis.
     InvalidLayer1(Layer1),
 
+    /// attachment data don't match attachment id {0}.
+    AttachmentMismatch(AttachId),
+
+    /// contract doesn't belong to {0} network.
+    NetworkMismatch(ChainNet),
+
+    /// allocation of {amount} under state type {ty} is below the configured
+    /// minimum of {min}.
+    DustAllocation {
+        ty: AssignmentType,
+        amount: Amount,
+        min: Amount,
+    },
+
+    /// allocation of {amount} under state type {ty} is not a multiple of the
+    /// configured granularity of {granularity} atomic units.
+    AmountGranularity {
+        ty: AssignmentType,
+        amount: Amount,
+        granularity: u64,
+    },
+
+    #[from]
+    #[display(inner)]
+    Timestamp(TimestampError),
+
     #[from]
     #[display(inner)]
     StrictEncode(SerializeError),
@@ -110,6 +161,74 @@ pub enum BuilderError {
     #[from]
     #[display(inner)]
     ContractInconsistency(validation::Status),
+
+    /// constructed operation violates contract schema: {0}
+    #[from]
+    SchemaViolation(SchemaViolation),
+}
+
+/// Violations of the schema rules detected in a state transition before it
+/// gets completed by the [`TransitionBuilder`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SchemaViolation {
+    /// transition type {0} is not defined by the schema.
+    UnknownTransition(TransitionType),
+
+    /// transition contains metadata of type {0} which is not allowed by the
+    /// schema.
+    UnknownMetadata(MetaType),
+
+    /// transition lacks metadata of type {0} required by the schema.
+    MissingMetadata(MetaType),
+
+    /// transition metadata of type {0} doesn't match the semantic type defined
+    /// by the schema.
+    InvalidMetadata(MetaType),
+
+    /// transition contains global state of type {0} which is not allowed by
+    /// the schema.
+    UnknownGlobal(GlobalStateType),
+
+    /// transition contains {found} global state items of type {ty}, while the
+    /// schema requires from {min} to {max}.
+    GlobalOccurrences {
+        ty: GlobalStateType,
+        min: u16,
+        max: u16,
+        found: u16,
+    },
+
+    /// transition spends state of type {0} which is not allowed by the schema.
+    UnknownInput(AssignmentType),
+
+    /// transition spends {found} inputs of type {ty}, while the schema requires
+    /// from {min} to {max}.
+    InputOccurrences {
+        ty: AssignmentType,
+        min: u16,
+        max: u16,
+        found: u16,
+    },
+
+    /// transition assigns state of type {0} which is not allowed by the
+    /// schema.
+    UnknownAssignment(AssignmentType),
+
+    /// transition contains {found} assignments of type {ty}, while the schema
+    /// requires from {min} to {max}.
+    AssignmentOccurrences {
+        ty: AssignmentType,
+        min: u16,
+        max: u16,
+        found: u16,
+    },
+
+    /// transition is rejected by the contract validation, including the
+    /// schema validation scripts.
+    ///
+    /// {0}
+    Rejected(validation::Status),
 }
 
 mod private {
@@ -141,9 +260,15 @@ impl TxOutpoint for XOutpoint {
 pub struct ContractBuilder {
     builder: OperationBuilder<GenesisSeal>,
     testnet: bool,
+    chain_net: Option<ChainNet>,
     alt_layers1: AltLayer1Set,
     scripts: Scripts,
     issuer: Identity,
+    timestamp_policy: TimestampPolicy,
+    attachments: SmallOrdMap<AttachId, MediumBlob>,
+    supplements: TinyOrdSet<Supplement>,
+    signers: Vec<Arc<dyn ContentSigner>>,
+    tags: ContractTags,
 }
 
 impl ContractBuilder {
@@ -158,9 +283,15 @@ impl ContractBuilder {
         Self {
             builder: OperationBuilder::with(iface, schema, iimpl, types),
             testnet: true,
+            chain_net: None,
             alt_layers1: none!(),
             scripts,
             issuer,
+            timestamp_policy: default!(),
+            attachments: none!(),
+            supplements: none!(),
+            signers: none!(),
+            tags: none!(),
         }
     }
 
@@ -175,19 +306,34 @@ impl ContractBuilder {
         Self {
             builder: OperationBuilder::deterministic(iface, schema, iimpl, types),
             testnet: true,
+            chain_net: None,
             alt_layers1: none!(),
             scripts,
             issuer,
+            timestamp_policy: default!(),
+            attachments: none!(),
+            supplements: none!(),
+            signers: none!(),
+            tags: none!(),
         }
     }
 
     pub fn type_system(&self) -> &TypeSystem { self.builder.type_system() }
 
+    pub fn issuer(&self) -> &Identity { &self.issuer }
+
     pub fn set_mainnet(mut self) -> Self {
         self.testnet = false;
         self
     }
 
+    /// Sets how genesis timestamp sanity failures are handled during the
+    /// issue. Defaults to [`TimestampPolicy::Warn`].
+    pub fn set_timestamp_policy(mut self, policy: TimestampPolicy) -> Self {
+        self.timestamp_policy = policy;
+        self
+    }
+
     pub fn has_layer1(&self, layer1: Layer1) -> bool {
         match layer1 {
             Layer1::Bitcoin => true,
@@ -201,6 +347,36 @@ impl ContractBuilder {
         Ok(())
     }
 
+    /// Configures the contract for the given network: sets mainnet or testnet
+    /// flag and adds the network layer 1, if needed. Once the network is set,
+    /// each added seal and the issued contract are checked against it with
+    /// [`Self::check_chain_net`].
+    pub fn set_chain_net(mut self, chain_net: ChainNet) -> Result<Self, BuilderError> {
+        self.testnet = !chain_net.is_prod();
+        self.chain_net = Some(chain_net);
+        if chain_net.layer1() == Layer1::Liquid && !self.has_layer1(Layer1::Liquid) {
+            self = self.add_layer1(AltLayer1::Liquid)?;
+        }
+        Ok(self)
+    }
+
+    /// Checks that seals from the given network can be used in the contract,
+    /// i.e. that both the network type (mainnet or testnet) and its layer 1
+    /// match the contract.
+    pub fn check_chain_net(&self, chain_net: ChainNet) -> Result<(), BuilderError> {
+        if chain_net.is_prod() == self.testnet {
+            return Err(BuilderError::NetworkMismatch(chain_net));
+        }
+        self.check_layer1(chain_net.layer1())
+    }
+
+    fn check_seal_layer1(&self, layer1: Layer1) -> Result<(), BuilderError> {
+        if let Some(chain_net) = self.chain_net {
+            self.check_chain_net(chain_net)?;
+        }
+        self.check_layer1(layer1)
+    }
+
     pub fn add_layer1(mut self, layer1: AltLayer1) -> Result<Self, BuilderError> {
         self.alt_layers1
             .push(layer1)
@@ -268,7 +444,7 @@ impl ContractBuilder {
         state: PersistedState,
     ) -> Result<Self, BuilderError> {
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         self.builder = self.builder.add_owned_state_det(name, seal, state)?;
         Ok(self)
     }
@@ -279,7 +455,7 @@ impl ContractBuilder {
         seal: impl Into<BuilderSeal<GenesisSeal>>,
     ) -> Result<Self, BuilderError> {
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         self.builder = self.builder.add_rights(name, seal)?;
         Ok(self)
     }
@@ -292,7 +468,7 @@ impl ContractBuilder {
     ) -> Result<Self, BuilderError> {
         let name = name.into();
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         self.builder.init_asset_tag(name.clone())?;
         self.builder = self.builder.add_fungible_state(name, seal, value)?;
         Ok(self)
@@ -307,7 +483,7 @@ impl ContractBuilder {
     ) -> Result<Self, BuilderError> {
         let name = name.into();
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         let tag = self.builder.init_asset_tag(name.clone())?;
         let state = RevealedValue::with_blinding(value.into(), blinding, tag);
         self.builder = self.builder.add_fungible_state_det(name, seal, state)?;
@@ -321,7 +497,7 @@ impl ContractBuilder {
         value: impl StrictSerialize,
     ) -> Result<Self, BuilderError> {
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         self.builder = self.builder.add_data(name, seal, value)?;
         Ok(self)
     }
@@ -333,7 +509,7 @@ impl ContractBuilder {
         data: RevealedData,
     ) -> Result<Self, BuilderError> {
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         self.builder = self.builder.add_data_det(name, seal, data)?;
         Ok(self)
     }
@@ -345,7 +521,7 @@ impl ContractBuilder {
         attachment: AttachState,
     ) -> Result<Self, BuilderError> {
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         self.builder = self.builder.add_attachment(name, seal, attachment)?;
         Ok(self)
     }
@@ -357,11 +533,63 @@ impl ContractBuilder {
         attachment: RevealedAttach,
     ) -> Result<Self, BuilderError> {
         let seal = seal.into();
-        self.check_layer1(seal.layer1())?;
+        self.check_seal_layer1(seal.layer1())?;
         self.builder = self.builder.add_attachment_det(name, seal, attachment)?;
         Ok(self)
     }
 
+    /// Embeds attachment file data (like media or terms files) into the
+    /// issued contract container. The file should be referenced by its id from
+    /// an attachment state added with [`Self::add_attachment`], which also
+    /// defines the file media type.
+    ///
+    /// The attachment id must be the SHA256 hash of the file data; otherwise
+    /// [`BuilderError::AttachmentMismatch`] is returned.
+    pub fn add_attachment_file(
+        mut self,
+        id: AttachId,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<Self, BuilderError> {
+        let data = data.into();
+        let mut hasher = Sha256::default();
+        hasher.input_raw(&data);
+        if AttachId::from(hasher.finish()) != id {
+            return Err(BuilderError::AttachmentMismatch(id));
+        }
+        self.attachments.insert(id, MediumBlob::try_from(data)?)?;
+        Ok(self)
+    }
+
+    /// Adds supplement (like identity-verified metadata for the schema or
+    /// interface) to the issued contract container.
+    pub fn add_supplement(mut self, supplement: Supplement) -> Result<Self, BuilderError> {
+        self.supplements.push(supplement)?;
+        Ok(self)
+    }
+
+    /// Adds application-defined tag, which is kept outside of the contract
+    /// consensus data in a separate unsigned genesis supplement. The tags are
+    /// local to the stock and are never exported with consignments. If the tag
+    /// with the same key was already added, its value gets replaced.
+    pub fn add_tag(
+        mut self,
+        key: impl ToString,
+        value: impl ToString,
+    ) -> Result<Self, BuilderError> {
+        self.tags.insert(
+            TinyString::try_from(key.to_string())?,
+            TinyString::try_from(value.to_string())?,
+        )?;
+        Ok(self)
+    }
+
+    /// Adds signer which will sign the contract genesis and all supplements
+    /// of the issued contract. Signatures are added to the contract container.
+    pub fn sign_with(mut self, signer: impl ContentSigner + 'static) -> Self {
+        self.signers.push(Arc::new(signer));
+        self
+    }
+
     pub fn issue_contract(self) -> Result<ValidConsignment<false>, BuilderError> {
         debug_assert!(
             !self.builder.deterministic,
@@ -382,7 +610,17 @@ impl ContractBuilder {
     }
 
     fn issue_contract_raw(self, timestamp: i64) -> Result<ValidConsignment<false>, BuilderError> {
-        let (schema, iface, iimpl, global, assignments, types, asset_tags) =
+        if let Some(chain_net) = self.chain_net {
+            self.check_chain_net(chain_net)?;
+        }
+        let timestamp_err = check_timestamp(timestamp, Utc::now().timestamp()).err();
+        if let Some(err) = timestamp_err {
+            if self.timestamp_policy == TimestampPolicy::Strict {
+                return Err(err.into());
+            }
+        }
+
+        let (schema, iface, iimpl, metadata, global, assignments, types, asset_tags) =
             self.builder.complete(None);
 
         let genesis = Genesis {
@@ -393,17 +631,44 @@ impl ContractBuilder {
             testnet: self.testnet,
             alt_layers1: self.alt_layers1,
             asset_tags,
-            metadata: empty!(),
+            metadata,
             globals: global,
             assignments,
             valencies: none!(),
-            issuer: self.issuer,
+            issuer: self.issuer.clone(),
             validator: none!(),
         };
 
+        let mut supplements = self.supplements;
+
         let ifaces = tiny_bmap! { iface => iimpl };
         let scripts = Confined::from_iter_checked(self.scripts.into_values());
 
+        let mut signatures = bmap! {};
+        if !self.signers.is_empty() {
+            let content_ids = [ContentId::Genesis(genesis.contract_id())]
+                .into_iter()
+                .chain(supplements.iter().map(|s| ContentId::Suppl(s.suppl_id())));
+            for content_id in content_ids {
+                let sigs = self
+                    .signers
+                    .iter()
+                    .map(|signer| (signer.identity(), signer.sign(content_id)))
+                    .collect::<BTreeMap<_, _>>();
+                signatures.insert(content_id, ContentSigs::from(Confined::try_from(sigs)?));
+            }
+        }
+
+        // Tags are local to the issuer and are never signed, so they are added
+        // only after all signatures were produced.
+        if !self.tags.is_empty() {
+            let mut suppl =
+                Supplement::new(ContentRef::Genesis(genesis.contract_id()), self.issuer);
+            suppl.timestamp = timestamp;
+            suppl.annotate_itself(SUPPL_ANNOT_TAGS, &self.tags)?;
+            supplements.push(suppl)?;
+        }
+
         let contract = Contract {
             version: ContainerVer::V2,
             transfer: false,
@@ -413,18 +678,21 @@ impl ContractBuilder {
             bundles: none!(),
             schema,
             ifaces,
-            attachments: none!(), // TODO: Add support for attachment files
+            attachments: self.attachments,
 
             types,
             scripts,
 
-            supplements: none!(), // TODO: Add supplements
-            signatures: none!(),  // TODO: Add signatures
+            supplements,
+            signatures: Confined::try_from(signatures)?,
         };
 
-        let valid_contract = contract
+        let mut valid_contract = contract
             .validate(&DumbResolver, self.testnet)
             .map_err(|(status, _)| status)?;
+        if let Some(err) = timestamp_err {
+            valid_contract.add_warning(validation::Warning::Custom(err.to_string()));
+        }
 
         Ok(valid_contract)
     }
@@ -437,27 +705,35 @@ pub struct TransitionBuilder {
     nonce: u64,
     transition_type: TransitionType,
     inputs: TinyOrdMap<Input, PersistedState>,
+    min_amount: Amount,
+    granularity: Precision,
+    change: HashSet<BuilderSeal<GraphSeal>>,
 }
 
 impl TransitionBuilder {
+    #[deprecated(since = "0.11.0-beta.9.1", note = "use BlankTransitionBuilder::new instead")]
     pub fn blank_transition(
         contract_id: ContractId,
         iface: Iface,
         schema: Schema,
         iimpl: IfaceImpl,
         types: TypeSystem,
-    ) -> Self {
-        Self::with(contract_id, iface, schema, iimpl, TransitionType::BLANK, types)
+    ) -> BlankTransitionBuilder {
+        BlankTransitionBuilder::new(contract_id, iface, schema, iimpl, types)
     }
 
+    #[deprecated(
+        since = "0.11.0-beta.9.1",
+        note = "use BlankTransitionBuilder::deterministic instead"
+    )]
     pub fn blank_transition_det(
         contract_id: ContractId,
         iface: Iface,
         schema: Schema,
         iimpl: IfaceImpl,
         types: TypeSystem,
-    ) -> Self {
-        Self::deterministic(contract_id, iface, schema, iimpl, TransitionType::BLANK, types)
+    ) -> BlankTransitionBuilder {
+        BlankTransitionBuilder::deterministic(contract_id, iface, schema, iimpl, types)
     }
 
     pub fn default_transition(
@@ -534,6 +810,9 @@ impl TransitionBuilder {
             nonce: u64::MAX,
             transition_type,
             inputs: none!(),
+            min_amount: Amount::ZERO,
+            granularity: Precision::Indivisible,
+            change: none!(),
         }
     }
 
@@ -551,6 +830,9 @@ impl TransitionBuilder {
             nonce: u64::MAX,
             transition_type,
             inputs: none!(),
+            min_amount: Amount::ZERO,
+            granularity: Precision::Indivisible,
+            change: none!(),
         }
     }
 
@@ -563,6 +845,22 @@ impl TransitionBuilder {
         self
     }
 
+    /// Sets the minimal amount of a fungible allocation which may be created
+    /// by the transition. Allocations below it are rejected on completion,
+    /// except the change (see [`Self::add_fungible_change`]).
+    pub fn set_min_amount(mut self, min_amount: impl Into<Amount>) -> Self {
+        self.min_amount = min_amount.into();
+        self
+    }
+
+    /// Requires all fungible allocations created by the transition to be a
+    /// whole number of units at the given precision (for instance,
+    /// [`Precision::Centi`] allows only multiples of 100 atomic units).
+    pub fn set_granularity(mut self, granularity: impl Into<Precision>) -> Self {
+        self.granularity = granularity.into();
+        self
+    }
+
     #[inline]
     pub fn asset_tag(&self, name: impl Into<FieldName>) -> Result<AssetTag, BuilderError> {
         self.builder.asset_tag(name)
@@ -704,6 +1002,21 @@ impl TransitionBuilder {
         Ok(self)
     }
 
+    /// Assigns fungible state returned to the payer as a change. Unlike
+    /// [`Self::add_fungible_state`], the change is exempt from the minimal
+    /// amount and granularity requirements, since its amount is defined by
+    /// the spent inputs and not chosen by the payer.
+    pub fn add_fungible_change(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GraphSeal>>,
+        value: impl Into<Amount>,
+    ) -> Result<Self, BuilderError> {
+        let seal = seal.into();
+        self.change.insert(seal);
+        self.add_fungible_state(name, seal, value)
+    }
+
     pub fn add_fungible_state_det(
         mut self,
         name: impl Into<FieldName>,
@@ -800,14 +1113,15 @@ impl TransitionBuilder {
     pub fn has_inputs(&self) -> bool { !self.inputs.is_empty() }
 
     pub fn complete_transition(self) -> Result<Transition, BuilderError> {
-        let (_, _, _, global, assignments, _, _) = self.builder.complete(Some(&self.inputs));
+        let (schema, _, _, metadata, global, assignments, types, _) =
+            self.builder.complete(Some(&self.inputs));
 
         let transition = Transition {
             ffv: none!(),
             contract_id: self.contract_id,
             nonce: self.nonce,
             transition_type: self.transition_type,
-            metadata: empty!(),
+            metadata,
             globals: global,
             inputs: SmallOrdSet::from_iter_checked(self.inputs.into_keys()).into(),
             assignments,
@@ -816,12 +1130,603 @@ impl TransitionBuilder {
             validator: none!(),
         };
 
-        // TODO: Validate against schema
+        if transition.transition_type != TransitionType::BLANK {
+            check_transition_schema(&schema, &types, &transition)?;
+            check_allocations(&transition, self.min_amount, self.granularity, &self.change)?;
+        }
 
         Ok(transition)
     }
 }
 
+/// Checks that fungible allocations created by a transition, except the
+/// change assigned to the `change` seals, are not dust and match the required
+/// granularity. Blank transitions just move existing allocations and must not
+/// be checked with this function.
+fn check_allocations(
+    transition: &Transition,
+    min: Amount,
+    granularity: Precision,
+    change: &HashSet<BuilderSeal<GraphSeal>>,
+) -> Result<(), BuilderError> {
+    for (ty, assigns) in transition.assignments.iter() {
+        let TypedAssigns::Fungible(assigns) = assigns else {
+            continue;
+        };
+        for assign in assigns.iter() {
+            let Some(state) = assign.as_revealed_state() else {
+                continue;
+            };
+            let seal = match assign.revealed_seal() {
+                Some(seal) => BuilderSeal::Revealed(seal),
+                None => BuilderSeal::Concealed(assign.to_confidential_seal()),
+            };
+            if change.contains(&seal) {
+                continue;
+            }
+            let amount = Amount::from(state.value);
+            if amount < min {
+                return Err(BuilderError::DustAllocation {
+                    ty: *ty,
+                    amount,
+                    min,
+                });
+            }
+            if amount.rem(granularity) != 0 {
+                return Err(BuilderError::AmountGranularity {
+                    ty: *ty,
+                    amount,
+                    granularity: granularity.multiplier(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks metadata and the number and types of global state, inputs and
+/// assignments of a transition against the schema. Blank transitions are not
+/// defined by the schema and must not be checked with this function.
+///
+/// Schema validation scripts require the contract history and state, and are
+/// run by [`crate::persistence::Stock::validate_transition`].
+fn check_transition_schema(
+    schema: &Schema,
+    types: &TypeSystem,
+    transition: &Transition,
+) -> Result<(), SchemaViolation> {
+    let op_schema = schema
+        .transitions
+        .get(&transition.transition_type)
+        .ok_or(SchemaViolation::UnknownTransition(transition.transition_type))?;
+
+    let mismatch =
+        |occ: &Occurrences, found: u16| found < occ.min_value() || found > occ.max_value();
+
+    for ty in transition.metadata.keys() {
+        if !op_schema.metadata().contains(ty) {
+            return Err(SchemaViolation::UnknownMetadata(*ty));
+        }
+    }
+    for ty in op_schema.metadata() {
+        let value = transition
+            .metadata
+            .get(ty)
+            .ok_or(SchemaViolation::MissingMetadata(*ty))?;
+        let sem_id = schema
+            .meta_types
+            .get(ty)
+            .ok_or(SchemaViolation::InvalidMetadata(*ty))?;
+        types
+            .strict_deserialize_type(*sem_id, value.as_ref())
+            .map_err(|_| SchemaViolation::InvalidMetadata(*ty))?;
+    }
+
+    for ty in transition.globals.keys() {
+        if !op_schema.globals().contains_key(ty) {
+            return Err(SchemaViolation::UnknownGlobal(*ty));
+        }
+    }
+    for (ty, occ) in op_schema.globals() {
+        let found = transition
+            .globals
+            .get(ty)
+            .map(|values| u16::try_from(values.len()).unwrap_or(u16::MAX))
+            .unwrap_or_default();
+        if mismatch(occ, found) {
+            return Err(SchemaViolation::GlobalOccurrences {
+                ty: *ty,
+                min: occ.min_value(),
+                max: occ.max_value(),
+                found,
+            });
+        }
+    }
+
+    let mut inputs = BTreeMap::<AssignmentType, u16>::new();
+    for input in transition.inputs.iter() {
+        let count = inputs.entry(input.prev_out.ty).or_default();
+        *count = count.saturating_add(1);
+    }
+    let schema_inputs = op_schema.inputs().expect("transitions always have inputs");
+    for ty in inputs.keys() {
+        if !schema_inputs.contains_key(ty) {
+            return Err(SchemaViolation::UnknownInput(*ty));
+        }
+    }
+    for (ty, occ) in schema_inputs {
+        let found = inputs.get(ty).copied().unwrap_or_default();
+        if mismatch(occ, found) {
+            return Err(SchemaViolation::InputOccurrences {
+                ty: *ty,
+                min: occ.min_value(),
+                max: occ.max_value(),
+                found,
+            });
+        }
+    }
+
+    for ty in transition.assignments.keys() {
+        if !op_schema.assignments().contains_key(ty) {
+            return Err(SchemaViolation::UnknownAssignment(*ty));
+        }
+    }
+    for (ty, occ) in op_schema.assignments() {
+        let found = transition
+            .assignments
+            .get(ty)
+            .map(|assigns| assigns.len_u16())
+            .unwrap_or_default();
+        if mismatch(occ, found) {
+            return Err(SchemaViolation::AssignmentOccurrences {
+                ty: *ty,
+                min: occ.min_value(),
+                max: occ.max_value(),
+                found,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Builder for blank state transitions.
+///
+/// Blank transitions move the state of contracts which are not participating
+/// in a transfer, but which have some state assigned to the outputs spent by
+/// the transfer. Unlike [`TransitionBuilder`], this builder does not allow
+/// arbitrary state assignments: each input added to the builder is carried
+/// over to a new seal with exactly the same state, such that the resulting
+/// transition always preserves the state and the blank transition type.
+#[derive(Clone, Debug)]
+pub struct BlankTransitionBuilder(TransitionBuilder);
+
+impl BlankTransitionBuilder {
+    pub fn new(
+        contract_id: ContractId,
+        iface: Iface,
+        schema: Schema,
+        iimpl: IfaceImpl,
+        types: TypeSystem,
+    ) -> Self {
+        Self(TransitionBuilder::with(
+            contract_id,
+            iface,
+            schema,
+            iimpl,
+            TransitionType::BLANK,
+            types,
+        ))
+    }
+
+    pub fn deterministic(
+        contract_id: ContractId,
+        iface: Iface,
+        schema: Schema,
+        iimpl: IfaceImpl,
+        types: TypeSystem,
+    ) -> Self {
+        Self(TransitionBuilder::deterministic(
+            contract_id,
+            iface,
+            schema,
+            iimpl,
+            TransitionType::BLANK,
+            types,
+        ))
+    }
+
+    pub fn type_system(&self) -> &TypeSystem { self.0.type_system() }
+
+    pub fn transition_type(&self) -> TransitionType { self.0.transition_type() }
+
+    pub fn set_nonce(self, nonce: u64) -> Self { Self(self.0.set_nonce(nonce)) }
+
+    #[inline]
+    pub fn add_asset_tag_raw(
+        self,
+        type_id: AssignmentType,
+        asset_tag: AssetTag,
+    ) -> Result<Self, BuilderError> {
+        self.0.add_asset_tag_raw(type_id, asset_tag).map(Self)
+    }
+
+    /// Spends the state under `opout` and assigns the same state to `seal`.
+    pub fn add_input(
+        self,
+        opout: Opout,
+        state: PersistedState,
+        seal: impl Into<BuilderSeal<GraphSeal>>,
+    ) -> Result<Self, BuilderError> {
+        self.0
+            .add_input(opout, state.clone())?
+            .add_owned_state_raw(opout.ty, seal, state)
+            .map(Self)
+    }
+
+    pub fn has_inputs(&self) -> bool { self.0.has_inputs() }
+
+    pub fn complete_transition(self) -> Result<Transition, BuilderError> {
+        self.0.complete_transition()
+    }
+}
+
+/// Builder of a state transition paying fungible state to multiple
+/// beneficiaries at once.
+///
+/// The builder is given a set of candidate inputs, out of which it selects
+/// the ones sufficient to cover all payments using a [`CoinSelect`] strategy,
+/// and assigns the remaining amount as a change.
+#[derive(Clone, Debug)]
+pub struct PaymentBuilder {
+    builder: TransitionBuilder,
+    assignment_name: FieldName,
+    assignment_type: AssignmentType,
+    beneficiaries: Vec<(BuilderSeal<GraphSeal>, Amount)>,
+    candidates: Vec<(Opout, PersistedState)>,
+}
+
+impl PaymentBuilder {
+    pub fn new(
+        builder: TransitionBuilder,
+        assignment_name: impl Into<FieldName>,
+    ) -> Result<Self, BuilderError> {
+        let assignment_name = assignment_name.into();
+        let assignment_type = builder
+            .assignments_type(&assignment_name)
+            .ok_or(BuilderError::AssignmentNotFound(assignment_name.clone()))?;
+        Ok(Self {
+            builder,
+            assignment_name,
+            assignment_type,
+            beneficiaries: vec![],
+            candidates: vec![],
+        })
+    }
+
+    pub fn with_default_assignment(builder: TransitionBuilder) -> Result<Self, BuilderError> {
+        let assignment_name = builder.default_assignment()?.clone();
+        Self::new(builder, assignment_name)
+    }
+
+    pub fn add_beneficiary(
+        mut self,
+        seal: impl Into<BuilderSeal<GraphSeal>>,
+        amount: impl Into<Amount>,
+    ) -> Self {
+        self.beneficiaries.push((seal.into(), amount.into()));
+        self
+    }
+
+    /// Adds state which may be spent by the payment. Only fungible state of
+    /// the payment assignment type is accepted.
+    pub fn add_candidate(
+        mut self,
+        opout: Opout,
+        state: PersistedState,
+    ) -> Result<Self, BuilderError> {
+        if opout.ty != self.assignment_type || !matches!(state, PersistedState::Amount(..)) {
+            return Err(BuilderError::InvalidStateType(opout.ty));
+        }
+        self.candidates.push((opout, state));
+        Ok(self)
+    }
+
+    /// Total amount paid to all beneficiaries, or `None` if it exceeds the
+    /// maximal possible amount.
+    pub fn amount(&self) -> Option<Amount> {
+        self.beneficiaries
+            .iter()
+            .try_fold(Amount::ZERO, |sum, (_, amount)| sum.checked_add(*amount))
+    }
+
+    /// Selects inputs with the provided coin selection strategy, assigns state
+    /// to the beneficiaries and the change (if any) to the `change` seal, and
+    /// completes the transition. Returns the transition together with the
+    /// change amount.
+    pub fn complete_payment(
+        self,
+        change: impl Into<BuilderSeal<GraphSeal>>,
+        selector: impl CoinSelect,
+    ) -> Result<(Transition, Amount), BuilderError> {
+        let required = self.amount().ok_or(BuilderError::AmountOverflow)?;
+
+        let amounts = self
+            .candidates
+            .iter()
+            .map(|(opout, state)| (*opout, state_amount(state)))
+            .collect::<Vec<_>>();
+        let Some(selection) = selector.select(&amounts, required) else {
+            let available = amounts
+                .iter()
+                .fold(Amount::ZERO, |sum, (_, amount)| sum.saturating_add(*amount));
+            return Err(BuilderError::InsufficientState {
+                required,
+                available,
+            });
+        };
+
+        let mut builder = self.builder;
+        let mut selected = Amount::ZERO;
+        for (opout, state) in self.candidates {
+            if !selection.contains(&opout) {
+                continue;
+            }
+            selected = selected
+                .checked_add(state_amount(&state))
+                .ok_or(BuilderError::AmountOverflow)?;
+            builder = builder.add_input(opout, state)?;
+        }
+        // Custom selection strategies may return insufficient set of inputs
+        let Some(change_amount) = selected.checked_sub(required) else {
+            return Err(BuilderError::InsufficientState {
+                required,
+                available: selected,
+            });
+        };
+
+        for (seal, amount) in self.beneficiaries {
+            builder = builder.add_fungible_state(self.assignment_name.clone(), seal, amount)?;
+        }
+        if change_amount > Amount::ZERO {
+            builder = builder.add_fungible_change(self.assignment_name, change, change_amount)?;
+        }
+
+        Ok((builder.complete_transition()?, change_amount))
+    }
+}
+
+fn state_amount(state: &PersistedState) -> Amount {
+    match state {
+        PersistedState::Amount(value, _, _) => *value,
+        _ => Amount::ZERO,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExtensionBuilder {
+    contract_id: ContractId,
+    builder: OperationBuilder<GenesisSeal>,
+    nonce: u64,
+    extension_type: ExtensionType,
+    redeemed: TinyOrdMap<ValencyType, OpId>,
+}
+
+impl ExtensionBuilder {
+    pub fn named_extension(
+        contract_id: ContractId,
+        iface: Iface,
+        schema: Schema,
+        iimpl: IfaceImpl,
+        extension_name: impl Into<FieldName>,
+        types: TypeSystem,
+    ) -> Result<Self, BuilderError> {
+        Self::with(
+            contract_id,
+            OperationBuilder::with(iface, schema, iimpl, types),
+            extension_name.into(),
+        )
+    }
+
+    pub fn named_extension_det(
+        contract_id: ContractId,
+        iface: Iface,
+        schema: Schema,
+        iimpl: IfaceImpl,
+        extension_name: impl Into<FieldName>,
+        types: TypeSystem,
+    ) -> Result<Self, BuilderError> {
+        Self::with(
+            contract_id,
+            OperationBuilder::deterministic(iface, schema, iimpl, types),
+            extension_name.into(),
+        )
+    }
+
+    fn with(
+        contract_id: ContractId,
+        builder: OperationBuilder<GenesisSeal>,
+        extension_name: FieldName,
+    ) -> Result<Self, BuilderError> {
+        let extension_type = builder
+            .iimpl
+            .extension_type(&extension_name)
+            .ok_or(BuilderError::ExtensionNotFound(extension_name))?;
+        Ok(Self {
+            contract_id,
+            builder,
+            nonce: u64::MAX,
+            extension_type,
+            redeemed: none!(),
+        })
+    }
+
+    pub fn type_system(&self) -> &TypeSystem { self.builder.type_system() }
+
+    pub fn extension_type(&self) -> ExtensionType { self.extension_type }
+
+    pub fn set_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    #[inline]
+    pub fn add_asset_tag_raw(
+        mut self,
+        type_id: AssignmentType,
+        asset_tag: AssetTag,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_asset_tag_raw(type_id, asset_tag)?;
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn add_metadata(
+        mut self,
+        name: impl Into<FieldName>,
+        value: impl StrictSerialize,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_metadata(name, value)?;
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn add_global_state(
+        mut self,
+        name: impl Into<FieldName>,
+        value: impl StrictSerialize,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_global_state(name, value)?;
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn valency_type(&self, name: &FieldName) -> Option<ValencyType> {
+        self.builder.valency_type(name)
+    }
+
+    /// Redeems valency `name` defined by the operation `opid` (genesis or
+    /// other state extension).
+    pub fn redeem_valency(
+        mut self,
+        name: impl Into<FieldName>,
+        opid: OpId,
+    ) -> Result<Self, BuilderError> {
+        let name = name.into();
+        let type_id = self
+            .builder
+            .valency_type(&name)
+            .ok_or(BuilderError::ValencyNotFound(name.clone()))?;
+        let redeemable = self
+            .builder
+            .schema
+            .extensions
+            .get(&self.extension_type)
+            .is_some_and(|schema| schema.redeems.contains(&type_id));
+        if !redeemable {
+            return Err(BuilderError::ValencyNotRedeemable(name));
+        }
+        self.redeemed.insert(type_id, opid)?;
+        Ok(self)
+    }
+
+    pub fn add_rights(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GenesisSeal>>,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_rights(name, seal)?;
+        Ok(self)
+    }
+
+    pub fn add_fungible_state(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GenesisSeal>>,
+        value: impl Into<Amount>,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_fungible_state(name.into(), seal, value)?;
+        Ok(self)
+    }
+
+    pub fn add_fungible_state_det(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GenesisSeal>>,
+        value: impl Into<Amount>,
+        blinding: BlindingFactor,
+    ) -> Result<Self, BuilderError> {
+        let name = name.into();
+        let type_id = self
+            .builder
+            .assignments_type(&name)
+            .ok_or(BuilderError::AssignmentNotFound(name.clone()))?;
+        let tag = self.builder.asset_tag_raw(type_id)?;
+        let state = RevealedValue::with_blinding(value.into(), blinding, tag);
+
+        self.builder = self.builder.add_fungible_state_det(name, seal, state)?;
+        Ok(self)
+    }
+
+    pub fn add_data(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GenesisSeal>>,
+        value: impl StrictSerialize,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_data(name, seal, value)?;
+        Ok(self)
+    }
+
+    pub fn add_data_det(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GenesisSeal>>,
+        data: RevealedData,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_data_det(name, seal, data)?;
+        Ok(self)
+    }
+
+    pub fn add_attachment(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GenesisSeal>>,
+        attachment: AttachState,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_attachment(name, seal, attachment)?;
+        Ok(self)
+    }
+
+    pub fn add_attachment_det(
+        mut self,
+        name: impl Into<FieldName>,
+        seal: impl Into<BuilderSeal<GenesisSeal>>,
+        attachment: RevealedAttach,
+    ) -> Result<Self, BuilderError> {
+        self.builder = self.builder.add_attachment_det(name, seal, attachment)?;
+        Ok(self)
+    }
+
+    pub fn complete_extension(self) -> Result<Extension, BuilderError> {
+        let (_, _, _, metadata, globals, assignments, _, _) = self.builder.complete(None);
+
+        Ok(Extension {
+            ffv: none!(),
+            contract_id: self.contract_id,
+            nonce: self.nonce,
+            extension_type: self.extension_type,
+            metadata,
+            globals,
+            assignments,
+            redeemed: Redeemed::from_inner(self.redeemed),
+            valencies: none!(),
+            witness: none!(),
+            validator: none!(),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct OperationBuilder<Seal: ExposedSeal> {
     // TODO: use references instead of owned values
@@ -1324,10 +2229,12 @@ impl<Seal: ExposedSeal> OperationBuilder<Seal> {
         Ok(self)
     }
 
+    #[allow(clippy::type_complexity)]
     fn complete(
         self,
         inputs: Option<&TinyOrdMap<Input, PersistedState>>,
-    ) -> (Schema, Iface, IfaceImpl, GlobalState, Assignments<Seal>, TypeSystem, AssetTags) {
+    ) -> (Schema, Iface, IfaceImpl, Metadata, GlobalState, Assignments<Seal>, TypeSystem, AssetTags)
+    {
         let owned_state = self.fungible.into_iter().map(|(id, vec)| {
             let mut blindings = Vec::with_capacity(vec.len());
             let mut vec = vec
@@ -1443,6 +2350,651 @@ impl<Seal: ExposedSeal> OperationBuilder<Seal> {
             .extend(Assignments::from_inner(owned_attachments).into_inner())
             .expect("too many assignments");
 
-        (self.schema, self.iface, self.iimpl, self.global, assignments, self.types, self.asset_tags)
+        (
+            self.schema,
+            self.iface,
+            self.iimpl,
+            self.meta,
+            self.global,
+            assignments,
+            self.types,
+            self.asset_tags,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::SmallBlob;
+    use bp::dbc::Method;
+    use bp::Vout;
+    use rgb::{ExtensionSchema, MetaValue, TransitionSchema};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::interface::{
+        AssignIface, ExtensionIface, Modifier, NamedField, OwnedIface, Req, SelectStrategy,
+        ValencyIface,
+    };
+
+    const AMOUNT: AssignmentType = AssignmentType::with(1);
+    const TRANSFER: TransitionType = TransitionType::with(1);
+    const ISSUE: ExtensionType = ExtensionType::with(1);
+    const RESERVE: ValencyType = ValencyType::with(1);
+    const OTHER: ValencyType = ValencyType::with(2);
+
+    fn contract() -> (Iface, Schema, IfaceImpl) {
+        let mut schema = Schema::strict_dumb();
+        schema.owned_types =
+            tiny_bmap! { AMOUNT => OwnedStateSchema::Fungible(FungibleType::Unsigned64Bit) };
+        schema.transitions = tiny_bmap! { TRANSFER => TransitionSchema {
+            metadata: none!(),
+            globals: none!(),
+            inputs: tiny_bmap! { AMOUNT => Occurrences::OnceOrMore },
+            assignments: tiny_bmap! { AMOUNT => Occurrences::OnceOrMore },
+            valencies: none!(),
+            validator: None,
+        } };
+        schema.valency_types = tiny_bset![RESERVE, OTHER];
+        schema.extensions = tiny_bmap! { ISSUE => ExtensionSchema {
+            metadata: none!(),
+            globals: none!(),
+            redeems: tiny_bset![RESERVE],
+            assignments: tiny_bmap! { AMOUNT => Occurrences::OnceOrMore },
+            valencies: none!(),
+            validator: None,
+        } };
+
+        let mut iface = Iface::strict_dumb();
+        iface.assignments = tiny_bmap! { fname!("amount") => AssignIface::public(OwnedIface::Amount, Req::OneOrMore) };
+        iface.transitions = tiny_bmap! { fname!("transfer") => TransitionIface {
+            modifier: Modifier::Final,
+            optional: false,
+            metadata: none!(),
+            globals: none!(),
+            inputs: tiny_bmap! { fname!("amount") => Occurrences::OnceOrMore },
+            assignments: tiny_bmap! { fname!("amount") => Occurrences::OnceOrMore },
+            valencies: none!(),
+            errors: none!(),
+            default_assignment: Some(fname!("amount")),
+        } };
+        iface.valencies = tiny_bmap! {
+            fname!("reserve") => ValencyIface { required: false },
+            fname!("other") => ValencyIface { required: false },
+        };
+        iface.extensions = tiny_bmap! { fname!("issue") => ExtensionIface {
+            modifier: Modifier::Final,
+            optional: false,
+            metadata: none!(),
+            globals: none!(),
+            assignments: tiny_bmap! { fname!("amount") => Occurrences::OnceOrMore },
+            redeems: tiny_bset![fname!("reserve")],
+            valencies: none!(),
+            errors: none!(),
+            default_assignment: Some(fname!("amount")),
+        } };
+
+        let mut iimpl = IfaceImpl::strict_dumb();
+        iimpl.schema_id = schema.schema_id();
+        iimpl.iface_id = iface.iface_id();
+        iimpl.assignments = tiny_bset![NamedField::with(AMOUNT, fname!("amount"))];
+        iimpl.transitions = tiny_bset![NamedField::with(TRANSFER, fname!("transfer"))];
+        iimpl.valencies = tiny_bset![
+            NamedField::with(RESERVE, fname!("reserve")),
+            NamedField::with(OTHER, fname!("other"))
+        ];
+        iimpl.extensions = tiny_bset![NamedField::with(ISSUE, fname!("issue"))];
+
+        (iface, schema, iimpl)
+    }
+
+    fn transfer_builder() -> TransitionBuilder {
+        let (iface, schema, iimpl) = contract();
+        TransitionBuilder::named_transition(
+            ContractId::strict_dumb(),
+            iface,
+            schema,
+            iimpl,
+            fname!("transfer"),
+            none!(),
+        )
+        .unwrap()
+        .add_asset_tag("amount", AssetTag::strict_dumb())
+        .unwrap()
+    }
+
+    fn seal(vout: u32) -> XChain<GraphSeal> {
+        XChain::Bitcoin(GraphSeal::new_random_vout(Method::OpretFirst, Vout::from_u32(vout)))
+    }
+
+    fn candidate(no: u8, amount: u64) -> (Opout, PersistedState) {
+        let opout = Opout::new(OpId::from([no; 32]), AMOUNT, 0);
+        let state = PersistedState::Amount(
+            amount.into(),
+            BlindingFactor::random(),
+            AssetTag::strict_dumb(),
+        );
+        (opout, state)
+    }
+
+    fn genesis_seal(layer1: Layer1) -> BuilderSeal<GenesisSeal> {
+        let seal = GenesisSeal::new_random(Method::OpretFirst, bp::Txid::from([1; 32]), 0u32);
+        BuilderSeal::Revealed(XChain::with(layer1, seal))
+    }
+
+    fn contract_builder() -> ContractBuilder {
+        let (iface, schema, iimpl) = contract();
+        ContractBuilder::with(Identity::default(), iface, schema, iimpl, TypeSystem::new(), none!())
+    }
+
+    #[test]
+    fn contract_chain_net() {
+        let seal = genesis_seal(Layer1::Liquid);
+        assert!(matches!(
+            contract_builder().add_fungible_state("amount", seal, 100u64),
+            Err(BuilderError::InvalidLayer1(Layer1::Liquid))
+        ));
+        let builder = contract_builder()
+            .set_chain_net(ChainNet::LiquidTestnet)
+            .unwrap();
+        assert!(builder.check_chain_net(ChainNet::BitcoinRegtest).is_ok());
+        assert!(matches!(
+            builder.check_chain_net(ChainNet::LiquidMainnet),
+            Err(BuilderError::NetworkMismatch(ChainNet::LiquidMainnet))
+        ));
+        assert!(builder.add_fungible_state("amount", seal, 100u64).is_ok());
+    }
+
+    #[test]
+    fn contract_timestamp_policy() {
+        let (iface, schema, iimpl) = contract();
+        let builder = || {
+            ContractBuilder::deterministic(
+                Identity::default(),
+                iface.clone(),
+                schema.clone(),
+                iimpl.clone(),
+                TypeSystem::new(),
+                none!(),
+            )
+        };
+        let err = TimestampError::BeforeEpoch(0);
+
+        let contract = builder().issue_contract_det(0).unwrap();
+        assert!(contract.validation_status().warnings.iter().any(
+            |warning| matches!(warning, validation::Warning::Custom(msg) if *msg == err.to_string())
+        ));
+
+        assert!(matches!(
+            builder()
+                .set_timestamp_policy(TimestampPolicy::Strict)
+                .issue_contract_det(0),
+            Err(BuilderError::Timestamp(e)) if e == err
+        ));
+    }
+
+    #[test]
+    fn contract_attachment_file() {
+        let (iface, schema, iimpl) = contract();
+        let data = b"terms and conditions".to_vec();
+        let mut hasher = Sha256::default();
+        hasher.input_raw(&data);
+        let id = AttachId::from(hasher.finish());
+        let other = AttachId::from([0xAA; 32]);
+
+        assert!(matches!(
+            contract_builder().add_attachment_file(other, data.clone()),
+            Err(BuilderError::AttachmentMismatch(mismatch)) if mismatch == other
+        ));
+
+        let contract = ContractBuilder::deterministic(
+            Identity::default(),
+            iface,
+            schema,
+            iimpl,
+            TypeSystem::new(),
+            none!(),
+        )
+        .add_attachment_file(id, data.clone())
+        .unwrap()
+        .issue_contract_det(0)
+        .unwrap();
+        assert_eq!(contract.attachments.get(&id).map(|blob| blob.as_slice()), Some(&data[..]));
+    }
+
+    #[test]
+    fn contract_chain_net_mismatch() {
+        let builder = contract_builder()
+            .set_chain_net(ChainNet::BitcoinRegtest)
+            .unwrap()
+            .set_mainnet();
+        assert!(matches!(
+            builder
+                .clone()
+                .add_fungible_state("amount", genesis_seal(Layer1::Bitcoin), 100u64),
+            Err(BuilderError::NetworkMismatch(ChainNet::BitcoinRegtest))
+        ));
+        assert!(matches!(
+            builder.issue_contract(),
+            Err(BuilderError::NetworkMismatch(ChainNet::BitcoinRegtest))
+        ));
+    }
+
+    fn transfer() -> Transition {
+        let (opout, state) = candidate(1, 10);
+        transfer_builder()
+            .add_input(opout, state)
+            .unwrap()
+            .add_fungible_state("amount", seal(1), 10u64)
+            .unwrap()
+            .complete_transition()
+            .unwrap()
+    }
+
+    #[test]
+    fn transition_schema_occurrences() {
+        let (_, schema, _) = contract();
+        let types = TypeSystem::new();
+        let transition = transfer();
+        assert_eq!(check_transition_schema(&schema, &types, &transition), Ok(()));
+
+        let mut unknown = transition.clone();
+        unknown.transition_type = TransitionType::with(2);
+        assert_eq!(
+            check_transition_schema(&schema, &types, &unknown),
+            Err(SchemaViolation::UnknownTransition(TransitionType::with(2)))
+        );
+
+        let mut no_inputs = transition.clone();
+        no_inputs.inputs = none!();
+        assert_eq!(
+            check_transition_schema(&schema, &types, &no_inputs),
+            Err(SchemaViolation::InputOccurrences {
+                ty: AMOUNT,
+                min: 1,
+                max: u16::MAX,
+                found: 0
+            })
+        );
+
+        let mut no_assignments = transition;
+        no_assignments.assignments = none!();
+        assert_eq!(
+            check_transition_schema(&schema, &types, &no_assignments),
+            Err(SchemaViolation::AssignmentOccurrences {
+                ty: AMOUNT,
+                min: 1,
+                max: u16::MAX,
+                found: 0
+            })
+        );
+    }
+
+    #[test]
+    fn transition_schema_metadata() {
+        const META: MetaType = MetaType::with(1);
+
+        let transition = transfer();
+        let (_, mut schema, _) = contract();
+        let types = TypeSystem::new();
+        let mut metadata = Metadata::default();
+        metadata
+            .add_value(META, MetaValue::from_inner(SmallBlob::from_checked(vec![1])))
+            .unwrap();
+        let mut with_metadata = transition.clone();
+        with_metadata.metadata = metadata;
+        assert_eq!(
+            check_transition_schema(&schema, &types, &with_metadata),
+            Err(SchemaViolation::UnknownMetadata(META))
+        );
+
+        schema.meta_types = tiny_bmap! { META => SemId::from([0u8; 32]) };
+        schema.transitions.get_mut(&TRANSFER).unwrap().metadata = tiny_bset![META];
+        assert_eq!(
+            check_transition_schema(&schema, &types, &transition),
+            Err(SchemaViolation::MissingMetadata(META))
+        );
+        // The type system doesn't know the metadata semantic type.
+        assert_eq!(
+            check_transition_schema(&schema, &types, &with_metadata),
+            Err(SchemaViolation::InvalidMetadata(META))
+        );
+    }
+
+    #[test]
+    fn metadata_consignment_round_trip() {
+        use std::str::FromStr;
+
+        use bp::dbc::opret::OpretProof;
+        use commit_verify::mpc;
+        use rgb::validation::DbcProof;
+        use rgb::TransitionBundle;
+
+        use crate::containers::{ClientBundle, Contract, PubWitness, WitnessBundle};
+        use crate::stl::StandardTypes;
+
+        const META: MetaType = MetaType::with(1);
+
+        let std = StandardTypes::new();
+        let sem_id = std.get("RGBContract.Amount");
+        let (mut iface, mut schema, mut iimpl) = contract();
+        schema.meta_types = tiny_bmap! { META => sem_id };
+        schema.genesis.metadata = tiny_bset![META];
+        schema.transitions.get_mut(&TRANSFER).unwrap().metadata = tiny_bset![META];
+        iface.metadata = tiny_bmap! { fname!("meta") => sem_id };
+        iface.genesis.metadata = tiny_bset![fname!("meta")];
+        iface
+            .transitions
+            .get_mut(&fname!("transfer"))
+            .unwrap()
+            .metadata = tiny_bset![fname!("meta")];
+        iimpl.schema_id = schema.schema_id();
+        iimpl.iface_id = iface.iface_id();
+        iimpl.metadata = tiny_bset![NamedField::with(META, fname!("meta"))];
+
+        let mut contract = ContractBuilder::deterministic(
+            Identity::default(),
+            iface.clone(),
+            schema.clone(),
+            iimpl.clone(),
+            std.type_system(),
+            none!(),
+        )
+        .add_metadata("meta", Amount::from(42u64))
+        .unwrap()
+        .issue_contract_det(1_700_000_000)
+        .unwrap()
+        .into_consignment();
+
+        let (opout, state) = candidate(1, 10);
+        let transition = TransitionBuilder::named_transition(
+            contract.contract_id(),
+            iface,
+            schema,
+            iimpl,
+            fname!("transfer"),
+            std.type_system(),
+        )
+        .unwrap()
+        .add_asset_tag("amount", AssetTag::strict_dumb())
+        .unwrap()
+        .add_metadata("meta", Amount::from(43u64))
+        .unwrap()
+        .add_input(opout, state)
+        .unwrap()
+        .add_fungible_state("amount", seal(1), 10u64)
+        .unwrap()
+        .complete_transition()
+        .unwrap();
+        let mut bundle = TransitionBundle::strict_dumb();
+        bundle.known_transitions =
+            Confined::from_checked(bmap! { transition.id() => transition.clone() });
+        contract.bundles = Confined::from_checked(vec![WitnessBundle::with(
+            XChain::Bitcoin(PubWitness::new(bp::Txid::from([1; 32]))),
+            ClientBundle::new(
+                mpc::MerkleProof::default(),
+                DbcProof::Opret(OpretProof::default()),
+                bundle,
+            ),
+        )]);
+
+        let restored = Contract::from_str(&contract.to_string()).unwrap();
+        let meta = restored.genesis.metadata.get(&META).unwrap();
+        assert_eq!(meta.as_slice(), 42u64.to_le_bytes());
+        let restored = restored
+            .bundles
+            .iter()
+            .flat_map(|wb| wb.known_transitions())
+            .next()
+            .unwrap();
+        assert_eq!(restored.id(), transition.id());
+        let meta = restored.metadata.get(&META).unwrap();
+        assert_eq!(meta.as_slice(), 43u64.to_le_bytes());
+    }
+
+    #[test]
+    fn blank_transition_preserves_state() {
+        let (iface, schema, iimpl) = contract();
+        #[allow(deprecated)]
+        let builder = TransitionBuilder::blank_transition(
+            ContractId::strict_dumb(),
+            iface,
+            schema,
+            iimpl,
+            none!(),
+        );
+        let (opout, state) = candidate(1, 42);
+        let PersistedState::Amount(_, blinding, _) = state else {
+            unreachable!()
+        };
+        let transition = builder
+            .add_asset_tag_raw(AMOUNT, AssetTag::strict_dumb())
+            .unwrap()
+            .add_input(opout, state, seal(1))
+            .unwrap()
+            .complete_transition()
+            .unwrap();
+
+        assert_eq!(transition.transition_type, TransitionType::BLANK);
+        assert_eq!(transition.inputs.len(), 1);
+        assert!(transition
+            .inputs
+            .iter()
+            .any(|input| input.prev_out == opout));
+        let TypedAssigns::Fungible(assigns) = transition.assignments.get(&AMOUNT).unwrap() else {
+            panic!("fungible state expected");
+        };
+        assert_eq!(assigns.len(), 1);
+        let value = assigns.first().unwrap().as_revealed_state().unwrap();
+        assert_eq!(value.value.as_u64(), 42);
+        assert_eq!(value.blinding, blinding);
+        assert_eq!(value.tag, AssetTag::strict_dumb());
+    }
+
+    #[test]
+    fn extension_redeems_valency() {
+        let (iface, schema, iimpl) = contract();
+        assert!(matches!(
+            ExtensionBuilder::named_extension_det(
+                ContractId::strict_dumb(),
+                iface.clone(),
+                schema.clone(),
+                iimpl.clone(),
+                fname!("unknown"),
+                none!(),
+            ),
+            Err(BuilderError::ExtensionNotFound(name)) if name == fname!("unknown")
+        ));
+
+        let builder = ExtensionBuilder::named_extension(
+            ContractId::strict_dumb(),
+            iface,
+            schema,
+            iimpl,
+            fname!("issue"),
+            none!(),
+        )
+        .unwrap()
+        .add_asset_tag_raw(AMOUNT, AssetTag::strict_dumb())
+        .unwrap();
+        assert_eq!(builder.extension_type(), ISSUE);
+
+        let opid = OpId::from([0xAA; 32]);
+        assert!(matches!(
+            builder.clone().redeem_valency("other", opid),
+            Err(BuilderError::ValencyNotRedeemable(name)) if name == fname!("other")
+        ));
+        assert!(matches!(
+            builder.clone().redeem_valency("unknown", opid),
+            Err(BuilderError::ValencyNotFound(name)) if name == fname!("unknown")
+        ));
+
+        let seal = XChain::Bitcoin(GenesisSeal::new_random(
+            Method::OpretFirst,
+            bp::Txid::from([0x01; 32]),
+            0u32,
+        ));
+        let extension = builder
+            .redeem_valency("reserve", opid)
+            .unwrap()
+            .add_fungible_state("amount", seal, 100u64)
+            .unwrap()
+            .complete_extension()
+            .unwrap();
+        assert_eq!(extension.extension_type, ISSUE);
+        assert_eq!(extension.redeemed.len(), 1);
+        assert_eq!(extension.redeemed.get(&RESERVE), Some(&opid));
+        assert_eq!(extension.assignments.get(&AMOUNT).unwrap().len_u16(), 1);
+    }
+
+    /// Selects just the first candidate, even if it is insufficient.
+    struct SelectFirst;
+    impl CoinSelect for SelectFirst {
+        fn select<T: Copy + Ord>(&self, candidates: &[(T, Amount)], _: Amount) -> Option<Vec<T>> {
+            candidates.first().map(|(id, _)| vec![*id])
+        }
+    }
+
+    /// Selects all candidates.
+    struct SelectAll;
+    impl CoinSelect for SelectAll {
+        fn select<T: Copy + Ord>(&self, candidates: &[(T, Amount)], _: Amount) -> Option<Vec<T>> {
+            Some(candidates.iter().map(|(id, _)| *id).collect())
+        }
+    }
+
+    fn payment(candidates: &[(u8, u64)]) -> PaymentBuilder {
+        let mut payment = PaymentBuilder::with_default_assignment(transfer_builder()).unwrap();
+        for (no, amount) in candidates {
+            let (opout, state) = candidate(*no, *amount);
+            payment = payment.add_candidate(opout, state).unwrap();
+        }
+        payment
+    }
+
+    #[test]
+    fn payment_with_change() {
+        let (transition, change) = payment(&[(1, 50), (2, 30), (3, 20)])
+            .add_beneficiary(seal(1), 40u64)
+            .add_beneficiary(seal(2), 20u64)
+            .complete_payment(seal(3), SelectStrategy::SmallestFirst)
+            .unwrap();
+        assert_eq!(change, Amount::from(10u64));
+        assert_eq!(transition.inputs.len(), 3);
+        let TypedAssigns::Fungible(assigns) = transition.assignments.get(&AMOUNT).unwrap() else {
+            panic!("fungible state expected");
+        };
+        assert_eq!(assigns.len(), 3);
+        let total = assigns
+            .iter()
+            .filter_map(|a| a.as_revealed_state())
+            .map(|state| state.value.as_u64())
+            .sum::<u64>();
+        assert_eq!(total, 100);
+    }
+
+    #[test]
+    fn payment_without_change() {
+        let (transition, change) = payment(&[(1, 50), (2, 30)])
+            .add_beneficiary(seal(1), 50u64)
+            .complete_payment(seal(2), SelectStrategy::ExactMatch)
+            .unwrap();
+        assert_eq!(change, Amount::ZERO);
+        assert_eq!(transition.inputs.len(), 1);
+        assert_eq!(transition.assignments.get(&AMOUNT).unwrap().len_u16(), 1);
+    }
+
+    #[test]
+    fn payment_insufficient() {
+        let err = payment(&[(1, 50), (2, 30)])
+            .add_beneficiary(seal(1), 81u64)
+            .complete_payment(seal(2), SelectStrategy::LargestFirst)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuilderError::InsufficientState { required, available }
+                if required == Amount::from(81u64) && available == Amount::from(80u64)
+        ));
+    }
+
+    #[test]
+    fn payment_insufficient_selection() {
+        let err = payment(&[(1, 50), (2, 30)])
+            .add_beneficiary(seal(1), 60u64)
+            .complete_payment(seal(2), SelectFirst)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuilderError::InsufficientState { required, available }
+                if required == Amount::from(60u64) && available == Amount::from(50u64)
+        ));
+    }
+
+    #[test]
+    fn payment_overflow() {
+        let builder = payment(&[(1, u64::MAX), (2, 1)])
+            .add_beneficiary(seal(1), u64::MAX)
+            .add_beneficiary(seal(2), 1u64);
+        assert_eq!(builder.amount(), None);
+        assert!(matches!(
+            builder.complete_payment(seal(3), SelectStrategy::LargestFirst),
+            Err(BuilderError::AmountOverflow)
+        ));
+
+        let err = payment(&[(1, u64::MAX), (2, 1)])
+            .add_beneficiary(seal(1), u64::MAX)
+            .complete_payment(seal(3), SelectAll)
+            .unwrap_err();
+        assert!(matches!(err, BuilderError::AmountOverflow));
+    }
+
+    fn restricted_payment(candidates: &[(u8, u64)]) -> PaymentBuilder {
+        let builder = transfer_builder()
+            .set_min_amount(10u64)
+            .set_granularity(Precision::Deci);
+        let mut payment = PaymentBuilder::with_default_assignment(builder).unwrap();
+        for (no, amount) in candidates {
+            let (opout, state) = candidate(*no, *amount);
+            payment = payment.add_candidate(opout, state).unwrap();
+        }
+        payment
+    }
+
+    #[test]
+    fn payment_change_allocation_exempt() {
+        // The change of 3 is both dust and not a whole number of tens.
+        let (transition, change) = restricted_payment(&[(1, 53)])
+            .add_beneficiary(seal(1), 50u64)
+            .complete_payment(seal(2), SelectStrategy::LargestFirst)
+            .unwrap();
+        assert_eq!(change, Amount::from(3u64));
+        assert_eq!(transition.assignments.get(&AMOUNT).unwrap().len_u16(), 2);
+    }
+
+    #[test]
+    fn payment_dust_allocation() {
+        let err = restricted_payment(&[(1, 60)])
+            .add_beneficiary(seal(1), 50u64)
+            .add_beneficiary(seal(2), 5u64)
+            .complete_payment(seal(3), SelectStrategy::LargestFirst)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuilderError::DustAllocation { ty, amount, min }
+                if ty == AMOUNT && amount == Amount::from(5u64) && min == Amount::from(10u64)
+        ));
+    }
+
+    #[test]
+    fn payment_allocation_granularity() {
+        let err = restricted_payment(&[(1, 60)])
+            .add_beneficiary(seal(1), 15u64)
+            .complete_payment(seal(2), SelectStrategy::LargestFirst)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuilderError::AmountGranularity { ty, amount, granularity: 10 }
+                if ty == AMOUNT && amount == Amount::from(15u64)
+        ));
     }
 }
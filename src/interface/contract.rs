@@ -20,9 +20,11 @@
 // limitations under the License.
 
 use std::borrow::Borrow;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
 
 use invoice::{Allocation, Amount};
+use rgb::vm::WitnessOrd;
 use rgb::{
     AssignmentType, AttachState, ContractId, DataState, OpId, RevealedAttach, RevealedData,
     RevealedValue, Schema, VoidState, XOutpoint, XOutputSeal, XWitnessId,
@@ -32,7 +34,7 @@ use strict_types::{StrictVal, TypeSystem};
 
 use crate::contract::{KnownState, OutputAssignment, WitnessInfo};
 use crate::info::ContractInfo;
-use crate::interface::{AssignmentsFilter, IfaceImpl};
+use crate::interface::{AssignmentsFilter, IfaceId, IfaceImpl};
 use crate::persistence::ContractStateRead;
 use crate::LIB_NAME_RGB_STD;
 
@@ -87,6 +89,42 @@ impl AllocatedState {
             _ => panic!("unwrapping non-fungible state"),
         }
     }
+
+    /// Returns a display wrapper showing the kind of the state, but not its
+    /// value (see [`Redacted`]).
+    pub fn redacted(&self) -> Redacted<'_, Self> { Redacted(self) }
+}
+
+/// Display wrapper hiding amounts and data, while keeping the structure of the
+/// displayed state or history intact. Used to render wallet state for
+/// screenshots and bug reports without disclosing the holdings.
+#[derive(Copy, Clone, Debug)]
+pub struct Redacted<'a, T>(&'a T);
+
+impl Display for Redacted<'_, AllocatedState> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            AllocatedState::Void => f.write_str("~"),
+            AllocatedState::Amount(_) => f.write_str("***"),
+            AllocatedState::Data(_) => f.write_str("<data>"),
+            AllocatedState::Attachment(_) => f.write_str("<attachment>"),
+        }
+    }
+}
+
+impl Display for Redacted<'_, ContractOp> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let op = self.0;
+        write!(f, "{}\t{}\t{}\t", op.direction, op.ty, op.state.redacted())?;
+        match op.to.len() {
+            1 => f.write_str("1 output")?,
+            n => write!(f, "{n} outputs")?,
+        }
+        match &op.witness {
+            Some(witness) => write!(f, "\t{}\t{}", witness.id, witness.ord),
+            None => f.write_str("\t~"),
+        }
+    }
 }
 
 pub type OwnedAllocation = OutputAssignment<AllocatedState>;
@@ -135,6 +173,10 @@ fn reduce_to_ty(allocations: impl IntoIterator<Item = OwnedAllocation>) -> Assig
 }
 
 impl ContractOp {
+    /// Returns a display wrapper rendering the operation without its state
+    /// value and beneficiary seals (see [`Redacted`]).
+    pub fn redacted(&self) -> Redacted<'_, Self> { Redacted(self) }
+
     fn non_fungible_genesis(
         our_allocations: HashSet<OwnedAllocation>,
     ) -> impl ExactSizeIterator<Item = Self> {
@@ -386,6 +428,34 @@ impl<S: ContractStateRead> ContractIface<S> {
             .collect()
     }
 
+    /// Returns contract operations from the wallet history (see
+    /// [`Self::history`]) which witness transactions were mined within the
+    /// provided inclusive range of block heights.
+    ///
+    /// Issue operations (which have no witness) are included only if the range
+    /// starts at height zero; operations with unmined witnesses are always
+    /// excluded.
+    pub fn history_between(
+        &self,
+        filter_outpoints: impl AssignmentsFilter + Clone,
+        filter_witnesses: impl AssignmentsFilter + Clone,
+        from_height: u32,
+        to_height: u32,
+    ) -> Vec<ContractOp> {
+        let range = from_height..=to_height;
+        self.history(filter_outpoints, filter_witnesses)
+            .into_iter()
+            .filter(|op| match op.witness {
+                None => from_height == 0,
+                Some(WitnessInfo {
+                    ord: WitnessOrd::Mined(pos),
+                    ..
+                }) => range.contains(&pos.height().get()),
+                Some(_) => false,
+            })
+            .collect()
+    }
+
     fn operations<'c, T: KnownState + 'c, I: Iterator<Item = &'c OutputAssignment<T>>>(
         &'c self,
         state: impl Fn(&'c S) -> I,
@@ -524,4 +594,146 @@ impl<S: ContractStateRead> ContractIface<S> {
             ord,
         })
     }
+
+    /// Constructs read-only view of the contract state in terms of the
+    /// interface, including owned state passing the filter.
+    pub fn state_view(
+        &self,
+        filter: impl AssignmentsFilter + Copy,
+    ) -> Result<IfaceStateView, ContractError> {
+        let mut global = BTreeMap::new();
+        for field in &self.iface.global_state {
+            let values = self.global(field.name.clone())?.collect();
+            global.insert(field.name.clone(), values);
+        }
+
+        let mut owned = BTreeMap::<FieldName, Vec<OwnedAllocation>>::new();
+        for allocation in self.allocations(filter) {
+            if let Some(name) = self.iface.assignment_name(allocation.opout.ty) {
+                owned.entry(name.clone()).or_default().push(allocation);
+            }
+        }
+
+        Ok(IfaceStateView {
+            contract_id: self.contract_id(),
+            iface_id: self.iface.iface_id,
+            global,
+            owned,
+        })
+    }
+}
+
+/// Read-only snapshot of the contract state expressed in terms of an
+/// interface, see [`ContractIface::state_view`].
+///
+/// State is named after the interface fields and global state is
+/// deserialized into its semantic types, such that explorers and wallets may
+/// read contracts without knowing their schema.
+#[derive(Clone, Debug)]
+pub struct IfaceStateView {
+    pub contract_id: ContractId,
+    pub iface_id: IfaceId,
+    pub global: BTreeMap<FieldName, Vec<StrictVal>>,
+    pub owned: BTreeMap<FieldName, Vec<OwnedAllocation>>,
+}
+
+impl IfaceStateView {
+    /// Returns all values of the global state with the given name.
+    pub fn global(&self, name: &FieldName) -> &[StrictVal] {
+        self.global.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns the first value of the global state with the given name, which
+    /// is useful for the state holding a single value (like asset nominal
+    /// data).
+    pub fn global_value(&self, name: &FieldName) -> Option<&StrictVal> { self.global(name).first() }
+
+    /// Returns allocations of the owned state with the given name.
+    pub fn owned(&self, name: &FieldName) -> &[OwnedAllocation] {
+        self.owned.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns the sum of fungible state allocated under the given name.
+    pub fn balance(&self, name: &FieldName) -> Amount {
+        self.owned(name)
+            .iter()
+            .filter_map(|allocation| match allocation.state {
+                AllocatedState::Amount(amount) => Some(amount),
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Returns balances of all owned state holding fungible allocations.
+    pub fn balances(&self) -> BTreeMap<FieldName, Amount> {
+        self.owned
+            .iter()
+            .filter(|(_, allocations)| {
+                allocations
+                    .iter()
+                    .any(|a| matches!(a.state, AllocatedState::Amount(_)))
+            })
+            .map(|(name, _)| (name.clone(), self.balance(name)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rgb::Opout;
+    use strict_types::svstr;
+
+    use super::*;
+
+    fn allocation(no: u16, state: AllocatedState) -> OwnedAllocation {
+        OutputAssignment {
+            opout: Opout::new(OpId::strict_dumb(), AssignmentType::with(1), no),
+            seal: XOutputSeal::strict_dumb(),
+            state,
+            witness: None,
+        }
+    }
+
+    fn state_view() -> IfaceStateView {
+        IfaceStateView {
+            contract_id: ContractId::strict_dumb(),
+            iface_id: IfaceId::strict_dumb(),
+            global: bmap! {
+                fname!("ticker") => vec![svstr!("TCKR")],
+                fname!("data") => vec![svstr!("first"), svstr!("second")],
+            },
+            owned: bmap! {
+                fname!("assetOwner") => vec![
+                    allocation(0, AllocatedState::Amount(Amount::from(5u64))),
+                    allocation(1, AllocatedState::Amount(Amount::from(7u64))),
+                ],
+                fname!("inflationAllowance") => vec![
+                    allocation(2, AllocatedState::Amount(Amount::ZERO)),
+                ],
+                fname!("burnRight") => vec![allocation(3, AllocatedState::Void)],
+            },
+        }
+    }
+
+    #[test]
+    fn global_value() {
+        let view = state_view();
+        assert_eq!(view.global_value(&fname!("ticker")), Some(&svstr!("TCKR")));
+        assert_eq!(view.global_value(&fname!("data")), Some(&svstr!("first")));
+        assert_eq!(view.global(&fname!("data")).len(), 2);
+        assert_eq!(view.global_value(&fname!("name")), None);
+        assert!(view.global(&fname!("name")).is_empty());
+    }
+
+    #[test]
+    fn balances() {
+        let view = state_view();
+        assert_eq!(view.balance(&fname!("assetOwner")), Amount::from(12u64));
+        assert_eq!(view.balance(&fname!("burnRight")), Amount::ZERO);
+        assert_eq!(view.balance(&fname!("unknown")), Amount::ZERO);
+        assert_eq!(view.balances(), bmap! {
+            fname!("assetOwner") => Amount::from(12u64),
+            fname!("inflationAllowance") => Amount::ZERO,
+        });
+    }
 }
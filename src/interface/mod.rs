@@ -28,14 +28,18 @@ mod iimpl;
 mod contract;
 mod builder;
 mod filter;
+mod select;
 pub(crate) mod resolver;
 mod contractum;
 mod inheritance;
 
-pub use builder::{BuilderError, ContractBuilder, TransitionBuilder, TxOutpoint};
+pub use builder::{
+    BlankTransitionBuilder, BuilderError, ContractBuilder, ExtensionBuilder, PaymentBuilder,
+    SchemaViolation, TransitionBuilder, TxOutpoint,
+};
 pub use contract::{
     AllocatedState, AttachAllocation, ContractError, ContractIface, ContractOp, DataAllocation,
-    FungibleAllocation, OpDirection, OwnedAllocation, RightsAllocation,
+    FungibleAllocation, IfaceStateView, OpDirection, OwnedAllocation, Redacted, RightsAllocation,
 };
 pub use contractum::IfaceDisplay;
 pub use filter::{AssignmentsFilter, FilterExclude, FilterIncludeAll};
@@ -46,6 +50,10 @@ pub use iface::{
 };
 pub use iimpl::{IfaceImpl, ImplId, NamedField, NamedType, NamedVariant, SchemaTypeIndex};
 pub use inheritance::{CheckInheritance, ExtensionError, InheritanceFailure};
+pub use resolver::ConfirmedResolver;
+#[cfg(feature = "async")]
+pub use resolver::{AsyncResolveWitness, BlockingResolver};
+pub use select::{CoinSelect, SelectStrategy};
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
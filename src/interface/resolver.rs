@@ -19,6 +19,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "async")]
+use std::collections::HashMap;
+#[cfg(feature = "async")]
+use std::future::Future;
+
 use rgb::validation::{ResolveWitness, WitnessResolverError};
 use rgb::vm::{WitnessOrd, XWitnessTx};
 use strict_encoding::StrictDumb;
@@ -36,3 +41,356 @@ impl ResolveWitness for DumbResolver {
         Ok(WitnessOrd::strict_dumb())
     }
 }
+
+/// Witness resolver accepting only witnesses mined with at least the given
+/// number of confirmations.
+///
+/// Wraps a wallet resolver when accepting transfers, such that the state
+/// from witness transactions which are not yet (sufficiently) mined doesn't
+/// get into the stock: such witnesses fail to resolve, aborting the
+/// consumption.
+#[derive(Clone, Debug)]
+pub struct ConfirmedResolver<R: ResolveWitness> {
+    inner: R,
+    tip_height: u32,
+    min_confirmations: u32,
+}
+
+impl<R: ResolveWitness> ConfirmedResolver<R> {
+    /// Constructs the resolver for the blockchain with the tip at
+    /// `tip_height`.
+    pub fn new(inner: R, tip_height: u32, min_confirmations: u32) -> Self {
+        Self {
+            inner,
+            tip_height,
+            min_confirmations,
+        }
+    }
+
+    pub fn into_inner(self) -> R { self.inner }
+}
+
+impl<R: ResolveWitness> ResolveWitness for ConfirmedResolver<R> {
+    fn resolve_pub_witness(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<XWitnessTx, WitnessResolverError> {
+        self.inner.resolve_pub_witness(witness_id)
+    }
+
+    fn resolve_pub_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        let ord = self.inner.resolve_pub_witness_ord(witness_id)?;
+        let confirmations = match ord {
+            WitnessOrd::Mined(pos) => self
+                .tip_height
+                .saturating_add(1)
+                .saturating_sub(pos.height().get()),
+            _ => 0,
+        };
+        if confirmations < self.min_confirmations {
+            return Err(WitnessResolverError::Other(
+                witness_id,
+                format!(
+                    "witness has {confirmations} confirmations while at least {} are required",
+                    self.min_confirmations
+                ),
+            ));
+        }
+        Ok(ord)
+    }
+}
+
+/// Asynchronous version of [`ResolveWitness`], for resolvers backed by
+/// asynchronous network clients.
+///
+/// Any blocking resolver is also an asynchronous one; an asynchronous resolver
+/// can be used where a blocking one is required by wrapping it into
+/// [`BlockingResolver`]. The returned futures are `Send`, such that they can be
+/// polled from multi-threaded runtimes.
+#[cfg(feature = "async")]
+pub trait AsyncResolveWitness {
+    fn resolve_pub_witness_async(
+        &self,
+        witness_id: XWitnessId,
+    ) -> impl Future<Output = Result<XWitnessTx, WitnessResolverError>> + Send;
+
+    fn resolve_pub_witness_ord_async(
+        &self,
+        witness_id: XWitnessId,
+    ) -> impl Future<Output = Result<WitnessOrd, WitnessResolverError>> + Send;
+}
+
+#[cfg(feature = "async")]
+impl<R: ResolveWitness + Sync> AsyncResolveWitness for R {
+    fn resolve_pub_witness_async(
+        &self,
+        witness_id: XWitnessId,
+    ) -> impl Future<Output = Result<XWitnessTx, WitnessResolverError>> + Send {
+        async move { self.resolve_pub_witness(witness_id) }
+    }
+
+    fn resolve_pub_witness_ord_async(
+        &self,
+        witness_id: XWitnessId,
+    ) -> impl Future<Output = Result<WitnessOrd, WitnessResolverError>> + Send {
+        async move { self.resolve_pub_witness_ord(witness_id) }
+    }
+}
+
+/// Witnesses resolved in advance with an asynchronous resolver, such that the
+/// synchronous validation and consumption of a consignment doesn't wait for
+/// network requests.
+///
+/// Resolution failures are kept and reported when the witness is requested,
+/// such that a single missing witness is reported by the validation together
+/// with the other issues, as it happens with synchronous resolvers. Witnesses
+/// which were not prefetched are reported as unknown.
+#[cfg(feature = "async")]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PrefetchedResolver {
+    txes: HashMap<XWitnessId, Result<XWitnessTx, WitnessResolverError>>,
+    ords: HashMap<XWitnessId, Result<WitnessOrd, WitnessResolverError>>,
+}
+
+#[cfg(feature = "async")]
+impl PrefetchedResolver {
+    pub(crate) async fn prefetch(
+        resolver: &(impl AsyncResolveWitness + Sync),
+        witness_ids: impl IntoIterator<Item = XWitnessId>,
+    ) -> Self {
+        let mut prefetched = Self::default();
+        for witness_id in witness_ids {
+            if prefetched.ords.contains_key(&witness_id) {
+                continue;
+            }
+            let tx = resolver.resolve_pub_witness_async(witness_id).await;
+            let ord = match &tx {
+                Ok(_) => resolver.resolve_pub_witness_ord_async(witness_id).await,
+                Err(err) => Err(err.clone()),
+            };
+            prefetched.txes.insert(witness_id, tx);
+            prefetched.ords.insert(witness_id, ord);
+        }
+        prefetched
+    }
+}
+
+#[cfg(feature = "async")]
+impl ResolveWitness for PrefetchedResolver {
+    fn resolve_pub_witness(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<XWitnessTx, WitnessResolverError> {
+        self.txes
+            .get(&witness_id)
+            .cloned()
+            .unwrap_or(Err(WitnessResolverError::Unknown(witness_id)))
+    }
+
+    fn resolve_pub_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        self.ords
+            .get(&witness_id)
+            .cloned()
+            .unwrap_or(Err(WitnessResolverError::Unknown(witness_id)))
+    }
+}
+
+/// Adapter using an [`AsyncResolveWitness`] where a blocking [`ResolveWitness`]
+/// is required, for instance during validation.
+///
+/// When called from within a multi-threaded tokio runtime, each call is driven
+/// to completion by the runtime (see [`tokio::task::block_in_place`]), such
+/// that the wrapped resolver may use runtime resources. Otherwise the call is
+/// driven to completion on the current thread; in this case the wrapped
+/// resolver must not require being polled from within a runtime context.
+#[cfg(feature = "async")]
+#[derive(Clone, Debug)]
+pub struct BlockingResolver<R: AsyncResolveWitness>(pub R);
+
+#[cfg(feature = "async")]
+impl<R: AsyncResolveWitness> ResolveWitness for BlockingResolver<R> {
+    fn resolve_pub_witness(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<XWitnessTx, WitnessResolverError> {
+        block_on(self.0.resolve_pub_witness_async(witness_id))
+    }
+
+    fn resolve_pub_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        block_on(self.0.resolve_pub_witness_ord_async(witness_id))
+    }
+}
+
+#[cfg(feature = "async")]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use tokio::runtime::{Handle, RuntimeFlavor};
+
+        // Runtime worker may be blocked only in a multi-threaded runtime, and
+        // only after its other tasks are handed over to the rest of the workers.
+        if let Ok(handle) = Handle::try_current() {
+            if handle.runtime_flavor() == RuntimeFlavor::MultiThread {
+                return tokio::task::block_in_place(|| handle.block_on(future));
+            }
+        }
+    }
+    park_on(future)
+}
+
+#[cfg(feature = "async")]
+fn park_on<F: Future>(future: F) -> F::Output {
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) { self.0.unpark() }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU32;
+
+    use amplify::ByteArray;
+    use bp::Txid;
+    use rgb::vm::WitnessPos;
+    use rgb::XChain;
+
+    use super::*;
+
+    struct MinedResolver(Option<u32>);
+    impl ResolveWitness for MinedResolver {
+        fn resolve_pub_witness(&self, _: XWitnessId) -> Result<XWitnessTx, WitnessResolverError> {
+            Ok(XWitnessTx::strict_dumb())
+        }
+        fn resolve_pub_witness_ord(
+            &self,
+            _: XWitnessId,
+        ) -> Result<WitnessOrd, WitnessResolverError> {
+            Ok(match self.0 {
+                Some(height) => WitnessOrd::Mined(
+                    WitnessPos::bitcoin(NonZeroU32::new(height).unwrap(), 1_700_000_000).unwrap(),
+                ),
+                None => WitnessOrd::Tentative,
+            })
+        }
+    }
+
+    #[test]
+    fn confirmed_resolver() {
+        let witness_id = XChain::Bitcoin(Txid::from_byte_array([0x11; 32]));
+        let confirmed = |mined, tip, min| {
+            ConfirmedResolver::new(MinedResolver(mined), tip, min)
+                .resolve_pub_witness_ord(witness_id)
+                .is_ok()
+        };
+        assert!(confirmed(Some(95), 100, 6));
+        assert!(!confirmed(Some(96), 100, 6));
+        assert!(confirmed(Some(100), 100, 1));
+        assert!(!confirmed(None, 100, 1));
+        assert!(confirmed(None, 100, 0));
+        // Tip at the maximal height must not overflow.
+        assert!(confirmed(Some(u32::MAX - 9), u32::MAX, 6));
+        assert!(!confirmed(Some(u32::MAX), u32::MAX, 6));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn prefetched_resolver() {
+        let known = XChain::Bitcoin(Txid::from_byte_array([0x11; 32]));
+        let unknown = XChain::Bitcoin(Txid::from_byte_array([0x22; 32]));
+        let resolver = block_on(PrefetchedResolver::prefetch(&DumbResolver, [known, known]));
+        assert_eq!(resolver.resolve_pub_witness_ord(known), Ok(WitnessOrd::strict_dumb()));
+        assert_eq!(resolver.resolve_pub_witness(known), Ok(XWitnessTx::strict_dumb()));
+        assert_eq!(
+            resolver.resolve_pub_witness_ord(unknown),
+            Err(WitnessResolverError::Unknown(unknown))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn prefetched_resolver_failure() {
+        struct OfflineResolver(XWitnessId);
+        impl ResolveWitness for OfflineResolver {
+            fn resolve_pub_witness(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<XWitnessTx, WitnessResolverError> {
+                if witness_id == self.0 {
+                    return Err(WitnessResolverError::Other(witness_id, s!("offline")));
+                }
+                Ok(XWitnessTx::strict_dumb())
+            }
+            fn resolve_pub_witness_ord(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<WitnessOrd, WitnessResolverError> {
+                if witness_id == self.0 {
+                    return Err(WitnessResolverError::Other(witness_id, s!("offline")));
+                }
+                Ok(WitnessOrd::strict_dumb())
+            }
+        }
+
+        let known = XChain::Bitcoin(Txid::from_byte_array([0x11; 32]));
+        let offline = XChain::Bitcoin(Txid::from_byte_array([0x22; 32]));
+        let resolver =
+            block_on(PrefetchedResolver::prefetch(&OfflineResolver(offline), [offline, known]));
+        // The failure of one witness doesn't prevent resolving the others.
+        assert_eq!(resolver.resolve_pub_witness(known), Ok(XWitnessTx::strict_dumb()));
+        assert_eq!(resolver.resolve_pub_witness_ord(known), Ok(WitnessOrd::strict_dumb()));
+        let err = WitnessResolverError::Other(offline, s!("offline"));
+        assert_eq!(resolver.resolve_pub_witness(offline), Err(err.clone()));
+        assert_eq!(resolver.resolve_pub_witness_ord(offline), Err(err));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn block_on_outside_runtime() {
+        let witness_id = XChain::Bitcoin(Txid::from_byte_array([0x11; 32]));
+        let resolver = BlockingResolver(DumbResolver);
+        assert_eq!(resolver.resolve_pub_witness_ord(witness_id), Ok(WitnessOrd::strict_dumb()));
+    }
+
+    #[test]
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    fn block_on_within_runtime() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        // The inner task may be run only by the runtime worker which is blocked
+        // by the outer one.
+        let value = runtime.block_on(async {
+            tokio::spawn(async { block_on(async { tokio::spawn(async { 42 }).await.unwrap() }) })
+                .await
+                .unwrap()
+        });
+        assert_eq!(value, 42);
+    }
+}
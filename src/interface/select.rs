@@ -0,0 +1,159 @@
+// RGB standard library for working with smart contracts on Bitcoin & Lightning
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Reverse;
+
+use invoice::Amount;
+use rand::seq::SliceRandom;
+
+/// Strategy selecting which fungible allocations (or outputs holding them)
+/// are spent to cover a payment.
+pub trait CoinSelect {
+    /// Selects a subset of `candidates` with the total amount not less than
+    /// `target`. Returns `None` if the candidates are not sufficient.
+    fn select<T: Copy + Ord>(&self, candidates: &[(T, Amount)], target: Amount) -> Option<Vec<T>>;
+}
+
+/// Built-in coin selection strategies.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+#[display(lowercase)]
+pub enum SelectStrategy {
+    /// Spends the smallest allocations first, consolidating dust.
+    SmallestFirst,
+
+    /// Spends the largest allocations first, minimizing the number of inputs.
+    #[default]
+    LargestFirst,
+
+    /// Spends a single allocation exactly matching the target, if present,
+    /// avoiding change. Falls back to [`SelectStrategy::LargestFirst`]
+    /// otherwise.
+    ExactMatch,
+
+    /// Spends allocations in random order, making wallet behaviour less
+    /// fingerprintable.
+    Random,
+}
+
+impl CoinSelect for SelectStrategy {
+    fn select<T: Copy + Ord>(&self, candidates: &[(T, Amount)], target: Amount) -> Option<Vec<T>> {
+        let mut candidates = candidates.to_vec();
+        match self {
+            SelectStrategy::SmallestFirst => candidates.sort_by_key(|(id, amount)| (*amount, *id)),
+            SelectStrategy::LargestFirst => {
+                candidates.sort_by_key(|(id, amount)| (Reverse(*amount), *id))
+            }
+            SelectStrategy::ExactMatch => {
+                candidates.sort_by_key(|(id, amount)| (Reverse(*amount), *id));
+                if let Some((id, _)) = candidates.iter().find(|(_, amount)| *amount == target) {
+                    return Some(vec![*id]);
+                }
+            }
+            SelectStrategy::Random => candidates.shuffle(&mut rand::thread_rng()),
+        }
+        accumulate(candidates, target)
+    }
+}
+
+fn accumulate<T>(
+    candidates: impl IntoIterator<Item = (T, Amount)>,
+    target: Amount,
+) -> Option<Vec<T>> {
+    let mut sum = Amount::ZERO;
+    let mut selected = vec![];
+    for (id, amount) in candidates {
+        if sum >= target {
+            break;
+        }
+        sum.saturating_add_assign(amount);
+        selected.push(id);
+    }
+    if sum < target {
+        return None;
+    }
+    Some(selected)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidates() -> Vec<(u8, Amount)> {
+        vec![(1, 30u64), (2, 10), (3, 50), (4, 20)]
+            .into_iter()
+            .map(|(id, amount)| (id, Amount::from(amount)))
+            .collect()
+    }
+
+    #[test]
+    fn smallest_first() {
+        let selected = SelectStrategy::SmallestFirst.select(&candidates(), Amount::from(35u64));
+        assert_eq!(selected, Some(vec![2, 4, 1]));
+    }
+
+    #[test]
+    fn largest_first() {
+        let selected = SelectStrategy::LargestFirst.select(&candidates(), Amount::from(60u64));
+        assert_eq!(selected, Some(vec![3, 1]));
+    }
+
+    #[test]
+    fn exact_match() {
+        let selected = SelectStrategy::ExactMatch.select(&candidates(), Amount::from(20u64));
+        assert_eq!(selected, Some(vec![4]));
+        // Falls back to the largest first
+        let selected = SelectStrategy::ExactMatch.select(&candidates(), Amount::from(55u64));
+        assert_eq!(selected, Some(vec![3, 1]));
+    }
+
+    #[test]
+    fn random() {
+        let mut selected = SelectStrategy::Random
+            .select(&candidates(), Amount::from(110u64))
+            .unwrap();
+        selected.sort();
+        assert_eq!(selected, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn insufficient() {
+        for strategy in [
+            SelectStrategy::SmallestFirst,
+            SelectStrategy::LargestFirst,
+            SelectStrategy::ExactMatch,
+            SelectStrategy::Random,
+        ] {
+            assert_eq!(strategy.select(&candidates(), Amount::from(111u64)), None);
+        }
+    }
+
+    #[test]
+    fn overflow() {
+        let candidates = [(1u8, Amount::from(u64::MAX)), (2, Amount::from(u64::MAX))];
+        let selected = SelectStrategy::SmallestFirst.select(&candidates, Amount::from(u64::MAX));
+        assert_eq!(selected, Some(vec![1]));
+        let selected = accumulate(
+            [(1u8, Amount::from(1u64)), (2, Amount::from(u64::MAX))],
+            Amount::from(u64::MAX),
+        );
+        assert_eq!(selected, Some(vec![1, 2]));
+    }
+}
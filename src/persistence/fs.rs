@@ -19,20 +19,50 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::path::PathBuf;
-use std::{fs, io};
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{fs, io, thread};
 
 use amplify::confinement::U32 as U32MAX;
+use amplify::hex::FromHex;
+use chrono::Utc;
+use invoice::{ChainNet, RgbTransport};
 use nonasync::persistence::{PersistenceError, PersistenceProvider};
+use rgb::vm::{WitnessOrd, WitnessPos};
+use rgb::{ContractId, Layer1, OpId, XWitnessId};
 use strict_encoding::{StrictDeserialize, StrictSerialize};
 
-use crate::persistence::{MemIndex, MemStash, MemState};
+use crate::containers::{ConsignmentId, FileContent, LoadError, Transfer};
+use crate::persistence::{
+    MemIndex, MemStash, MemState, QuotaError, StockEvent, StockSettings, StorageQuota,
+};
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct FsBinStore {
     pub stash: PathBuf,
     pub state: PathBuf,
     pub index: PathBuf,
+    pub settings: PathBuf,
+    /// Maximal number of bytes the store is allowed to occupy on disk.
+    pub quota: Option<u64>,
+}
+
+/// Disk space used by the components of [`FsBinStore`], in bytes.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct DiskUsage {
+    pub stash: u64,
+    pub state: u64,
+    pub index: u64,
+    pub settings: u64,
+}
+
+impl DiskUsage {
+    pub fn total(&self) -> u64 { self.stash + self.state + self.index + self.settings }
 }
 
 impl FsBinStore {
@@ -45,14 +75,98 @@ impl FsBinStore {
         state.push("state.dat");
         let mut index = path.clone();
         index.push("index.dat");
+        let mut settings = path.clone();
+        settings.push("settings.dat");
 
         Ok(Self {
             stash,
             state,
             index,
+            settings,
+            quota: None,
+        })
+    }
+
+    /// Creates store for the data of a single network inside the `root`
+    /// directory, such that stocks of different networks kept side by side
+    /// never share the same files.
+    pub fn with_network(mut root: PathBuf, chain_net: ChainNet) -> io::Result<Self> {
+        root.push(chain_net.to_string());
+        Self::new(root)
+    }
+
+    pub fn with_quota(path: PathBuf, quota: u64) -> io::Result<Self> {
+        let mut me = Self::new(path)?;
+        me.quota = Some(quota);
+        Ok(me)
+    }
+
+    /// Reports disk space used by the store data.
+    ///
+    /// All contracts are kept within the same files; the space taken by a
+    /// specific contract can be estimated with `Stock::contract_usage`.
+    pub fn disk_usage(&self) -> io::Result<DiskUsage> {
+        Ok(DiskUsage {
+            stash: file_len(&self.stash)?,
+            state: file_len(&self.state)?,
+            index: file_len(&self.index)?,
+            settings: file_len(&self.settings)?,
         })
     }
+
+    /// Checks whether the provided data (for instance, a consignment which is
+    /// going to be consumed) fits into the storage quota.
+    ///
+    /// The quota is not enforced when the stock data are written, since
+    /// refusing to write just one of the stash, state and index files would
+    /// leave the stock inconsistent. Instead, the quota is checked by the
+    /// stock before consuming new data, once the store is provided to
+    /// `Stock::set_quota`.
+    pub fn check_quota(&self, data: &impl StrictSerialize) -> Result<(), QuotaError> {
+        self.check_space(data.strict_serialized_len::<U32MAX>()? as u64)
+    }
+}
+
+impl StorageQuota for FsBinStore {
+    fn check_space(&self, required: u64) -> Result<(), QuotaError> {
+        let Some(quota) = self.quota else {
+            return Ok(());
+        };
+        let used = self.disk_usage()?.total();
+        if used.saturating_add(required) > quota {
+            return Err(QuotaError::Exceeded {
+                used,
+                required,
+                quota,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn file_len(path: &PathBuf) -> io::Result<u64> {
+    match fs::metadata(path) {
+        Ok(meta) => Ok(meta.len()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err),
+    }
+}
+
+/// Writes the file through a temporary file which is renamed into `path` once
+/// completely written, such that an interrupted write never leaves a
+/// partially written file behind.
+fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = fs::File::create(&tmp)?;
+    write(&mut file)?;
+    file.sync_all()?;
+    fs::rename(tmp, path)
 }
+
 impl PersistenceProvider<MemStash> for FsBinStore {
     fn load(&self) -> Result<MemStash, PersistenceError> {
         MemStash::strict_deserialize_from_file::<U32MAX>(&self.stash)
@@ -91,3 +205,838 @@ impl PersistenceProvider<MemIndex> for FsBinStore {
             .map_err(PersistenceError::with)
     }
 }
+
+impl PersistenceProvider<StockSettings> for FsBinStore {
+    /// Loads the stock settings, falling back to the defaults for stores
+    /// created before the settings were persisted.
+    fn load(&self) -> Result<StockSettings, PersistenceError> {
+        if !self.settings.exists() {
+            return Ok(StockSettings::default());
+        }
+        StockSettings::strict_deserialize_from_file::<U32MAX>(&self.settings)
+            .map_err(PersistenceError::with)
+    }
+
+    fn store(&self, object: &StockSettings) -> Result<(), PersistenceError> {
+        object
+            .strict_serialize_to_file::<U32MAX>(&self.settings)
+            .map_err(PersistenceError::with)
+    }
+}
+
+/// Outbound consignment which was not yet delivered to its beneficiary, see
+/// [`DeliveryQueue`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Delivery {
+    pub consignment_id: ConsignmentId,
+    /// Transport endpoints the consignment must be delivered to.
+    pub endpoints: Vec<RgbTransport>,
+    /// Number of failed delivery attempts.
+    pub attempts: u32,
+    /// UTC unix timestamp of the last failed delivery attempt.
+    pub last_attempt: Option<i64>,
+    /// Error reported by the last failed delivery attempt.
+    pub last_error: Option<String>,
+}
+
+impl Delivery {
+    fn to_record(&self) -> String {
+        let mut record = String::new();
+        for endpoint in &self.endpoints {
+            record.push_str(&format!("endpoint {endpoint}\n"));
+        }
+        record.push_str(&format!("attempts {}\n", self.attempts));
+        if let Some(timestamp) = self.last_attempt {
+            record.push_str(&format!("last_attempt {timestamp}\n"));
+        }
+        if let Some(err) = &self.last_error {
+            record.push_str(&format!("last_error {}\n", err.replace('\n', " ")));
+        }
+        record
+    }
+
+    fn from_record(consignment_id: ConsignmentId, record: &str) -> Option<Self> {
+        let mut delivery = Delivery {
+            consignment_id,
+            endpoints: vec![],
+            attempts: 0,
+            last_attempt: None,
+            last_error: None,
+        };
+        for line in record.lines().filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once(' ')?;
+            match key {
+                "endpoint" => delivery.endpoints.push(RgbTransport::from_str(value).ok()?),
+                "attempts" => delivery.attempts = value.parse().ok()?,
+                "last_attempt" => delivery.last_attempt = Some(value.parse().ok()?),
+                "last_error" => delivery.last_error = Some(value.to_owned()),
+                _ => return None,
+            }
+        }
+        Some(delivery)
+    }
+}
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DeliveryError {
+    /// consignment {0} is absent from the delivery queue.
+    Unknown(ConsignmentId),
+
+    /// delivery queue entry {0} is corrupted.
+    Corrupted(String),
+
+    #[from]
+    #[display(inner)]
+    Load(LoadError),
+
+    #[from]
+    #[display(inner)]
+    Io(io::Error),
+}
+
+/// Persistent queue of outbound consignments, ensuring that a consignment
+/// which failed to be uploaded to the beneficiary transport endpoint is not
+/// lost and gets retried.
+///
+/// Each queued consignment is kept in the queue directory together with a
+/// text record of its endpoints and retry state, until it is removed with
+/// [`DeliveryQueue::complete`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DeliveryQueue {
+    pub dir: PathBuf,
+}
+
+impl DeliveryQueue {
+    pub fn new(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, consignment_id: ConsignmentId, ext: &str) -> PathBuf {
+        let mut path = self.dir.clone();
+        path.push(format!("{consignment_id:x}.{ext}"));
+        path
+    }
+
+    fn store(&self, delivery: &Delivery) -> io::Result<()> {
+        write_atomic(&self.path(delivery.consignment_id, "delivery"), |file| {
+            file.write_all(delivery.to_record().as_bytes())
+        })
+    }
+
+    /// Adds the transfer to the queue. If the transfer is already queued, its
+    /// retry state is preserved and the endpoints are updated.
+    pub fn enqueue(
+        &self,
+        transfer: &Transfer,
+        endpoints: impl IntoIterator<Item = RgbTransport>,
+    ) -> Result<ConsignmentId, DeliveryError> {
+        let consignment_id = transfer.consignment_id();
+        let endpoints = endpoints.into_iter().collect();
+        let delivery = match self.delivery(consignment_id) {
+            Ok(delivery) => Delivery {
+                endpoints,
+                ..delivery
+            },
+            Err(DeliveryError::Unknown(_)) => Delivery {
+                consignment_id,
+                endpoints,
+                attempts: 0,
+                last_attempt: None,
+                last_error: None,
+            },
+            Err(err) => return Err(err),
+        };
+        write_atomic(&self.path(consignment_id, "rgb"), |file| transfer.save(file))?;
+        self.store(&delivery)?;
+        Ok(consignment_id)
+    }
+
+    /// Returns information about a queued delivery.
+    pub fn delivery(&self, consignment_id: ConsignmentId) -> Result<Delivery, DeliveryError> {
+        let path = self.path(consignment_id, "delivery");
+        let record = match fs::read_to_string(&path) {
+            Ok(record) => record,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(DeliveryError::Unknown(consignment_id));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        Delivery::from_record(consignment_id, &record)
+            .ok_or_else(|| DeliveryError::Corrupted(path.display().to_string()))
+    }
+
+    /// Loads queued transfer.
+    pub fn transfer(&self, consignment_id: ConsignmentId) -> Result<Transfer, DeliveryError> {
+        self.delivery(consignment_id)?;
+        Ok(Transfer::load_file(self.path(consignment_id, "rgb"))?)
+    }
+
+    /// Lists all queued deliveries.
+    pub fn pending(&self) -> Result<Vec<Delivery>, DeliveryError> {
+        let mut deliveries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map(|ext| ext != "delivery")
+                .unwrap_or(true)
+            {
+                continue;
+            }
+            let consignment_id = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| ConsignmentId::from_hex(stem).ok())
+                .ok_or_else(|| DeliveryError::Corrupted(path.display().to_string()))?;
+            deliveries.push(self.delivery(consignment_id)?);
+        }
+        deliveries.sort_by_key(|delivery| delivery.last_attempt);
+        Ok(deliveries)
+    }
+
+    /// Lists queued deliveries which should be attempted now.
+    ///
+    /// The interval between attempts doubles with each failure, starting from
+    /// `retry_interval` seconds.
+    pub fn due(&self, retry_interval: i64) -> Result<Vec<Delivery>, DeliveryError> {
+        let now = Utc::now().timestamp();
+        let mut deliveries = self.pending()?;
+        deliveries.retain(|delivery| match delivery.last_attempt {
+            None => true,
+            Some(last) => {
+                let backoff = retry_interval.saturating_mul(1 << delivery.attempts.min(16));
+                now.saturating_sub(last) >= backoff
+            }
+        });
+        Ok(deliveries)
+    }
+
+    /// Records failed delivery attempt, returning the updated retry state.
+    pub fn record_failure(
+        &self,
+        consignment_id: ConsignmentId,
+        err: impl Display,
+    ) -> Result<Delivery, DeliveryError> {
+        let mut delivery = self.delivery(consignment_id)?;
+        delivery.attempts += 1;
+        delivery.last_attempt = Some(Utc::now().timestamp());
+        delivery.last_error = Some(err.to_string());
+        self.store(&delivery)?;
+        Ok(delivery)
+    }
+
+    /// Removes successfully delivered consignment from the queue.
+    pub fn complete(&self, consignment_id: ConsignmentId) -> Result<(), DeliveryError> {
+        self.delivery(consignment_id)?;
+        fs::remove_file(self.path(consignment_id, "delivery"))?;
+        match fs::remove_file(self.path(consignment_id, "rgb")) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Wallet-level event recorded in the [`EventJournal`].
+#[derive(Clone, Eq, PartialEq, Debug, From)]
+pub enum WalletEvent {
+    /// Invoice was issued by the wallet.
+    InvoiceCreated(String),
+    /// Consignment was validated and consumed into the stock.
+    ConsignmentConsumed {
+        contract_id: ContractId,
+        consignment_id: ConsignmentId,
+    },
+    /// Change of the stock data, see
+    /// [`Stock::subscribe`](crate::persistence::Stock::subscribe).
+    #[from]
+    Stock(StockEvent),
+}
+
+impl Display for WalletEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletEvent::InvoiceCreated(invoice) => write!(f, "invoice {invoice}"),
+            WalletEvent::ConsignmentConsumed {
+                contract_id,
+                consignment_id,
+            } => write!(f, "consumed {contract_id} {consignment_id}"),
+            WalletEvent::Stock(StockEvent::ContractAdded(contract_id)) => {
+                write!(f, "added {contract_id}")
+            }
+            WalletEvent::Stock(StockEvent::ContractRemoved(contract_id)) => {
+                write!(f, "removed {contract_id}")
+            }
+            WalletEvent::Stock(StockEvent::StateChanged { contract_id, opids }) => {
+                write!(f, "changed {contract_id}")?;
+                for opid in opids {
+                    write!(f, " {opid}")?;
+                }
+                Ok(())
+            }
+            WalletEvent::Stock(StockEvent::WitnessAttested(witness_id)) => {
+                write!(f, "attested {witness_id}")
+            }
+            WalletEvent::Stock(StockEvent::WitnessUpdated(witness_id, ord)) => {
+                write!(f, "updated {witness_id} ")?;
+                match ord {
+                    WitnessOrd::Mined(pos) => {
+                        write!(f, "mined:{}:{}", pos.height(), pos.timestamp())
+                    }
+                    WitnessOrd::Tentative => f.write_str("tentative"),
+                    WitnessOrd::Archived => f.write_str("archived"),
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for WalletEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, args) = s.split_once(' ').unwrap_or((s, ""));
+        let mut split = args.split(' ').filter(|arg| !arg.is_empty());
+        let mut next = || {
+            split
+                .next()
+                .ok_or_else(|| format!("incomplete event `{s}`"))
+        };
+        let contract_id = |id: &str| ContractId::from_str(id).map_err(|e| format!("{e}"));
+        let witness_id = |id: &str| XWitnessId::from_str(id).map_err(|e| format!("{e:?}"));
+        Ok(match kind {
+            "invoice" => WalletEvent::InvoiceCreated(args.to_owned()),
+            "consumed" => WalletEvent::ConsignmentConsumed {
+                contract_id: contract_id(next()?)?,
+                consignment_id: next()?.parse().map_err(|e| format!("{e}"))?,
+            },
+            "added" => StockEvent::ContractAdded(contract_id(next()?)?).into(),
+            "removed" => StockEvent::ContractRemoved(contract_id(next()?)?).into(),
+            "changed" => {
+                let contract_id = contract_id(next()?)?;
+                let mut opids = BTreeSet::new();
+                while let Ok(opid) = next() {
+                    opids.insert(OpId::from_str(opid).map_err(|e| format!("{e}"))?);
+                }
+                StockEvent::StateChanged { contract_id, opids }.into()
+            }
+            "attested" => StockEvent::WitnessAttested(witness_id(next()?)?).into(),
+            "updated" => {
+                let witness_id = witness_id(next()?)?;
+                let ord = match next()? {
+                    "tentative" => WitnessOrd::Tentative,
+                    "archived" => WitnessOrd::Archived,
+                    ord => {
+                        let pos = ord.strip_prefix("mined:").and_then(|pos| {
+                            let (height, timestamp) = pos.split_once(':')?;
+                            let height = height.parse().ok()?;
+                            let timestamp = timestamp.parse().ok()?;
+                            match witness_id.layer1() {
+                                Layer1::Bitcoin => WitnessPos::bitcoin(height, timestamp),
+                                Layer1::Liquid => WitnessPos::liquid(height, timestamp),
+                            }
+                        });
+                        WitnessOrd::Mined(
+                            pos.ok_or_else(|| format!("invalid witness position `{ord}`"))?,
+                        )
+                    }
+                };
+                StockEvent::WitnessUpdated(witness_id, ord).into()
+            }
+            _ => return Err(format!("unknown event `{s}`")),
+        })
+    }
+}
+
+/// Entry of the [`EventJournal`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct JournalEntry {
+    /// Monotonically increasing sequence number of the entry, starting from 1.
+    pub seq: u64,
+    /// UTC unix timestamp at which the event was recorded.
+    pub timestamp: i64,
+    pub event: WalletEvent,
+}
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum JournalError {
+    /// journal entry at line {0} is corrupted.
+    Corrupted(usize),
+
+    /// journal is locked by another writer; if no other process uses the
+    /// journal, the lock file `{0}` is stale and must be removed.
+    Locked(PathBuf),
+
+    #[from]
+    #[display(inner)]
+    Io(io::Error),
+}
+
+/// Default time after which an attempt to lock the journal fails.
+pub const JOURNAL_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Exclusive lock of the journal held by a writer, released on drop.
+///
+/// The lock is represented by a lock file next to the journal, created
+/// atomically, such that writers from different processes (or different
+/// journal instances within a process) never assign the same sequence number.
+struct JournalLock(PathBuf);
+
+impl JournalLock {
+    fn acquire(path: &Path, timeout: Duration) -> Result<Self, JournalError> {
+        let mut lock = path.as_os_str().to_owned();
+        lock.push(".lock");
+        let lock = PathBuf::from(lock);
+        let start = Instant::now();
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock)
+            {
+                Ok(_) => return Ok(JournalLock(lock)),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    if start.elapsed() >= timeout {
+                        return Err(JournalError::Locked(lock));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Drop for JournalLock {
+    fn drop(&mut self) { let _ = fs::remove_file(&self.0); }
+}
+
+/// Durable append-only journal of wallet events.
+///
+/// Clients which lost connection to the wallet remember the sequence number
+/// of the last event they have seen and catch up with [`EventJournal::since`]
+/// after reconnecting. Stock changes get into the journal with
+/// [`EventJournal::record_stock_events`] from a
+/// [`Stock::subscribe`](crate::persistence::Stock::subscribe) receiver.
+///
+/// Writers lock the journal file, and sequence numbers are always assigned
+/// from the journal data, thus multiple journal instances (including ones in
+/// different processes) may safely write to the same file.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct EventJournal {
+    pub path: PathBuf,
+    /// Time after which an attempt to write to the journal locked by another
+    /// writer fails with [`JournalError::Locked`]. Defaults to
+    /// [`JOURNAL_LOCK_TIMEOUT`].
+    pub lock_timeout: Duration,
+}
+
+impl EventJournal {
+    /// Opens journal at the given path, creating it if it doesn't exist.
+    ///
+    /// A trailing line left incomplete by an interrupted write is removed, such
+    /// that new events start from a new line.
+    pub fn open(path: PathBuf) -> Result<Self, JournalError> {
+        let me = Self {
+            path,
+            lock_timeout: JOURNAL_LOCK_TIMEOUT,
+        };
+        let _lock = JournalLock::acquire(&me.path, me.lock_timeout)?;
+        let (data, complete) = me.read()?;
+        if complete < data.len() {
+            let file = fs::OpenOptions::new().write(true).open(&me.path)?;
+            file.set_len(complete as u64)?;
+            file.sync_data()?;
+        }
+        me.entries()?;
+        Ok(me)
+    }
+
+    /// Sequence number of the last recorded event, or zero for an empty
+    /// journal.
+    pub fn last_seq(&self) -> Result<u64, JournalError> {
+        Ok(self.entries()?.last().map_or(0, |entry| entry.seq))
+    }
+
+    /// Appends event to the journal, returning its sequence number.
+    pub fn record(&mut self, event: impl Into<WalletEvent>) -> Result<u64, JournalError> {
+        let _lock = JournalLock::acquire(&self.path, self.lock_timeout)?;
+        let seq = self.last_seq()? + 1;
+        let timestamp = Utc::now().timestamp();
+        let event = event.into().to_string().replace('\n', " ");
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{seq} {timestamp} {event}")?;
+        file.sync_data()?;
+        Ok(seq)
+    }
+
+    /// Records all stock events received so far by the `subscription` (see
+    /// [`Stock::subscribe`](crate::persistence::Stock::subscribe)), without
+    /// waiting for new ones. Returns the
+    /// sequence number of the last recorded event.
+    pub fn record_stock_events(
+        &mut self,
+        subscription: &mpsc::Receiver<StockEvent>,
+    ) -> Result<u64, JournalError> {
+        let mut seq = None;
+        for event in subscription.try_iter() {
+            seq = Some(self.record(event)?);
+        }
+        seq.map_or_else(|| self.last_seq(), Ok)
+    }
+
+    /// Returns all events with sequence number above `cursor`.
+    pub fn since(&self, cursor: u64) -> Result<Vec<JournalEntry>, JournalError> {
+        let mut entries = self.entries()?;
+        entries.retain(|entry| entry.seq > cursor);
+        Ok(entries)
+    }
+
+    /// Reads journal data, returning them together with the length of the
+    /// part consisting of complete lines.
+    fn read(&self) -> Result<(Vec<u8>, usize), JournalError> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((vec![], 0)),
+            Err(err) => return Err(err.into()),
+        };
+        let complete = data
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |pos| pos + 1);
+        Ok((data, complete))
+    }
+
+    /// Parses journal entries, skipping a trailing line which is not yet (or
+    /// was never) completely written.
+    fn entries(&self) -> Result<Vec<JournalEntry>, JournalError> {
+        let (data, complete) = self.read()?;
+        String::from_utf8_lossy(&data[..complete])
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(no, line)| {
+                let mut split = line.splitn(3, ' ');
+                let entry = (|| {
+                    Some(JournalEntry {
+                        seq: split.next()?.parse().ok()?,
+                        timestamp: split.next()?.parse().ok()?,
+                        event: split.next()?.parse().ok()?,
+                    })
+                })();
+                entry.ok_or(JournalError::Corrupted(no + 1))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use bp::Txid;
+
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("rgb-std-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn quota_check() {
+        let dir = test_dir("quota");
+        let stash = MemStash::in_memory();
+        let len = stash.strict_serialized_len::<U32MAX>().unwrap() as u64;
+
+        let store = FsBinStore::with_quota(dir.clone(), len - 1).unwrap();
+        assert!(matches!(store.check_quota(&stash), Err(QuotaError::Exceeded {
+            used: 0,
+            required,
+            quota,
+        }) if required == len && quota == len - 1));
+        store.check_space(len - 1).unwrap();
+
+        let store = FsBinStore::with_quota(dir.clone(), len).unwrap();
+        store.check_quota(&stash).unwrap();
+        store.store(&stash).unwrap();
+        assert_eq!(store.disk_usage().unwrap().total(), len);
+        assert!(store.check_space(1).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn queued_transfer() -> Transfer {
+        Transfer::from_str(include_str!("../../asset/armored_transfer.default")).unwrap()
+    }
+
+    #[test]
+    fn delivery_enqueue() {
+        let dir = test_dir("delivery-enqueue");
+        let queue = DeliveryQueue::new(dir.clone()).unwrap();
+        let transfer = queued_transfer();
+        let endpoint = RgbTransport::RestHttp {
+            tls: true,
+            host: s!("proxy.example.com"),
+        };
+
+        let consignment_id = queue.enqueue(&transfer, [endpoint.clone()]).unwrap();
+        assert_eq!(consignment_id, transfer.consignment_id());
+        assert_eq!(queue.transfer(consignment_id).unwrap(), transfer);
+        let delivery = queue.delivery(consignment_id).unwrap();
+        assert_eq!(delivery.endpoints, vec![endpoint]);
+        assert_eq!(delivery.attempts, 0);
+        assert_eq!(queue.pending().unwrap(), vec![delivery]);
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        // Re-enqueueing preserves retry state and updates the endpoints
+        queue.record_failure(consignment_id, "timeout").unwrap();
+        queue.enqueue(&transfer, []).unwrap();
+        let delivery = queue.delivery(consignment_id).unwrap();
+        assert!(delivery.endpoints.is_empty());
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.last_error.as_deref(), Some("timeout"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn delivery_backoff() {
+        let dir = test_dir("delivery-backoff");
+        let queue = DeliveryQueue::new(dir.clone()).unwrap();
+        let consignment_id = queue.enqueue(&queued_transfer(), []).unwrap();
+
+        // Never attempted deliveries are always due
+        assert_eq!(queue.due(3600).unwrap().len(), 1);
+
+        let delivery = queue
+            .record_failure(consignment_id, "connection\nrefused")
+            .unwrap();
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.last_error.as_deref(), Some("connection\nrefused"));
+        assert_eq!(
+            queue
+                .delivery(consignment_id)
+                .unwrap()
+                .last_error
+                .as_deref(),
+            Some("connection refused")
+        );
+        assert!(queue.due(3600).unwrap().is_empty());
+        assert_eq!(queue.due(0).unwrap().len(), 1);
+
+        // The interval doubles with each failure
+        let mut delivery = queue.record_failure(consignment_id, "timeout").unwrap();
+        assert_eq!(delivery.attempts, 2);
+        delivery.last_attempt = Some(Utc::now().timestamp() - 10);
+        queue.store(&delivery).unwrap();
+        assert_eq!(queue.due(3).unwrap().len(), 0);
+        assert_eq!(queue.due(2).unwrap().len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn delivery_drain() {
+        let dir = test_dir("delivery-drain");
+        let queue = DeliveryQueue::new(dir.clone()).unwrap();
+        let consignment_id = queue.enqueue(&queued_transfer(), []).unwrap();
+
+        queue.complete(consignment_id).unwrap();
+        assert!(queue.pending().unwrap().is_empty());
+        assert!(matches!(queue.delivery(consignment_id), Err(DeliveryError::Unknown(_))));
+        assert!(matches!(queue.complete(consignment_id), Err(DeliveryError::Unknown(_))));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn events() -> Vec<WalletEvent> {
+        use std::num::NonZeroU32;
+
+        let contract_id = ContractId::from_byte_array([0x11; 32]);
+        let witness_id = XWitnessId::Bitcoin(Txid::from_byte_array([0x33; 32]));
+        let pos = WitnessPos::bitcoin(NonZeroU32::new(840_000).unwrap(), 1713571767).unwrap();
+        vec![
+            WalletEvent::InvoiceCreated(s!("rgb:~/~/bc:utxob:invoice?expiry=1")),
+            WalletEvent::ConsignmentConsumed {
+                contract_id,
+                consignment_id: ConsignmentId::from_byte_array([0x22; 32]),
+            },
+            StockEvent::ContractAdded(contract_id).into(),
+            StockEvent::StateChanged {
+                contract_id,
+                opids: bset![OpId::from([0x44; 32]), OpId::from([0x55; 32])],
+            }
+            .into(),
+            StockEvent::StateChanged {
+                contract_id,
+                opids: bset![],
+            }
+            .into(),
+            StockEvent::WitnessAttested(witness_id).into(),
+            StockEvent::WitnessUpdated(witness_id, WitnessOrd::Mined(pos)).into(),
+            StockEvent::WitnessUpdated(witness_id, WitnessOrd::Tentative).into(),
+            StockEvent::WitnessUpdated(witness_id, WitnessOrd::Archived).into(),
+            StockEvent::ContractRemoved(contract_id).into(),
+        ]
+    }
+
+    #[test]
+    fn wallet_event_str_round_trip() {
+        for event in events() {
+            assert_eq!(WalletEvent::from_str(&event.to_string()), Ok(event));
+        }
+        assert!(WalletEvent::from_str("attested").is_err());
+        assert!(WalletEvent::from_str("updated bc:33 mined:0:0").is_err());
+        assert!(WalletEvent::from_str("unknown event").is_err());
+    }
+
+    #[test]
+    fn journal_replay() {
+        let dir = test_dir("journal");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.log");
+
+        let mut journal = EventJournal::open(path.clone()).unwrap();
+        assert_eq!(journal.last_seq().unwrap(), 0);
+        for (no, event) in events().into_iter().enumerate() {
+            assert_eq!(journal.record(event).unwrap(), no as u64 + 1);
+        }
+        let count = events().len() as u64;
+
+        let journal = EventJournal::open(path).unwrap();
+        assert_eq!(journal.last_seq().unwrap(), count);
+        let entries = journal.since(0).unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(),
+            (1..=count).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            entries
+                .into_iter()
+                .map(|entry| entry.event)
+                .collect::<Vec<_>>(),
+            events()
+        );
+        let entries = journal.since(count - 2).unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(), vec![
+            count - 1,
+            count
+        ]);
+        assert!(journal.since(count).unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn journal_torn_line() {
+        let dir = test_dir("journal-torn");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.log");
+
+        let mut journal = EventJournal::open(path.clone()).unwrap();
+        let (event1, event2) = (events()[0].clone(), events()[1].clone());
+        journal.record(event1.clone()).unwrap();
+        // Simulate write interrupted in the middle of the line
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "2 1713571767 reorg bc:").unwrap();
+        drop(file);
+        assert_eq!(journal.since(0).unwrap().len(), 1);
+
+        let mut journal = EventJournal::open(path.clone()).unwrap();
+        assert_eq!(journal.last_seq().unwrap(), 1);
+        assert_eq!(journal.record(event2.clone()).unwrap(), 2);
+        let entries = journal.since(0).unwrap();
+        assert_eq!(
+            entries
+                .into_iter()
+                .map(|entry| entry.event)
+                .collect::<Vec<_>>(),
+            vec![event1, event2]
+        );
+
+        // Corruption of a complete line is still reported
+        fs::write(&path, "1 1713571767 invalid\n").unwrap();
+        assert!(matches!(EventJournal::open(path), Err(JournalError::Corrupted(1))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn journal_shared() {
+        let dir = test_dir("journal-shared");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.log");
+
+        // Two instances writing to the same file continue the same sequence.
+        let mut journal1 = EventJournal::open(path.clone()).unwrap();
+        let mut journal2 = EventJournal::open(path.clone()).unwrap();
+        let events = events();
+        assert_eq!(journal1.record(events[0].clone()).unwrap(), 1);
+        assert_eq!(journal2.record(events[1].clone()).unwrap(), 2);
+        assert_eq!(journal1.record(events[2].clone()).unwrap(), 3);
+        assert_eq!(journal2.last_seq().unwrap(), 3);
+
+        // A writer holding the lock blocks the others.
+        let lock = JournalLock::acquire(&path, Duration::ZERO).unwrap();
+        journal1.lock_timeout = Duration::ZERO;
+        assert!(matches!(journal1.record(events[3].clone()), Err(JournalError::Locked(_))));
+        drop(lock);
+        assert_eq!(journal1.record(events[3].clone()).unwrap(), 4);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn journal_stock_events() {
+        use strict_encoding::StrictDumb;
+
+        use crate::containers::{Contract, ValidContract};
+        use crate::interface::resolver::DumbResolver;
+        use crate::persistence::Stock;
+
+        let dir = test_dir("journal-stock");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.log");
+
+        let mut stock = Stock::in_memory();
+        let subscription = stock.subscribe();
+        let mut journal = EventJournal::open(path).unwrap();
+        assert_eq!(journal.record_stock_events(&subscription).unwrap(), 0);
+
+        let contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        stock
+            .import_contract(ValidContract::assume_valid(contract), DumbResolver)
+            .unwrap();
+        assert_eq!(journal.record_stock_events(&subscription).unwrap(), 2);
+        assert_eq!(journal.record_stock_events(&subscription).unwrap(), 2);
+        assert_eq!(
+            journal
+                .since(0)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.event)
+                .collect::<Vec<_>>(),
+            vec![
+                StockEvent::ContractAdded(contract_id).into(),
+                StockEvent::StateChanged {
+                    contract_id,
+                    opids: bset![]
+                }
+                .into()
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -32,7 +32,7 @@ use rgb::{
 };
 
 use crate::containers::{ConsignmentExt, ToWitnessId, WitnessBundle};
-use crate::persistence::{MemError, StoreTransaction};
+use crate::persistence::{MemError, RemoveError, StoreTransaction};
 use crate::SecretSeal;
 
 #[derive(Debug, Display, Error, From)]
@@ -338,6 +338,24 @@ impl<P: IndexProvider> Index<P> {
     ) -> Result<(impl Iterator<Item = XWitnessId> + '_, ContractId), IndexError<P>> {
         Ok(self.provider.bundle_info(bundle_id)?)
     }
+
+    pub(super) fn bundle_count(&self) -> Result<usize, IndexError<P>> {
+        self.provider
+            .bundle_count()
+            .map_err(IndexError::ReadProvider)
+    }
+
+    pub(super) fn remove_contract(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<bool, RemoveError<IndexError<P>>> {
+        self.provider
+            .remove_contract(contract_id)
+            .map_err(|err| match err {
+                RemoveError::Unsupported => RemoveError::Unsupported,
+                RemoveError::Provider(err) => RemoveError::Provider(IndexError::WriteProvider(err)),
+            })
+    }
 }
 
 impl<P: IndexProvider> StoreTransaction for Index<P> {
@@ -393,6 +411,9 @@ pub trait IndexReadProvider {
         &self,
         bundle_id: BundleId,
     ) -> Result<(impl Iterator<Item = XWitnessId>, ContractId), IndexReadError<Self::Error>>;
+
+    /// Returns the number of indexed state transition bundles.
+    fn bundle_count(&self) -> Result<usize, Self::Error>;
 }
 
 pub trait IndexWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
@@ -429,4 +450,15 @@ pub trait IndexWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
         type_id: AssignmentType,
         witness_id: XWitnessId,
     ) -> Result<(), IndexWriteError<Self::Error>>;
+
+    /// Removes all index entries of the contract, including its bundles,
+    /// operations and terminals. Returns `false` if the contract was not
+    /// known. The default implementation fails with
+    /// [`RemoveError::Unsupported`].
+    fn remove_contract(
+        &mut self,
+        _contract_id: ContractId,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        Err(RemoveError::Unsupported)
+    }
 }
@@ -24,6 +24,7 @@ use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::Infallible;
 use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
 use std::num::NonZeroU32;
 use std::{iter, mem};
 
@@ -33,6 +34,7 @@ use amplify::confinement::{
     TinyOrdMap, TinyOrdSet,
 };
 use amplify::num::u24;
+use amplify::Wrapper;
 use bp::dbc::tapret::TapretCommitment;
 use commit_verify::{CommitId, Conceal};
 use nonasync::persistence::{CloneNoPersistence, Persistence, PersistenceError, Persisting};
@@ -53,10 +55,10 @@ use strict_types::TypeSystem;
 
 use super::{
     ContractIfaceError, ContractStateRead, ContractStateWrite, IndexInconsistency, IndexProvider,
-    IndexReadError, IndexReadProvider, IndexWriteError, IndexWriteProvider, SchemaIfaces,
-    StashInconsistency, StashProvider, StashProviderError, StashReadProvider, StashWriteProvider,
-    StateInconsistency, StateProvider, StateReadProvider, StateWriteProvider, StoreTransaction,
-    UpdateRes,
+    IndexReadError, IndexReadProvider, IndexWriteError, IndexWriteProvider, RemoveError,
+    SchemaIfaces, StashInconsistency, StashProvider, StashProviderError, StashReadProvider,
+    StashWriteProvider, StateInconsistency, StateProvider, StateReadProvider, StateWriteProvider,
+    StoreTransaction, UpdateRes,
 };
 use crate::containers::{
     AnchorSet, ContentId, ContentRef, ContentSigs, SealWitness, SigBlob, Supplement, TrustLevel,
@@ -73,6 +75,102 @@ pub enum MemError {
 
     #[from]
     Confinement(confinement::Error),
+
+    #[display(doc_comments)]
+    /// interface implementation references unknown schema {0}.
+    UnknownSchema(SchemaId),
+
+    #[display(doc_comments)]
+    /// interface implementation references unknown interface {0}.
+    UnknownIface(IfaceId),
+}
+
+impl From<confinement::Error> for RemoveError<MemError> {
+    fn from(err: confinement::Error) -> Self { RemoveError::Provider(err.into()) }
+}
+
+/// Log of the changes made to an in-memory provider since the start of a
+/// transaction, used for the rollback.
+///
+/// Each write operation records a closure undoing it, so rolling back costs
+/// proportionally to the size of the transaction and not of the whole
+/// provider. Nested transactions put a mark into the log; rolling back a
+/// nested transaction undoes only the changes made after its mark.
+struct UndoLog<T> {
+    marks: Vec<usize>,
+    entries: Vec<Box<dyn FnOnce(&mut T) + Send + Sync>>,
+}
+
+impl<T> Default for UndoLog<T> {
+    fn default() -> Self {
+        UndoLog {
+            marks: vec![],
+            entries: vec![],
+        }
+    }
+}
+
+impl<T> Debug for UndoLog<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UndoLog")
+            .field("marks", &self.marks)
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+impl<T> UndoLog<T> {
+    fn begin(&mut self) { self.marks.push(self.entries.len()); }
+
+    /// Closes the innermost transaction, returning whether it was the
+    /// outermost one.
+    fn commit(&mut self) -> bool {
+        self.marks.pop();
+        if self.marks.is_empty() {
+            self.entries.clear();
+            return true;
+        }
+        false
+    }
+
+    /// Records an undo operation. Does nothing outside of a transaction.
+    fn record(&mut self, undo: impl FnOnce(&mut T) + Send + Sync + 'static) {
+        if !self.marks.is_empty() {
+            self.entries.push(Box::new(undo));
+        }
+    }
+
+    /// Closes the innermost transaction, returning undo operations in the
+    /// order they must be applied.
+    fn rollback(&mut self) -> Vec<Box<dyn FnOnce(&mut T) + Send + Sync>> {
+        let mark = self.marks.pop().unwrap_or_default();
+        let mut undo = self.entries.split_off(mark);
+        undo.reverse();
+        undo
+    }
+}
+
+trait Restore<K, V> {
+    /// Puts back the value which was present under the key before a change,
+    /// removing the key if there was no value.
+    fn restore(&mut self, key: K, prev: Option<V>);
+}
+
+impl<K: Ord + Hash, V, const MIN_LEN: usize, const MAX_LEN: usize> Restore<K, V>
+    for Confined<BTreeMap<K, V>, MIN_LEN, MAX_LEN>
+{
+    fn restore(&mut self, key: K, prev: Option<V>) {
+        match (prev, self.get_mut(&key)) {
+            (Some(prev), Some(value)) => *value = prev,
+            (Some(prev), None) => {
+                self.insert(key, prev)
+                    .expect("restores previously present value");
+            }
+            (None, _) => {
+                self.remove(&key).expect("restores previously absent value");
+            }
+        }
+    }
 }
 
 //////////
@@ -89,6 +187,10 @@ pub struct MemStash {
     #[strict_type(skip)]
     persistence: Option<Persistence<Self>>,
 
+    #[getter(skip)]
+    #[strict_type(skip)]
+    undo: UndoLog<Self>,
+
     schemata: TinyOrdMap<SchemaId, SchemaIfaces>,
     ifaces: TinyOrdMap<IfaceId, Iface>,
     geneses: TinyOrdMap<ContractId, Genesis>,
@@ -111,6 +213,7 @@ impl MemStash {
     pub fn in_memory() -> Self {
         Self {
             persistence: none!(),
+            undo: default!(),
             schemata: empty!(),
             ifaces: empty!(),
             geneses: empty!(),
@@ -132,6 +235,7 @@ impl CloneNoPersistence for MemStash {
     fn clone_no_persistence(&self) -> Self {
         Self {
             persistence: None,
+            undo: default!(),
             schemata: self.schemata.clone(),
             ifaces: self.ifaces.clone(),
             geneses: self.geneses.clone(),
@@ -162,13 +266,23 @@ impl StoreTransaction for MemStash {
     type TransactionErr = MemError;
     #[inline]
     fn begin_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.undo.begin();
         self.mark_dirty();
         Ok(())
     }
     #[inline]
-    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> { Ok(self.store()?) }
+    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        if self.undo.commit() {
+            self.store()?;
+        }
+        Ok(())
+    }
     #[inline]
-    fn rollback_transaction(&mut self) { unreachable!() }
+    fn rollback_transaction(&mut self) {
+        for undo in self.undo.rollback() {
+            undo(self);
+        }
+    }
 }
 
 impl StashProvider for MemStash {}
@@ -247,7 +361,10 @@ impl StashReadProvider for MemStash {
     }
 
     fn supplement(&self, content_ref: ContentRef) -> Result<Option<&Supplement>, Self::Error> {
-        Ok(self.suppl.get(&content_ref).and_then(|s| s.first()))
+        Ok(self
+            .suppl
+            .get(&content_ref)
+            .and_then(|s| s.iter().find(|suppl| suppl.tags().is_none())))
     }
 
     fn supplements(
@@ -354,6 +471,10 @@ impl StashReadProvider for MemStash {
     fn secret_seals(&self) -> Result<impl Iterator<Item = XChain<GraphSeal>>, Self::Error> {
         Ok(self.secret_seals.iter().copied())
     }
+
+    fn attachment(&self, id: AttachId) -> Result<Option<&MediumBlob>, Self::Error> {
+        Ok(self.attachments.get(&id))
+    }
 }
 
 impl StashWriteProvider for MemStash {
@@ -363,6 +484,8 @@ impl StashWriteProvider for MemStash {
         let schema_id = schema.schema_id();
         if !self.schemata.contains_key(&schema_id) {
             self.schemata.insert(schema_id, SchemaIfaces::new(schema))?;
+            self.undo
+                .record(move |me: &mut Self| me.schemata.restore(schema_id, None));
             return Ok(true);
         }
         Ok(false)
@@ -372,20 +495,31 @@ impl StashWriteProvider for MemStash {
         let iface_id = iface.iface_id();
         if !self.ifaces.contains_key(&iface_id) {
             self.ifaces.insert(iface_id, iface)?;
+            self.undo
+                .record(move |me: &mut Self| me.ifaces.restore(iface_id, None));
             return Ok(true);
         }
         Ok(false)
     }
 
     fn replace_iimpl(&mut self, iimpl: IfaceImpl) -> Result<bool, Self::Error> {
+        let schema_id = iimpl.schema_id;
         let schema_ifaces = self
             .schemata
-            .get_mut(&iimpl.schema_id)
-            .expect("unknown schema");
-        let iface = self.ifaces.get(&iimpl.iface_id).expect("unknown interface");
+            .get_mut(&schema_id)
+            .ok_or(MemError::UnknownSchema(schema_id))?;
+        let iface = self
+            .ifaces
+            .get(&iimpl.iface_id)
+            .ok_or(MemError::UnknownIface(iimpl.iface_id))?;
         let iface_name = iface.name.clone();
-        let present = schema_ifaces.iimpls.contains_key(&iface_name);
-        schema_ifaces.iimpls.insert(iface_name, iimpl)?;
+        let prev = schema_ifaces.iimpls.insert(iface_name.clone(), iimpl)?;
+        let present = prev.is_some();
+        self.undo.record(move |me: &mut Self| {
+            if let Some(schema_ifaces) = me.schemata.get_mut(&schema_id) {
+                schema_ifaces.iimpls.restore(iface_name, prev);
+            }
+        });
         Ok(!present)
     }
 
@@ -394,41 +528,66 @@ impl StashWriteProvider for MemStash {
         identity: Identity,
         trust: TrustLevel,
     ) -> Result<(), confinement::Error> {
-        self.identities.insert(identity, trust)?;
+        let prev = self.identities.insert(identity.clone(), trust)?;
+        self.undo
+            .record(move |me: &mut Self| me.identities.restore(identity, prev));
         Ok(())
     }
 
     fn add_supplement(&mut self, suppl: Supplement) -> Result<(), Self::Error> {
-        match self.suppl.get_mut(&suppl.content_id) {
+        let content_ref = suppl.content_id;
+        let prev = self.suppl.get(&content_ref).cloned();
+        match self.suppl.get_mut(&content_ref) {
             None => {
-                self.suppl.insert(suppl.content_id, tiny_bset![suppl])?;
+                self.suppl.insert(content_ref, tiny_bset![suppl])?;
+            }
+            Some(suppls) => {
+                if suppl.tags().is_some() {
+                    *suppls = Confined::from_iter_checked(
+                        suppls.iter().filter(|s| s.tags().is_none()).cloned(),
+                    );
+                }
+                suppls.push(suppl)?
             }
-            Some(suppls) => suppls.push(suppl)?,
         }
+        self.undo
+            .record(move |me: &mut Self| me.suppl.restore(content_ref, prev));
         Ok(())
     }
 
     fn replace_genesis(&mut self, genesis: Genesis) -> Result<bool, Self::Error> {
         let contract_id = genesis.contract_id();
-        let present = self.geneses.insert(contract_id, genesis)?.is_some();
+        let prev = self.geneses.insert(contract_id, genesis)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.geneses.restore(contract_id, prev));
         Ok(!present)
     }
 
     fn replace_extension(&mut self, extension: Extension) -> Result<bool, Self::Error> {
         let opid = extension.id();
-        let present = self.extensions.insert(opid, extension)?.is_some();
+        let prev = self.extensions.insert(opid, extension)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.extensions.restore(opid, prev));
         Ok(!present)
     }
 
     fn replace_bundle(&mut self, bundle: TransitionBundle) -> Result<bool, Self::Error> {
         let bundle_id = bundle.bundle_id();
-        let present = self.bundles.insert(bundle_id, bundle)?.is_some();
+        let prev = self.bundles.insert(bundle_id, bundle)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.bundles.restore(bundle_id, prev));
         Ok(!present)
     }
 
     fn replace_witness(&mut self, witness: SealWitness) -> Result<bool, Self::Error> {
         let witness_id = witness.witness_id();
-        let present = self.witnesses.insert(witness_id, witness)?.is_some();
+        let prev = self.witnesses.insert(witness_id, witness)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.witnesses.restore(witness_id, prev));
         Ok(!present)
     }
 
@@ -437,47 +596,136 @@ impl StashWriteProvider for MemStash {
         id: AttachId,
         attach: MediumBlob,
     ) -> Result<bool, Self::Error> {
-        let present = self.attachments.insert(id, attach)?.is_some();
+        let prev = self.attachments.insert(id, attach)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.attachments.restore(id, prev));
         Ok(!present)
     }
 
     fn consume_types(&mut self, types: TypeSystem) -> Result<(), Self::Error> {
+        let added = types
+            .keys()
+            .filter(|sem_id| self.type_system.get(**sem_id).is_none())
+            .copied()
+            .collect::<Vec<_>>();
+        // Recorded ahead of the change since extending may fail half-way
+        self.undo.record(move |me: &mut Self| {
+            let mut types = mem::take(&mut me.type_system).into_inner().release();
+            for sem_id in added {
+                types.remove(&sem_id);
+            }
+            me.type_system = TypeSystem::from(Confined::from_checked(types));
+        });
         Ok(self.type_system.extend(types)?)
     }
 
     fn replace_lib(&mut self, lib: Lib) -> Result<bool, Self::Error> {
-        let present = self.libs.insert(lib.id(), lib)?.is_some();
+        let lib_id = lib.id();
+        let prev = self.libs.insert(lib_id, lib)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.libs.restore(lib_id, prev));
         Ok(!present)
     }
 
     fn import_sigs<I>(&mut self, content_id: ContentId, sigs: I) -> Result<(), Self::Error>
     where I: IntoIterator<Item = (Identity, SigBlob)> {
+        let prev = self.sigs.get(&content_id).cloned();
+        let mut new_identities = vec![];
         let sigs = sigs.into_iter().filter(|(id, _)| {
             match self.identities.get(id) {
                 Some(level) => *level,
                 None => {
                     let level = TrustLevel::default();
                     // We ignore if the identities are full
-                    self.identities.insert(id.clone(), level).ok();
+                    if self.identities.insert(id.clone(), level).is_ok() {
+                        new_identities.push(id.clone());
+                    }
                     level
                 }
             }
             .should_accept()
         });
-        if let Some(prev_sigs) = self.sigs.get_mut(&content_id) {
-            prev_sigs.extend(sigs)?;
+        let res = if let Some(prev_sigs) = self.sigs.get_mut(&content_id) {
+            prev_sigs.extend(sigs)
         } else {
-            let sigs = Confined::try_from_iter(sigs)?;
-            self.sigs.insert(content_id, ContentSigs::from(sigs)).ok();
+            Confined::try_from_iter(sigs).map(|sigs| {
+                self.sigs.insert(content_id, ContentSigs::from(sigs)).ok();
+            })
+        };
+        self.undo.record(move |me: &mut Self| {
+            me.sigs.restore(content_id, prev);
+            for id in new_identities {
+                me.identities.restore(id, None);
+            }
+        });
+        Ok(res?)
+    }
+
+    fn remove_contract(
+        &mut self,
+        contract_id: ContractId,
+        bundles: impl IntoIterator<Item = BundleId>,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        for bundle_id in bundles {
+            let prev = self.bundles.remove(&bundle_id)?;
+            self.undo
+                .record(move |me: &mut Self| me.bundles.restore(bundle_id, prev));
         }
-        Ok(())
+        let extensions = self
+            .extensions
+            .iter()
+            .filter(|(_, ext)| ext.contract_id == contract_id)
+            .map(|(opid, _)| *opid)
+            .collect::<Vec<_>>();
+        for opid in extensions {
+            let prev = self.extensions.remove(&opid)?;
+            self.undo
+                .record(move |me: &mut Self| me.extensions.restore(opid, prev));
+        }
+        let suppl_ref = ContentRef::Genesis(contract_id);
+        let prev = self.suppl.remove(&suppl_ref)?;
+        self.undo
+            .record(move |me: &mut Self| me.suppl.restore(suppl_ref, prev));
+        let sigs_id = ContentId::Genesis(contract_id);
+        let prev = self.sigs.remove(&sigs_id)?;
+        self.undo
+            .record(move |me: &mut Self| me.sigs.restore(sigs_id, prev));
+        let prev = self.geneses.remove(&contract_id)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.geneses.restore(contract_id, prev));
+        Ok(present)
     }
 
     fn add_secret_seal(&mut self, seal: XChain<GraphSeal>) -> Result<bool, Self::Error> {
         let present = self.secret_seals.contains(&seal);
         self.secret_seals.push(seal)?;
+        if !present {
+            self.undo.record(move |me: &mut Self| {
+                me.secret_seals
+                    .remove(&seal)
+                    .expect("collection allows zero elements");
+            });
+        }
         Ok(!present)
     }
+
+    fn remove_secret_seal(
+        &mut self,
+        seal: XChain<GraphSeal>,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        let present = self.secret_seals.remove(&seal)?;
+        if present {
+            self.undo.record(move |me: &mut Self| {
+                me.secret_seals
+                    .push(seal)
+                    .expect("restores previously present seal");
+            });
+        }
+        Ok(present)
+    }
 }
 
 //////////
@@ -493,6 +741,10 @@ pub struct MemState {
     #[strict_type(skip)]
     persistence: Option<Persistence<Self>>,
 
+    #[getter(skip)]
+    #[strict_type(skip)]
+    undo: UndoLog<Self>,
+
     witnesses: LargeOrdMap<XWitnessId, WitnessOrd>,
     contracts: TinyOrdMap<ContractId, MemContractState>,
 }
@@ -504,6 +756,7 @@ impl MemState {
     pub fn in_memory() -> Self {
         Self {
             persistence: none!(),
+            undo: default!(),
             witnesses: empty!(),
             contracts: empty!(),
         }
@@ -514,6 +767,7 @@ impl CloneNoPersistence for MemState {
     fn clone_no_persistence(&self) -> Self {
         Self {
             persistence: None,
+            undo: default!(),
             witnesses: self.witnesses.clone(),
             contracts: self.contracts.clone(),
         }
@@ -533,13 +787,23 @@ impl StoreTransaction for MemState {
     type TransactionErr = MemError;
     #[inline]
     fn begin_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.undo.begin();
         self.mark_dirty();
         Ok(())
     }
     #[inline]
-    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> { Ok(self.store()?) }
+    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        if self.undo.commit() {
+            self.store()?;
+        }
+        Ok(())
+    }
     #[inline]
-    fn rollback_transaction(&mut self) { unreachable!() }
+    fn rollback_transaction(&mut self) {
+        for undo in self.undo.rollback() {
+            undo(self);
+        }
+    }
 }
 
 impl StateProvider for MemState {}
@@ -606,18 +870,22 @@ impl StateWriteProvider for MemState {
         } else {
             self.contracts
                 .insert(contract_id, MemContractState::new(schema, contract_id))?;
+            self.undo
+                .record(move |me: &mut Self| me.contracts.restore(contract_id, None));
             self.contracts.get_mut(&contract_id).expect("just inserted")
         };
         let mut writer = MemContractWriter {
             writer: Box::new(
-                |witness_id: XWitnessId, ord: WitnessOrd| -> Result<(), confinement::Error> {
+                |witness_id: XWitnessId,
+                 ord: WitnessOrd|
+                 -> Result<Option<WitnessOrd>, confinement::Error> {
                     // NB: We do not check the existence of the witness since we have a newer
                     // version anyway and even if it is known we have to replace it
-                    self.witnesses.insert(witness_id, ord)?;
-                    Ok(())
+                    self.witnesses.insert(witness_id, ord)
                 },
             ),
             contract,
+            undo: Some(&mut self.undo),
         };
         writer.add_genesis(genesis)?;
         Ok(writer)
@@ -628,23 +896,24 @@ impl StateWriteProvider for MemState {
         contract_id: ContractId,
     ) -> Result<Option<Self::ContractWrite<'_>>, Self::Error> {
         // TODO: Add begin/commit transaction
-        Ok(self
-            .contracts
-            .get_mut(&contract_id)
-            .map(|contract| MemContractWriter {
-                // We can't move this constructor to a dedicated method due to the rust borrower
-                // checker
-                writer: Box::new(
-                    |witness_id: XWitnessId, ord: WitnessOrd| -> Result<(), confinement::Error> {
-                        // NB: We do not check the existence of the witness since we have a newer
-                        // version anyway and even if it is known we have to replace
-                        // it
-                        self.witnesses.insert(witness_id, ord)?;
-                        Ok(())
-                    },
-                ),
-                contract,
-            }))
+        let Some(contract) = self.contracts.get_mut(&contract_id) else {
+            return Ok(None);
+        };
+        Ok(Some(MemContractWriter {
+            // We can't move this constructor to a dedicated method due to the rust borrower
+            // checker
+            writer: Box::new(
+                |witness_id: XWitnessId,
+                 ord: WitnessOrd|
+                 -> Result<Option<WitnessOrd>, confinement::Error> {
+                    // NB: We do not check the existence of the witness since we have a newer
+                    // version anyway and even if it is known we have to replace it
+                    self.witnesses.insert(witness_id, ord)
+                },
+            ),
+            contract,
+            undo: Some(&mut self.undo),
+        }))
     }
 
     fn update_witnesses(
@@ -659,23 +928,69 @@ impl StateWriteProvider for MemState {
         let mut witnesses = LargeOrdMap::new();
         mem::swap(&mut self.witnesses, &mut witnesses);
         let mut witnesses = witnesses.release();
+        let mut changed = vec![];
+        let mut updated = BTreeMap::new();
         for (id, ord) in &mut witnesses {
             if matches!(ord, WitnessOrd::Mined(pos) if pos.height() < after_height) {
                 continue;
             }
             match resolver.resolve_pub_witness_ord(*id) {
-                Ok(new) => *ord = new,
+                Ok(new) => {
+                    if new != *ord {
+                        changed.push((*id, *ord));
+                        updated.insert(*id, new);
+                    }
+                    *ord = new
+                }
                 Err(err) => {
                     failed.insert(*id, err.to_string());
                 }
             }
             succeeded += 1;
         }
-        let mut witnesses =
-            LargeOrdMap::try_from(witnesses).inspect_err(|_| self.rollback_transaction())?;
+        let mut witnesses = LargeOrdMap::from_checked(witnesses);
         mem::swap(&mut self.witnesses, &mut witnesses);
+        self.undo.record(move |me: &mut Self| {
+            for (id, ord) in changed {
+                me.witnesses.restore(id, Some(ord));
+            }
+        });
         self.commit_transaction()?;
-        Ok(UpdateRes { succeeded, failed })
+        Ok(UpdateRes {
+            succeeded,
+            failed,
+            changed: updated,
+        })
+    }
+
+    fn update_witness(
+        &mut self,
+        witness_id: XWitnessId,
+        ord: WitnessOrd,
+    ) -> Result<bool, Self::Error> {
+        if !self.witnesses.contains_key(&witness_id) {
+            return Ok(false);
+        }
+        self.begin_transaction()?;
+        let prev = self
+            .witnesses
+            .get_mut(&witness_id)
+            .map(|prev| mem::replace(prev, ord));
+        self.undo
+            .record(move |me: &mut Self| me.witnesses.restore(witness_id, prev));
+        self.commit_transaction()?;
+        Ok(true)
+    }
+
+    fn remove_contract(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        let prev = self.contracts.remove(&contract_id)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.contracts.restore(contract_id, prev));
+        Ok(present)
     }
 }
 
@@ -746,8 +1061,11 @@ impl MemContractState {
         }
     }
 
-    fn add_operation(&mut self, op: OrdOpRef) {
+    /// Adds state from the operation, returning which of it was not known
+    /// before.
+    fn add_operation(&mut self, op: OrdOpRef) -> AddedState {
         let opid = op.id();
+        let mut added = AddedState::default();
 
         for (ty, state) in op.globals() {
             let map = self
@@ -761,9 +1079,13 @@ impl MemContractState {
                     index: idx as u16,
                     op_witness: OpWitness::from(op),
                 };
-                map.known
+                let prev = map
+                    .known
                     .insert(out, s.clone())
                     .expect("contract global state exceeded 2^32 items, which is unrealistic");
+                if prev.is_none() {
+                    added.global.push((*ty, out));
+                }
             }
         }
 
@@ -803,12 +1125,13 @@ impl MemContractState {
         let witness_id = op.witness_id();
         match op.assignments() {
             AssignmentsRef::Genesis(assignments) => {
-                self.add_assignments(witness_id, opid, assignments)
+                self.add_assignments(witness_id, opid, assignments, &mut added)
             }
             AssignmentsRef::Graph(assignments) => {
-                self.add_assignments(witness_id, opid, assignments)
+                self.add_assignments(witness_id, opid, assignments, &mut added)
             }
         }
+        added
     }
 
     fn add_assignments<Seal: ExposedSeal>(
@@ -816,9 +1139,11 @@ impl MemContractState {
         witness_id: Option<XWitnessId>,
         opid: OpId,
         assignments: &Assignments<Seal>,
+        added: &mut AddedState,
     ) {
         fn process<State: ExposedState + KnownState, Seal: ExposedSeal>(
             contract_state: &mut LargeOrdSet<OutputAssignment<State>>,
+            added: &mut Vec<OutputAssignment<State>>,
             assignments: &[Assign<State, Seal>],
             opid: OpId,
             ty: AssignmentType,
@@ -835,29 +1160,79 @@ impl MemContractState {
                     }
                     None => OutputAssignment::with_no_witness(seal, state, opid, ty, no as u16),
                 };
+                if contract_state.contains(&assigned_state) {
+                    continue;
+                }
                 contract_state
-                    .push(assigned_state)
+                    .push(assigned_state.clone())
                     .expect("contract state exceeded 2^32 items, which is unrealistic");
+                added.push(assigned_state);
             }
         }
 
         for (ty, assignments) in assignments.iter() {
             match assignments {
                 TypedAssigns::Declarative(assignments) => {
-                    process(&mut self.rights, assignments, opid, *ty, witness_id)
-                }
-                TypedAssigns::Fungible(assignments) => {
-                    process(&mut self.fungibles, assignments, opid, *ty, witness_id)
+                    process(&mut self.rights, &mut added.rights, assignments, opid, *ty, witness_id)
                 }
+                TypedAssigns::Fungible(assignments) => process(
+                    &mut self.fungibles,
+                    &mut added.fungibles,
+                    assignments,
+                    opid,
+                    *ty,
+                    witness_id,
+                ),
                 TypedAssigns::Structured(assignments) => {
-                    process(&mut self.data, assignments, opid, *ty, witness_id)
+                    process(&mut self.data, &mut added.data, assignments, opid, *ty, witness_id)
                 }
                 TypedAssigns::Attachment(assignments) => {
-                    process(&mut self.attach, assignments, opid, *ty, witness_id)
+                    process(&mut self.attach, &mut added.attach, assignments, opid, *ty, witness_id)
                 }
             }
         }
     }
+
+    fn remove_state(&mut self, added: AddedState) {
+        for (ty, out) in added.global {
+            if let Some(map) = self.global.get_mut(&ty) {
+                map.known
+                    .remove(&out)
+                    .expect("collection allows zero elements");
+            }
+        }
+        for assignment in added.rights {
+            self.rights
+                .remove(&assignment)
+                .expect("collection allows zero elements");
+        }
+        for assignment in added.fungibles {
+            self.fungibles
+                .remove(&assignment)
+                .expect("collection allows zero elements");
+        }
+        for assignment in added.data {
+            self.data
+                .remove(&assignment)
+                .expect("collection allows zero elements");
+        }
+        for assignment in added.attach {
+            self.attach
+                .remove(&assignment)
+                .expect("collection allows zero elements");
+        }
+    }
+}
+
+/// State which was added to a contract by an operation and which has to be
+/// removed if the operation is rolled back.
+#[derive(Default)]
+struct AddedState {
+    global: Vec<(GlobalStateType, GlobalOut)>,
+    rights: Vec<OutputAssignment<VoidState>>,
+    fungibles: Vec<OutputAssignment<RevealedValue>>,
+    data: Vec<OutputAssignment<RevealedData>>,
+    attach: Vec<OutputAssignment<RevealedAttach>>,
 }
 
 pub struct MemContract<M: Borrow<MemContractState> = MemContractState> {
@@ -1039,15 +1414,17 @@ impl ContractStateEvolve for MemContract<MemContractState> {
         fn writer(me: &mut MemContract<MemContractState>) -> MemContractWriter {
             MemContractWriter {
                 writer: Box::new(
-                    |witness_id: XWitnessId, ord: WitnessOrd| -> Result<(), confinement::Error> {
+                    |witness_id: XWitnessId,
+                     ord: WitnessOrd|
+                     -> Result<Option<WitnessOrd>, confinement::Error> {
                         // NB: We do not check the existence of the witness since we have a
                         // newer version anyway and even if it is
                         // known we have to replace it
-                        me.filter.insert(witness_id, ord);
-                        Ok(())
+                        Ok(me.filter.insert(witness_id, ord))
                     },
                 ),
                 contract: &mut me.unfiltered,
+                undo: None,
             }
         }
         match op {
@@ -1067,7 +1444,11 @@ impl ContractStateEvolve for MemContract<MemContractState> {
         .map_err(|err| {
             // TODO: remove once evolve_state would accept arbitrary errors
             match err {
-                MemError::Persistence(_) => unreachable!("only confinement errors are possible"),
+                MemError::Persistence(_)
+                | MemError::UnknownSchema(_)
+                | MemError::UnknownIface(_) => {
+                    unreachable!("only confinement errors are possible")
+                }
                 MemError::Confinement(e) => e,
             }
         })?;
@@ -1125,8 +1506,28 @@ impl<M: Borrow<MemContractState>> ContractStateRead for MemContract<M> {
 }
 
 pub struct MemContractWriter<'mem> {
-    writer: Box<dyn FnMut(XWitnessId, WitnessOrd) -> Result<(), confinement::Error> + 'mem>,
+    writer: Box<
+        dyn FnMut(XWitnessId, WitnessOrd) -> Result<Option<WitnessOrd>, confinement::Error> + 'mem,
+    >,
     contract: &'mem mut MemContractState,
+    undo: Option<&'mem mut UndoLog<MemState>>,
+}
+
+impl MemContractWriter<'_> {
+    fn record(&mut self, witness: Option<(XWitnessId, Option<WitnessOrd>)>, added: AddedState) {
+        let Some(undo) = &mut self.undo else {
+            return;
+        };
+        let contract_id = self.contract.contract_id;
+        undo.record(move |me: &mut MemState| {
+            if let Some(contract) = me.contracts.get_mut(&contract_id) {
+                contract.remove_state(added);
+            }
+            if let Some((witness_id, prev)) = witness {
+                me.witnesses.restore(witness_id, prev);
+            }
+        });
+    }
 }
 
 impl ContractStateWrite for MemContractWriter<'_> {
@@ -1137,7 +1538,8 @@ impl ContractStateWrite for MemContractWriter<'_> {
     /// If genesis violates RGB consensus rules and wasn't checked against the
     /// schema before adding to the history.
     fn add_genesis(&mut self, genesis: &Genesis) -> Result<(), Self::Error> {
-        self.contract.add_operation(OrdOpRef::Genesis(genesis));
+        let added = self.contract.add_operation(OrdOpRef::Genesis(genesis));
+        self.record(None, added);
         Ok(())
     }
 
@@ -1151,9 +1553,11 @@ impl ContractStateWrite for MemContractWriter<'_> {
         witness_id: XWitnessId,
         ord: WitnessOrd,
     ) -> Result<(), Self::Error> {
-        (self.writer)(witness_id, ord)?;
-        self.contract
+        let prev = (self.writer)(witness_id, ord)?;
+        let added = self
+            .contract
             .add_operation(OrdOpRef::Transition(transition, witness_id, ord));
+        self.record(Some((witness_id, prev)), added);
         Ok(())
     }
 
@@ -1167,9 +1571,11 @@ impl ContractStateWrite for MemContractWriter<'_> {
         witness_id: XWitnessId,
         ord: WitnessOrd,
     ) -> Result<(), Self::Error> {
-        (self.writer)(witness_id, ord)?;
-        self.contract
+        let prev = (self.writer)(witness_id, ord)?;
+        let added = self
+            .contract
             .add_operation(OrdOpRef::Extension(extension, witness_id, ord));
+        self.record(Some((witness_id, prev)), added);
         Ok(())
     }
 }
@@ -1208,6 +1614,10 @@ pub struct MemIndex {
     #[strict_type(skip)]
     persistence: Option<Persistence<Self>>,
 
+    #[getter(skip)]
+    #[strict_type(skip)]
+    undo: UndoLog<Self>,
+
     op_bundle_index: MediumOrdMap<OpId, BundleId>,
     bundle_contract_index: MediumOrdMap<BundleId, ContractId>,
     bundle_witness_index: MediumOrdMap<BundleId, TinyOrdSet<XWitnessId>>,
@@ -1222,6 +1632,7 @@ impl MemIndex {
     pub fn in_memory() -> Self {
         Self {
             persistence: None,
+            undo: default!(),
             op_bundle_index: empty!(),
             bundle_contract_index: empty!(),
             bundle_witness_index: empty!(),
@@ -1235,6 +1646,7 @@ impl CloneNoPersistence for MemIndex {
     fn clone_no_persistence(&self) -> Self {
         Self {
             persistence: None,
+            undo: default!(),
             op_bundle_index: self.op_bundle_index.clone(),
             bundle_contract_index: self.bundle_contract_index.clone(),
             bundle_witness_index: self.bundle_witness_index.clone(),
@@ -1257,13 +1669,23 @@ impl StoreTransaction for MemIndex {
     type TransactionErr = MemError;
     #[inline]
     fn begin_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        self.undo.begin();
         self.mark_dirty();
         Ok(())
     }
     #[inline]
-    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> { Ok(self.store()?) }
+    fn commit_transaction(&mut self) -> Result<(), Self::TransactionErr> {
+        if self.undo.commit() {
+            self.store()?;
+        }
+        Ok(())
+    }
     #[inline]
-    fn rollback_transaction(&mut self) { unreachable!() }
+    fn rollback_transaction(&mut self) {
+        for undo in self.undo.rollback() {
+            undo(self);
+        }
+    }
 }
 
 impl IndexProvider for MemIndex {}
@@ -1361,6 +1783,8 @@ impl IndexReadProvider for MemIndex {
             .ok_or(IndexInconsistency::BundleContractUnknown(bundle_id))?;
         Ok((witness_id.iter().cloned(), *contract_id))
     }
+
+    fn bundle_count(&self) -> Result<usize, Self::Error> { Ok(self.bundle_contract_index.len()) }
 }
 
 impl IndexWriteProvider for MemIndex {
@@ -1369,12 +1793,26 @@ impl IndexWriteProvider for MemIndex {
     fn register_contract(&mut self, contract_id: ContractId) -> Result<bool, Self::Error> {
         if !self.contract_index.contains_key(&contract_id) {
             self.contract_index.insert(contract_id, empty!())?;
+            self.undo
+                .record(move |me: &mut Self| me.contract_index.restore(contract_id, None));
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    fn remove_contract(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        self.remove_contract_entries(contract_id)?;
+        let prev = self.contract_index.remove(&contract_id)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.contract_index.restore(contract_id, prev));
+        Ok(present)
+    }
+
     fn register_bundle(
         &mut self,
         bundle_id: BundleId,
@@ -1393,14 +1831,18 @@ impl IndexWriteProvider for MemIndex {
             }
             .into());
         }
+        let prev_witnesses = self.bundle_witness_index.get(&bundle_id).cloned();
         self.bundle_witness_index
             .entry(bundle_id)?
             .or_default()
             .push(witness_id)?;
-        let present2 = self
-            .bundle_contract_index
-            .insert(bundle_id, contract_id)?
-            .is_some();
+        self.undo.record(move |me: &mut Self| {
+            me.bundle_witness_index.restore(bundle_id, prev_witnesses)
+        });
+        let prev = self.bundle_contract_index.insert(bundle_id, contract_id)?;
+        let present2 = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.bundle_contract_index.restore(bundle_id, prev));
         Ok(!present2)
     }
 
@@ -1421,7 +1863,10 @@ impl IndexWriteProvider for MemIndex {
             }
             .into());
         }
-        let present = self.op_bundle_index.insert(opid, bundle_id)?.is_some();
+        let prev = self.op_bundle_index.insert(opid, bundle_id)?;
+        let present = prev.is_some();
+        self.undo
+            .record(move |me: &mut Self| me.op_bundle_index.restore(opid, prev));
         Ok(!present)
     }
 
@@ -1443,13 +1888,21 @@ impl IndexWriteProvider for MemIndex {
                 let output = seal
                     .to_output_seal()
                     .expect("genesis seals always have outpoint");
-                match index.outpoint_opouts.get_mut(&output) {
+                let added = match index.outpoint_opouts.get_mut(&output) {
                     Some(opouts) => {
+                        let present = opouts.contains(&opout);
                         opouts.push(opout)?;
+                        !present
                     }
                     None => {
                         index.outpoint_opouts.insert(output, medium_bset!(opout))?;
+                        true
                     }
+                };
+                if added {
+                    self.undo.record(move |me: &mut Self| {
+                        me.remove_outpoint_opout(contract_id, output, opout)
+                    });
                 }
             }
         }
@@ -1481,13 +1934,21 @@ impl IndexWriteProvider for MemIndex {
                         seal, witness_id
                     )
                 });
-                match index.outpoint_opouts.get_mut(&output) {
+                let added = match index.outpoint_opouts.get_mut(&output) {
                     Some(opouts) => {
+                        let present = opouts.contains(&opout);
                         opouts.push(opout)?;
+                        !present
                     }
                     None => {
                         index.outpoint_opouts.insert(output, medium_bset!(opout))?;
+                        true
                     }
+                };
+                if added {
+                    self.undo.record(move |me: &mut Self| {
+                        me.remove_outpoint_opout(contract_id, output, opout)
+                    });
                 }
             }
         }
@@ -1498,6 +1959,80 @@ impl IndexWriteProvider for MemIndex {
 }
 
 impl MemIndex {
+    fn remove_contract_entries(&mut self, contract_id: ContractId) -> Result<(), MemError> {
+        let bundles = self
+            .bundle_contract_index
+            .iter()
+            .filter(|(_, id)| **id == contract_id)
+            .map(|(bundle_id, _)| *bundle_id)
+            .collect::<BTreeSet<_>>();
+        for bundle_id in bundles.iter().copied() {
+            let prev = self.bundle_contract_index.remove(&bundle_id)?;
+            self.undo
+                .record(move |me: &mut Self| me.bundle_contract_index.restore(bundle_id, prev));
+            let prev = self.bundle_witness_index.remove(&bundle_id)?;
+            self.undo
+                .record(move |me: &mut Self| me.bundle_witness_index.restore(bundle_id, prev));
+        }
+        let ops = self
+            .op_bundle_index
+            .iter()
+            .filter(|(_, bundle_id)| bundles.contains(*bundle_id))
+            .map(|(opid, _)| *opid)
+            .collect::<BTreeSet<_>>();
+        for opid in ops.iter().copied() {
+            let prev = self.op_bundle_index.remove(&opid)?;
+            self.undo
+                .record(move |me: &mut Self| me.op_bundle_index.restore(opid, prev));
+        }
+        let terminals = self
+            .terminal_index
+            .iter()
+            .filter(|(_, opouts)| opouts.iter().any(|opout| ops.contains(&opout.op)))
+            .map(|(seal, opouts)| {
+                let rest = opouts
+                    .iter()
+                    .filter(|opout| !ops.contains(&opout.op))
+                    .copied()
+                    .collect::<BTreeSet<_>>();
+                (*seal, rest)
+            })
+            .collect::<Vec<_>>();
+        for (seal, rest) in terminals {
+            let prev = self.terminal_index.remove(&seal)?;
+            self.undo
+                .record(move |me: &mut Self| me.terminal_index.restore(seal, prev));
+            if !rest.is_empty() {
+                self.terminal_index
+                    .insert(seal, Confined::from_checked(rest))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_outpoint_opout(
+        &mut self,
+        contract_id: ContractId,
+        output: XOutputSeal,
+        opout: Opout,
+    ) {
+        let Some(index) = self.contract_index.get_mut(&contract_id) else {
+            return;
+        };
+        let Some(opouts) = index.outpoint_opouts.get_mut(&output) else {
+            return;
+        };
+        opouts
+            .remove(&opout)
+            .expect("collection allows zero elements");
+        if opouts.is_empty() {
+            index
+                .outpoint_opouts
+                .remove(&output)
+                .expect("collection allows zero elements");
+        }
+    }
+
     fn extend_terminals<State: ExposedState, Seal: ExposedSeal>(
         &mut self,
         vec: &[Assign<State, Seal>],
@@ -1520,11 +2055,14 @@ impl MemIndex {
         seal: XChain<SecretSeal>,
         opout: Opout,
     ) -> Result<(), IndexWriteError<MemError>> {
-        match self
+        let prev = self
             .terminal_index
             .remove(&seal)
-            .expect("can have zero elements")
-        {
+            .expect("can have zero elements");
+        let restored = prev.clone();
+        self.undo
+            .record(move |me: &mut Self| me.terminal_index.restore(seal, restored));
+        match prev {
             Some(mut existing_opouts) => {
                 existing_opouts.push(opout)?;
                 let _ = self.terminal_index.insert(seal, existing_opouts);
@@ -34,6 +34,9 @@ mod stock;
 mod stash;
 mod state;
 mod index;
+mod plugin;
+mod settings;
+mod sync;
 
 mod memory;
 #[cfg(feature = "fs")]
@@ -46,6 +49,8 @@ pub use index::{
 pub use memory::{
     MemContract, MemContractState, MemError, MemGlobalState, MemIndex, MemStash, MemState,
 };
+pub use plugin::{ConsignmentView, VerifyPlugin};
+pub use settings::StockSettings;
 pub use stash::{
     ProviderError as StashProviderError, SchemaIfaces, Stash, StashDataError, StashError,
     StashInconsistency, StashProvider, StashReadProvider, StashWriteProvider,
@@ -55,9 +60,29 @@ pub use state::{
     StateProvider, StateReadProvider, StateWriteProvider,
 };
 pub use stock::{
-    ComposeError, ConsignError, ContractIfaceError, FasciaError, InputError as StockInputError,
-    Stock, StockError, StockErrorAll, StockErrorMem, UpdateRes,
+    AcceptError, ArticlesError, ComposeError, ConsignError, ConsignmentEstimate, ConsistencyError,
+    ConsolidationPolicy, ConsumeProgress, ContractIfaceError, ContractReport, FasciaError,
+    Fragmentation, InputError as StockInputError, IssuedSeal, QuotaError, Revalidation,
+    SealDefinition, SealStatus, Stock, StockError, StockErrorAll, StockErrorMem, StockEvent,
+    StorageQuota, SubmitError, SupportReport, TransferDraft, TransferError, UpdateRes,
+    WitnessAudit, WitnessCost, WitnessSummary,
 };
+pub use sync::{LockError, SyncError, SyncStock};
+
+/// Errors removing data from a persistence provider.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum RemoveError<E: std::error::Error> {
+    /// the persistence provider doesn't support data removal.
+    Unsupported,
+
+    /// {0}
+    Provider(E),
+}
+
+impl<E: std::error::Error> From<E> for RemoveError<E> {
+    fn from(err: E) -> Self { Self::Provider(err) }
+}
 
 pub trait StoreTransaction {
     type TransactionErr: std::error::Error;
@@ -0,0 +1,80 @@
+// RGB standard library for working with smart contracts on Bitcoin & Lightning
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Debug;
+
+use amplify::confinement::{LargeOrdSet, SmallOrdMap, TinyOrdMap, TinyOrdSet};
+use rgb::{BundleId, ContractId, Extension, Genesis, SchemaId, SecretSeal, XChain};
+
+use crate::containers::{
+    Consignment, ConsignmentId, ContentId, ContentSigs, Supplement, WitnessBundle,
+};
+
+/// Wallet-local verification plugin applying additional policy to the
+/// consignments of contracts under a specific schema.
+///
+/// Plugins are run by [`super::Stock`] after the consignment has passed the
+/// consensus validation and before any of its data are added to the stock.
+/// They can't make a consensus-invalid consignment valid; they may only add
+/// warnings to the validation status or reject the consignment.
+pub trait VerifyPlugin: Debug + Send + Sync {
+    /// Plugin name used in the validation status and error messages.
+    fn name(&self) -> &str;
+
+    /// Checks the consignment against the plugin policy.
+    ///
+    /// Returns a list of non-fatal warnings which are added to the validation
+    /// status, or an error message if the consignment must be rejected.
+    fn verify(&self, consignment: ConsignmentView) -> Result<Vec<String>, String>;
+}
+
+/// Read-only view of a contract or transfer consignment provided to the
+/// [`VerifyPlugin`]s.
+#[derive(Copy, Clone, Debug)]
+pub struct ConsignmentView<'c> {
+    pub consignment_id: ConsignmentId,
+    pub contract_id: ContractId,
+    pub schema_id: SchemaId,
+    pub transfer: bool,
+    pub terminals: &'c SmallOrdMap<BundleId, XChain<SecretSeal>>,
+    pub genesis: &'c Genesis,
+    pub extensions: &'c LargeOrdSet<Extension>,
+    pub bundles: &'c LargeOrdSet<WitnessBundle>,
+    pub supplements: &'c TinyOrdSet<Supplement>,
+    pub signatures: &'c TinyOrdMap<ContentId, ContentSigs>,
+}
+
+impl<'c, const TRANSFER: bool> From<&'c Consignment<TRANSFER>> for ConsignmentView<'c> {
+    fn from(consignment: &'c Consignment<TRANSFER>) -> Self {
+        ConsignmentView {
+            consignment_id: consignment.consignment_id(),
+            contract_id: consignment.contract_id(),
+            schema_id: consignment.schema_id(),
+            transfer: consignment.transfer,
+            terminals: &consignment.terminals,
+            genesis: &consignment.genesis,
+            extensions: &consignment.extensions,
+            bundles: &consignment.bundles,
+            supplements: &consignment.supplements,
+            signatures: &consignment.signatures,
+        }
+    }
+}
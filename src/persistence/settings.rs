@@ -0,0 +1,148 @@
+// RGB standard library for working with smart contracts on Bitcoin & Lightning
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify::confinement::{self, MediumOrdSet};
+use invoice::ChainNet;
+use nonasync::persistence::{CloneNoPersistence, Persistence, Persisting};
+use rgb::{ContractId, Identity};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::containers::TimestampPolicy;
+use crate::LIB_NAME_RGB_STORAGE;
+
+/// Stock configuration which must survive stock reloads, persisted
+/// separately from the stash, state and index data.
+#[derive(Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB_STORAGE, dumb = Self::in_memory())]
+pub struct StockSettings {
+    #[strict_type(skip)]
+    persistence: Option<Persistence<Self>>,
+
+    quarantine: MediumOrdSet<ContractId>,
+    default_issuer: Option<Identity>,
+    chain_net: Option<ChainNet>,
+    timestamp_policy: TimestampPolicy,
+}
+
+impl StrictSerialize for StockSettings {}
+impl StrictDeserialize for StockSettings {}
+
+impl Default for StockSettings {
+    fn default() -> Self { Self::in_memory() }
+}
+
+impl StockSettings {
+    pub fn in_memory() -> Self {
+        Self {
+            persistence: none!(),
+            quarantine: empty!(),
+            default_issuer: None,
+            chain_net: None,
+            timestamp_policy: default!(),
+        }
+    }
+
+    /// Contracts excluded from composing and sending new transfers.
+    pub fn quarantined(&self) -> impl Iterator<Item = ContractId> + '_ {
+        self.quarantine.iter().copied()
+    }
+
+    pub fn is_quarantined(&self, contract_id: ContractId) -> bool {
+        self.quarantine.contains(&contract_id)
+    }
+
+    /// Adds contract to the quarantine, returning whether it was not
+    /// quarantined before.
+    pub fn quarantine(&mut self, contract_id: ContractId) -> Result<bool, confinement::Error> {
+        if self.quarantine.contains(&contract_id) {
+            return Ok(false);
+        }
+        self.quarantine.push(contract_id)?;
+        self.mark_dirty();
+        Ok(true)
+    }
+
+    /// Removes contract from the quarantine, returning whether it was
+    /// quarantined.
+    pub fn release(&mut self, contract_id: ContractId) -> bool {
+        let removed = self
+            .quarantine
+            .remove(&contract_id)
+            .expect("quarantine has no minimal size");
+        if removed {
+            self.mark_dirty();
+        }
+        removed
+    }
+
+    /// Issuer identity used for new contracts when no issuer is provided.
+    pub fn default_issuer(&self) -> Option<&Identity> { self.default_issuer.as_ref() }
+
+    pub fn set_default_issuer(&mut self, issuer: Option<Identity>) {
+        if self.default_issuer != issuer {
+            self.default_issuer = issuer;
+            self.mark_dirty();
+        }
+    }
+
+    /// Network the stock is restricted to.
+    pub fn chain_net(&self) -> Option<ChainNet> { self.chain_net }
+
+    pub fn set_chain_net(&mut self, chain_net: Option<ChainNet>) {
+        if self.chain_net != chain_net {
+            self.chain_net = chain_net;
+            self.mark_dirty();
+        }
+    }
+
+    /// Handling of insane genesis timestamps of the consumed and issued
+    /// contracts.
+    pub fn timestamp_policy(&self) -> TimestampPolicy { self.timestamp_policy }
+
+    pub fn set_timestamp_policy(&mut self, policy: TimestampPolicy) {
+        if self.timestamp_policy != policy {
+            self.timestamp_policy = policy;
+            self.mark_dirty();
+        }
+    }
+}
+
+impl CloneNoPersistence for StockSettings {
+    fn clone_no_persistence(&self) -> Self {
+        Self {
+            persistence: None,
+            quarantine: self.quarantine.clone(),
+            default_issuer: self.default_issuer.clone(),
+            chain_net: self.chain_net,
+            timestamp_policy: self.timestamp_policy,
+        }
+    }
+}
+
+impl Persisting for StockSettings {
+    #[inline]
+    fn persistence(&self) -> Option<&Persistence<Self>> { self.persistence.as_ref() }
+    #[inline]
+    fn persistence_mut(&mut self) -> Option<&mut Persistence<Self>> { self.persistence.as_mut() }
+    #[inline]
+    fn as_mut_persistence(&mut self) -> &mut Option<Persistence<Self>> { &mut self.persistence }
+}
@@ -47,9 +47,10 @@ use crate::containers::{
     SigBlob, Supplement, TrustLevel, WitnessBundle,
 };
 use crate::interface::{
-    ContractBuilder, Iface, IfaceClass, IfaceId, IfaceImpl, IfaceRef, TransitionBuilder,
+    BlankTransitionBuilder, BuilderError, ContractBuilder, ExtensionBuilder, Iface, IfaceClass,
+    IfaceId, IfaceImpl, IfaceRef, TransitionBuilder,
 };
-use crate::persistence::{ContractIfaceError, StoreTransaction};
+use crate::persistence::{ContractIfaceError, RemoveError, StoreTransaction};
 use crate::{MergeReveal, MergeRevealError, SecretSeal, LIB_NAME_RGB_STD};
 
 #[derive(Debug, Display, Error, From)]
@@ -74,6 +75,7 @@ pub enum StashError<P: StashProvider> {
     #[from(MergeError)]
     #[from(MergeRevealError)]
     #[from(mpc::InvalidProof)]
+    #[from(BuilderError)]
     Data(StashDataError),
 }
 
@@ -160,6 +162,10 @@ pub enum StashDataError {
     #[from]
     #[display(inner)]
     NoAbstractIface(ContractIfaceError),
+
+    #[from]
+    #[display(inner)]
+    Builder(BuilderError),
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -190,6 +196,15 @@ impl SchemaIfaces {
     pub fn contains(&self, id: IfaceId) -> bool {
         self.iimpls.values().any(|iimpl| iimpl.iface_id == id)
     }
+
+    /// Returns interface implementation if the schema implements exactly one
+    /// known interface.
+    pub fn default_iimpl(&self) -> Option<&IfaceImpl> {
+        if self.iimpls.len() != 1 {
+            return None;
+        }
+        self.iimpls.values().next()
+    }
 }
 
 #[derive(Debug)]
@@ -258,12 +273,39 @@ impl<P: StashProvider> Stash<P> {
     pub(super) fn genesis(&self, contract_id: ContractId) -> Result<&Genesis, StashError<P>> {
         Ok(self.provider.genesis(contract_id)?)
     }
+    pub(super) fn bundle_ids(&self) -> Result<impl Iterator<Item = BundleId> + '_, StashError<P>> {
+        self.provider.bundle_ids().map_err(StashError::ReadProvider)
+    }
+    pub(super) fn extension_ids(&self) -> Result<impl Iterator<Item = OpId> + '_, StashError<P>> {
+        self.provider
+            .extension_ids()
+            .map_err(StashError::ReadProvider)
+    }
+    pub(super) fn extension(&self, op_id: OpId) -> Result<&Extension, StashError<P>> {
+        Ok(self.provider.extension(op_id)?)
+    }
     pub(super) fn bundle(&self, bundle_id: BundleId) -> Result<&TransitionBundle, StashError<P>> {
         Ok(self.provider.bundle(bundle_id)?)
     }
     pub(super) fn witness(&self, witness_id: XWitnessId) -> Result<&SealWitness, StashError<P>> {
         Ok(self.provider.witness(witness_id)?)
     }
+    pub(super) fn secret_seals(
+        &self,
+    ) -> Result<impl Iterator<Item = XChain<GraphSeal>> + '_, StashError<P>> {
+        self.provider
+            .secret_seals()
+            .map_err(StashError::ReadProvider)
+    }
+
+    pub(super) fn seal_secret(
+        &self,
+        secret: XChain<SecretSeal>,
+    ) -> Result<Option<XChain<GraphSeal>>, StashError<P>> {
+        self.provider
+            .seal_secret(secret)
+            .map_err(StashError::ReadProvider)
+    }
 
     pub(super) fn supplements(
         &self,
@@ -292,6 +334,12 @@ impl<P: StashProvider> Stash<P> {
             .map_err(StashError::ReadProvider)
     }
 
+    pub(super) fn attachment(&self, id: AttachId) -> Result<Option<&MediumBlob>, StashError<P>> {
+        self.provider
+            .attachment(id)
+            .map_err(StashError::ReadProvider)
+    }
+
     pub(super) fn extract<'a>(
         &self,
         schema: &Schema,
@@ -388,11 +436,42 @@ impl<P: StashProvider> Stash<P> {
         Ok(builder)
     }
 
+    pub(super) fn extension_builder(
+        &self,
+        contract_id: ContractId,
+        iface: impl Into<IfaceRef>,
+        extension_name: impl Into<FieldName>,
+    ) -> Result<ExtensionBuilder, StashError<P>> {
+        let schema_ifaces = self.provider.contract_schema(contract_id)?;
+        let iface = self.iface(iface)?;
+        let schema = &schema_ifaces.schema;
+        let iimpl = schema_ifaces
+            .get(iface.iface_id())
+            .ok_or(StashDataError::NoIfaceImpl(schema.schema_id(), iface.iface_id()))?;
+        let genesis = self.provider.genesis(contract_id)?;
+
+        let (types, _) = self.extract(&schema_ifaces.schema, [iface])?;
+
+        let mut builder = ExtensionBuilder::named_extension(
+            contract_id,
+            iface.clone(),
+            schema.clone(),
+            iimpl.clone(),
+            extension_name,
+            types,
+        )?;
+        for (assignment_type, asset_tag) in genesis.asset_tags.iter() {
+            builder = builder.add_asset_tag_raw(*assignment_type, *asset_tag)?;
+        }
+
+        Ok(builder)
+    }
+
     pub(super) fn blank_builder(
         &self,
         contract_id: ContractId,
         iface: impl Into<IfaceRef>,
-    ) -> Result<TransitionBuilder, StashError<P>> {
+    ) -> Result<BlankTransitionBuilder, StashError<P>> {
         let schema_ifaces = self.provider.contract_schema(contract_id)?;
         let iface = self.iface(iface)?;
         let schema = &schema_ifaces.schema;
@@ -404,7 +483,7 @@ impl<P: StashProvider> Stash<P> {
         let (types, _) = self.extract(&schema_ifaces.schema, [iface])?;
 
         let mut builder = if let Some(iimpl) = schema_ifaces.get(iface.iface_id()) {
-            TransitionBuilder::blank_transition(
+            BlankTransitionBuilder::new(
                 contract_id,
                 iface.clone(),
                 schema.clone(),
@@ -416,7 +495,7 @@ impl<P: StashProvider> Stash<P> {
                 schema_ifaces.iimpls.first_key_value().unwrap();
             let default_iface = self.iface(default_iface_name.clone())?;
 
-            TransitionBuilder::blank_transition(
+            BlankTransitionBuilder::new(
                 contract_id,
                 default_iface.clone(),
                 schema.clone(),
@@ -461,7 +540,9 @@ impl<P: StashProvider> Stash<P> {
         }
 
         // TODO: filter out non-trusted signers
-        for suppl in kit.supplements {
+        // Contract tags are local to the stock and must never be imported from
+        // the outside.
+        for suppl in kit.supplements.into_iter().filter(|s| s.tags().is_none()) {
             self.provider
                 .add_supplement(suppl)
                 .map_err(StashError::WriteProvider)?;
@@ -480,11 +561,7 @@ impl<P: StashProvider> Stash<P> {
         &self,
         mut consignment: Consignment<TRANSFER>,
     ) -> Result<Consignment<TRANSFER>, StashError<P>> {
-        consignment = consignment.reveal_terminal_seals(|secret| {
-            self.provider
-                .seal_secret(secret)
-                .map_err(StashError::ReadProvider)
-        })?;
+        consignment = consignment.reveal_terminal_seals(|secret| self.seal_secret(secret))?;
         Ok(consignment)
     }
 
@@ -494,6 +571,7 @@ impl<P: StashProvider> Stash<P> {
     ) -> Result<(), StashError<P>> {
         let contract_id = consignment.contract_id();
 
+        let known = self.genesis(contract_id).is_ok();
         let genesis = match self.genesis(contract_id) {
             Ok(g) => g.clone().merge_reveal(consignment.genesis)?,
             Err(_) => consignment.genesis,
@@ -523,16 +601,34 @@ impl<P: StashProvider> Stash<P> {
                 .map_err(StashError::WriteProvider)?;
         }
 
+        // We persist interface implementations shipped with the consignment,
+        // such that the contract can be immediately used with them even if the
+        // schema was not known before. Implementations not matching the
+        // consigned schema or interface are ignored.
+        let schema_id = consignment.schema.schema_id();
         let (ifaces, iimpls): (BTreeSet<_>, BTreeSet<_>) = consignment
             .ifaces
             .release()
             .into_iter()
+            .filter(|(iface, iimpl)| {
+                iimpl.schema_id == schema_id && iimpl.iface_id == iface.iface_id()
+            })
             .fold((bset!(), bset!()), |(mut keys, mut values), (k, v)| {
                 keys.insert(k);
                 values.insert(v);
                 (keys, values)
             });
 
+        // Contract tags are accepted only from the contract issued locally,
+        // i.e. when the contract gets imported for the first time; they are
+        // never taken from transfers.
+        let tags = consignment
+            .supplements
+            .iter()
+            .find(|suppl| suppl.tags().is_some())
+            .filter(|_| !TRANSFER && !known)
+            .cloned();
+
         self.consume_kit(Kit {
             version: consignment.version,
             ifaces: Confined::from_checked(ifaces),
@@ -542,7 +638,14 @@ impl<P: StashProvider> Stash<P> {
             types: consignment.types,
             scripts: Confined::from_checked(consignment.scripts.release()),
             signatures: consignment.signatures,
-        })
+        })?;
+
+        if let Some(suppl) = tags {
+            self.provider
+                .add_supplement(suppl)
+                .map_err(StashError::WriteProvider)?;
+        }
+        Ok(())
     }
 
     fn consume_witness_bundle(
@@ -685,6 +788,40 @@ impl<P: StashProvider> Stash<P> {
         self.commit_transaction()?;
         Ok(seal)
     }
+
+    pub(super) fn store_supplement(&mut self, suppl: Supplement) -> Result<(), StashError<P>> {
+        self.begin_transaction()?;
+        self.provider
+            .add_supplement(suppl)
+            .inspect_err(|_| self.rollback_transaction())
+            .map_err(StashError::WriteProvider)?;
+        self.commit_transaction()
+    }
+
+    pub(super) fn remove_secret_seal(
+        &mut self,
+        seal: XChain<GraphSeal>,
+    ) -> Result<bool, RemoveError<StashError<P>>> {
+        self.provider
+            .remove_secret_seal(seal)
+            .map_err(|err| match err {
+                RemoveError::Unsupported => RemoveError::Unsupported,
+                RemoveError::Provider(err) => RemoveError::Provider(StashError::WriteProvider(err)),
+            })
+    }
+
+    pub(super) fn remove_contract(
+        &mut self,
+        contract_id: ContractId,
+        bundles: impl IntoIterator<Item = BundleId>,
+    ) -> Result<bool, RemoveError<StashError<P>>> {
+        self.provider
+            .remove_contract(contract_id, bundles)
+            .map_err(|err| match err {
+                RemoveError::Unsupported => RemoveError::Unsupported,
+                RemoveError::Provider(err) => RemoveError::Provider(StashError::WriteProvider(err)),
+            })
+    }
 }
 
 impl<P: StashProvider> StoreTransaction for Stash<P> {
@@ -741,7 +878,13 @@ pub trait StashReadProvider {
     }
 
     fn get_trust(&self, identity: &Identity) -> Result<TrustLevel, Self::Error>;
+    /// Returns the supplement which is exported together with the content.
+    /// Supplements carrying local contract tags (see
+    /// [`ContractTags`](crate::containers::ContractTags)) are never returned
+    /// by this method.
     fn supplement(&self, content_ref: ContentRef) -> Result<Option<&Supplement>, Self::Error>;
+    /// Returns all known supplements for the content, including the one
+    /// carrying local contract tags.
     fn supplements(
         &self,
         content_ref: ContentRef,
@@ -761,6 +904,7 @@ pub trait StashReadProvider {
         secret: XChain<SecretSeal>,
     ) -> Result<Option<XChain<GraphSeal>>, Self::Error>;
     fn secret_seals(&self) -> Result<impl Iterator<Item = XChain<GraphSeal>>, Self::Error>;
+    fn attachment(&self, id: AttachId) -> Result<Option<&MediumBlob>, Self::Error>;
 }
 
 pub trait StashWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
@@ -783,9 +927,35 @@ pub trait StashWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
         identity: Identity,
         trust: TrustLevel,
     ) -> Result<(), confinement::Error>;
+    /// Adds supplement to the stash. A supplement carrying contract tags
+    /// replaces the previous tags supplement for the same content.
     fn add_supplement(&mut self, suppl: Supplement) -> Result<(), Self::Error>;
     fn import_sigs<I>(&mut self, content_id: ContentId, sigs: I) -> Result<(), Self::Error>
     where I: IntoIterator<Item = (Identity, SigBlob)>;
 
     fn add_secret_seal(&mut self, seal: XChain<GraphSeal>) -> Result<bool, Self::Error>;
+
+    /// Removes the secret seal. Returns `false` if the seal was not known.
+    /// The default implementation fails with [`RemoveError::Unsupported`].
+    fn remove_secret_seal(
+        &mut self,
+        _seal: XChain<GraphSeal>,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        Err(RemoveError::Unsupported)
+    }
+
+    /// Removes contract genesis, extensions, the provided bundles of the
+    /// contract state transitions and contract-specific supplements and
+    /// signatures. Schemata, interfaces and witnesses, which may be shared
+    /// with other contracts, are kept.
+    ///
+    /// Returns `false` if the contract was not known. The default
+    /// implementation fails with [`RemoveError::Unsupported`].
+    fn remove_contract(
+        &mut self,
+        _contract_id: ContractId,
+        _bundles: impl IntoIterator<Item = BundleId>,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        Err(RemoveError::Unsupported)
+    }
 }
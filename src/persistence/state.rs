@@ -37,7 +37,8 @@ use rgb::{
 
 use crate::containers::{ConsignmentExt, ToWitnessId};
 use crate::contract::OutputAssignment;
-use crate::persistence::{StoreTransaction, UpdateRes};
+use crate::interface::OwnedAllocation;
+use crate::persistence::{RemoveError, StoreTransaction, UpdateRes};
 
 #[derive(Debug, Display, Error, From)]
 #[display(inner)]
@@ -244,6 +245,28 @@ impl<P: StateProvider> State<P> {
             .update_witnesses(resolver, after_height)
             .map_err(StateError::WriteProvider)
     }
+
+    pub fn update_witness(
+        &mut self,
+        witness_id: XWitnessId,
+        ord: WitnessOrd,
+    ) -> Result<bool, StateError<P>> {
+        self.provider
+            .update_witness(witness_id, ord)
+            .map_err(StateError::WriteProvider)
+    }
+
+    pub(super) fn remove_contract(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<bool, RemoveError<StateError<P>>> {
+        self.provider
+            .remove_contract(contract_id)
+            .map_err(|err| match err {
+                RemoveError::Unsupported => RemoveError::Unsupported,
+                RemoveError::Provider(err) => RemoveError::Provider(StateError::WriteProvider(err)),
+            })
+    }
 }
 
 impl<P: StateProvider> StoreTransaction for State<P> {
@@ -303,6 +326,27 @@ pub trait StateWriteProvider: StoreTransaction<TransactionErr = Self::Error> {
         resolver: impl ResolveWitness,
         after_height: u32,
     ) -> Result<UpdateRes, Self::Error>;
+
+    /// Sets the blockchain position of an already known witness, such that
+    /// the contract state gets recomputed when it is mined, re-orged out or
+    /// dropped from the mempool.
+    ///
+    /// Returns `false` if the witness is not known.
+    fn update_witness(
+        &mut self,
+        witness_id: XWitnessId,
+        ord: WitnessOrd,
+    ) -> Result<bool, Self::Error>;
+
+    /// Removes the state of the contract. Returns `false` if the contract
+    /// was not known. The default implementation fails with
+    /// [`RemoveError::Unsupported`].
+    fn remove_contract(
+        &mut self,
+        _contract_id: ContractId,
+    ) -> Result<bool, RemoveError<Self::Error>> {
+        Err(RemoveError::Unsupported)
+    }
 }
 
 pub trait ContractStateRead: ContractStateAccess {
@@ -313,6 +357,20 @@ pub trait ContractStateRead: ContractStateAccess {
     fn fungible_all(&self) -> impl Iterator<Item = &OutputAssignment<RevealedValue>>;
     fn data_all(&self) -> impl Iterator<Item = &OutputAssignment<RevealedData>>;
     fn attach_all(&self) -> impl Iterator<Item = &OutputAssignment<RevealedAttach>>;
+
+    /// Returns all contract allocations, of any state type.
+    fn allocations_all(&self) -> impl Iterator<Item = OwnedAllocation> {
+        self.rights_all()
+            .cloned()
+            .map(OutputAssignment::transmute)
+            .chain(
+                self.fungible_all()
+                    .cloned()
+                    .map(OutputAssignment::transmute),
+            )
+            .chain(self.data_all().cloned().map(OutputAssignment::transmute))
+            .chain(self.attach_all().cloned().map(OutputAssignment::transmute))
+    }
 }
 
 pub trait ContractStateWrite {
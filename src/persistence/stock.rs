@@ -19,47 +19,79 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::Infallible;
 use std::error::Error;
-use std::fmt::Debug;
-
-use amplify::confinement::{Confined, U24};
-use amplify::Wrapper;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::{io, iter};
+
+use amplify::confinement::{self, Confined, MediumBlob, U24};
+use amplify::{ByteArray, Wrapper};
 use bp::dbc::Method;
 use bp::seals::txout::CloseMethod;
-use bp::Vout;
+use bp::{Sats, ScriptPubkey, Vout, Weight};
 use chrono::Utc;
-use invoice::{Amount, Beneficiary, InvoiceState, NonFungible, RgbInvoice};
-use nonasync::persistence::{CloneNoPersistence, PersistenceError, PersistenceProvider};
-use rgb::validation::{DbcProof, ResolveWitness, WitnessResolverError};
+use commit_verify::Conceal;
+use invoice::{
+    Amount, Beneficiary, ChainNet, InvoiceState, NonFungible, RgbInvoice, RgbInvoiceBuilder,
+    XChainNet,
+};
+use nonasync::persistence::{
+    CloneNoPersistence, PersistenceError, PersistenceProvider, Persisting,
+};
+use rgb::validation::{
+    CheckedConsignment, ConsignmentApi, DbcProof, EAnchor, OpRef, ResolveWitness, Scripts, Warning,
+    WitnessResolverError,
+};
+use rgb::vm::{
+    ContractStateAccess, ContractStateEvolve, GlobalContractState, GlobalStateIter, OrdOpRef,
+    UnknownGlobalStateType, WitnessOrd, XWitnessTx,
+};
 use rgb::{
-    validation, AssignmentType, BlindingFactor, BundleId, ContractId, DataState, GraphSeal,
-    Identity, OpId, Operation, Opout, SchemaId, SecretSeal, Transition, TxoSeal, XChain, XOutpoint,
-    XOutputSeal, XWitnessId,
+    validation, AssignmentType, AttachId, AttachState, BlindingFactor, BundleId, ContractId,
+    DataState, Extension, FungibleState, Genesis, GlobalStateType, GraphSeal, Identity, Layer1,
+    OpId, Operation, Opout, Schema, SchemaId, SecretSeal, Transition, TransitionBundle, TxoSeal,
+    XChain, XOutpoint, XOutputSeal, XWitnessId,
 };
-use strict_encoding::FieldName;
+use strict_encoding::{FieldName, SerializeError, StreamWriter, StrictEncode};
+use strict_types::TypeSystem;
 
 use super::{
-    ContractStateRead, Index, IndexError, IndexInconsistency, IndexProvider, IndexReadProvider,
-    IndexWriteProvider, MemIndex, MemStash, MemState, PersistedState, SchemaIfaces, Stash,
-    StashDataError, StashError, StashInconsistency, StashProvider, StashReadProvider,
-    StashWriteProvider, State, StateError, StateInconsistency, StateProvider, StateReadProvider,
-    StateWriteProvider, StoreTransaction,
+    ConsignmentView, ContractStateRead, Index, IndexError, IndexInconsistency, IndexProvider,
+    IndexReadProvider, IndexWriteProvider, MemIndex, MemStash, MemState, PersistedState,
+    RemoveError, SchemaIfaces, Stash, StashDataError, StashError, StashInconsistency,
+    StashProvider, StashReadProvider, StashWriteProvider, State, StateError, StateInconsistency,
+    StateProvider, StateReadProvider, StateWriteProvider, StockSettings, StoreTransaction,
+    VerifyPlugin,
 };
 use crate::containers::{
-    AnchorSet, AnchoredBundleMismatch, Batch, BuilderSeal, ClientBundle, Consignment, ContainerVer,
-    ContentId, ContentRef, Contract, Fascia, Kit, SealWitness, SupplItem, SupplSub, Transfer,
-    TransitionDichotomy, TransitionInfo, TransitionInfoError, UnrelatedTransition,
+    check_timestamp, AnchorSet, AnchoredBundleMismatch, Batch, BuilderSeal, ClientBundle,
+    Consignment, ContainerVer, ContentId, ContentRef, ContentSigner, Contract, ContractTags,
+    CyclicDependency, DocCommitment, DocProof, Fascia, FileContent, Kit, LoadError, MultiTransfer,
+    SealWitness, SupplItem, SupplSub, Supplement, TerminalError, TimestampError, TimestampPolicy,
+    Transfer, TransitionDichotomy, TransitionInfo, TransitionInfoError, UnrelatedTransition,
     ValidConsignment, ValidContract, ValidKit, ValidTransfer, VelocityHint, WitnessBundle,
-    SUPPL_ANNOT_VELOCITY,
+    SUPPL_ANNOT_TAGS, SUPPL_ANNOT_VELOCITY,
 };
+use crate::contract::TypedAssignsExt;
 use crate::info::{ContractInfo, IfaceInfo, SchemaInfo};
+use crate::interface::resolver::DumbResolver;
+#[cfg(feature = "async")]
+use crate::interface::resolver::PrefetchedResolver;
+#[cfg(feature = "async")]
+use crate::interface::AsyncResolveWitness;
 use crate::interface::{
-    BuilderError, ContractBuilder, ContractIface, Iface, IfaceClass, IfaceId, IfaceRef,
-    IfaceWrapper, TransitionBuilder,
+    AllocatedState, AssignmentsFilter, BlankTransitionBuilder, BuilderError, CoinSelect,
+    ContractBuilder, ContractIface, ContractOp, ExtensionBuilder, Iface, IfaceClass, IfaceId,
+    IfaceRef, IfaceWrapper, OwnedAllocation, SchemaViolation, TransitionBuilder,
 };
+use crate::stl::contract_id_sem_id;
 use crate::MergeRevealError;
 
 pub type ContractAssignments = HashMap<XOutputSeal, HashMap<Opout, PersistedState>>;
@@ -114,6 +146,47 @@ pub enum StockError<
 
     /// witness {0} can't be resolved: {1}
     WitnessUnresolved(XWitnessId, WitnessResolverError),
+
+    #[from]
+    #[display(doc_comments)]
+    /// the consumed contract has insane timestamp: {0}
+    Timestamp(TimestampError),
+
+    /// consumption of the consignment was aborted by the caller.
+    Aborted,
+
+    /// consignment was rejected by the verification plugin '{0}': {1}
+    PluginRejected(String, String),
+
+    /// contract {0} doesn't belong to {1} network.
+    NetworkMismatch(ContractId, ChainNet),
+
+    /// the persistence provider doesn't support data removal.
+    RemovalUnsupported,
+
+    /// bundle {0} of the outgoing transfer was not created by this stock.
+    OutgoingUnknown(BundleId),
+
+    /// contract {0} is quarantined since its history doesn't match the chain
+    /// known to the resolver; check the backend configuration and release the
+    /// contract from the quarantine before sending its state.
+    Quarantined(ContractId),
+
+    #[from]
+    #[display(inner)]
+    Quota(QuotaError),
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider, E: Error, X: Error> From<RemoveError<X>>
+    for StockError<S, H, P, E>
+where Self: From<X>
+{
+    fn from(err: RemoveError<X>) -> Self {
+        match err {
+            RemoveError::Unsupported => Self::RemovalUnsupported,
+            RemoveError::Provider(err) => err.into(),
+        }
+    }
 }
 
 impl<S: StashProvider, H: StateProvider, P: IndexProvider, E: Error> From<StashError<S>>
@@ -183,6 +256,18 @@ pub enum ConsignError {
 
     /// the spent state from transition {1} inside bundle {0} is concealed.
     Concealed(BundleId, OpId),
+
+    /// requested terminal seal {0} is not assigned by any of the contract
+    /// state transitions.
+    NoTerminal(XChain<SecretSeal>),
+
+    #[from]
+    #[display(inner)]
+    Terminal(TerminalError),
+
+    #[from]
+    #[display(inner)]
+    Cycle(CyclicDependency),
 }
 
 impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<ConsignError>
@@ -197,6 +282,56 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<MergeRevealError
     fn from(err: MergeRevealError) -> Self { Self::InvalidInput(err.into()) }
 }
 
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ArticlesError {
+    #[from]
+    #[display(inner)]
+    Consign(ConsignError),
+
+    #[from]
+    #[display(inner)]
+    Load(LoadError),
+
+    /// unable to write contract articles: {0}
+    #[from]
+    Io(io::Error),
+
+    /// the provided contract file contains state history and can't be used
+    /// as contract articles.
+    NotArticles,
+
+    /// invalid contract articles.
+    ///
+    /// {0}
+    Invalid(validation::Status),
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<ArticlesError>
+    for StockError<S, H, P, ArticlesError>
+{
+    fn from(err: ArticlesError) -> Self { Self::InvalidInput(err) }
+}
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum AcceptError {
+    #[from]
+    #[display(inner)]
+    Load(LoadError),
+
+    /// invalid transfer.
+    ///
+    /// {0}
+    Invalid(validation::Status),
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<AcceptError>
+    for StockError<S, H, P, AcceptError>
+{
+    fn from(err: AcceptError) -> Self { Self::InvalidInput(err) }
+}
+
 impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<UnrelatedTransition>
     for StockError<S, H, P, ConsignError>
 {
@@ -225,12 +360,21 @@ pub enum ComposeError {
     /// expired invoice.
     InvoiceExpired,
 
-    /// the invoice contains no contract information.
+    /// the invoice contains no contract information, which must be provided by
+    /// the payer with `RgbInvoice::set_contract`.
     NoContract,
 
-    /// the invoice contains no interface information.
+    /// the invoice contains no interface information and the contract doesn't
+    /// have a default interface.
     NoIface,
 
+    /// the invoice doesn't specify the amount or other state to pay, which
+    /// must be provided by the payer with `RgbInvoice::set_state`.
+    NoState,
+
+    /// paying attachments with invoices is not supported.
+    UnsupportedState,
+
     /// the invoice requirements can't be fulfilled using available assets or
     /// smart contract state.
     InsufficientState,
@@ -271,6 +415,81 @@ pub enum FasciaError {
     InvalidBundle(ContractId, BundleId),
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum TransferError {
+    /// the signed witness doesn't anchor state transitions of contract {0}
+    /// being transferred.
+    UnrelatedFascia(ContractId),
+
+    /// the signed witness doesn't commit to the state transition {1} of
+    /// contract {0} from the transfer draft.
+    UncommittedTransition(ContractId, OpId),
+
+    #[from]
+    #[display(inner)]
+    Fascia(FasciaError),
+
+    #[from]
+    #[display(inner)]
+    Consign(ConsignError),
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<TransferError>
+    for StockError<S, H, P, TransferError>
+{
+    fn from(err: TransferError) -> Self { Self::InvalidInput(err) }
+}
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum SubmitError {
+    #[from]
+    #[display(inner)]
+    Fascia(FasciaError),
+
+    #[from]
+    #[display(inner)]
+    Consign(ConsignError),
+
+    /// the witness doesn't anchor bundle {1} of contract {0} with the bundle
+    /// close method.
+    InvalidAnchor(ContractId, BundleId),
+
+    /// operations submitted for contract {0} are invalid.
+    ///
+    /// {1}
+    Invalid(ContractId, validation::Status),
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<SubmitError>
+    for StockError<S, H, P, SubmitError>
+{
+    fn from(err: SubmitError) -> Self { Self::InvalidInput(err) }
+}
+
+/// Transfer composed with [`Stock::compose_transfer`] which awaits signing of
+/// its witness transaction. Once signed, it gets completed with
+/// [`Stock::complete_transfer`].
+#[derive(Clone, Debug)]
+pub struct TransferDraft {
+    contract_id: ContractId,
+    beneficiary: DraftBeneficiary,
+    /// State transitions which must be committed to in the witness
+    /// transaction.
+    pub batch: Batch,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum DraftBeneficiary {
+    Terminal(XChain<SecretSeal>),
+    WitnessVout(Vout),
+}
+
+impl TransferDraft {
+    pub fn contract_id(&self) -> ContractId { self.contract_id }
+}
+
 impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<FasciaError>
     for StockError<S, H, P, FasciaError>
 {
@@ -283,6 +502,11 @@ pub enum ContractIfaceError {
     /// no known implementations of {0::<0} parent interfaces for
     /// the schema {1::<0}.
     NoAbstractImpl(IfaceId, SchemaId),
+
+    /// contract {0} doesn't have a default interface since it implements
+    /// either none or multiple known interfaces; the interface must be
+    /// provided explicitly.
+    NoDefaultIface(ContractId),
 }
 
 impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<ContractIfaceError>
@@ -291,6 +515,39 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<ContractIfaceErr
     fn from(err: ContractIfaceError) -> Self { Self::InvalidInput(err) }
 }
 
+/// Errors detected by [`Stock::check_consistency`], indicating that stash,
+/// state and index data do not belong to each other (for instance, when some
+/// of the data files were copied from a different location).
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ConsistencyError {
+    /// genesis of contract {0} is present in the stash, but the contract state
+    /// is absent.
+    StateAbsent(ContractId),
+
+    /// state of contract {0} refers to schema {1}, while the contract genesis
+    /// uses schema {2}.
+    SchemaMismatch(ContractId, SchemaId, SchemaId),
+
+    /// bundle {0} is present in the stash but is not indexed.
+    BundleNotIndexed(BundleId),
+
+    /// bundle {0} is indexed under contract {1}, which genesis is absent in the
+    /// stash.
+    BundleContractAbsent(BundleId, ContractId),
+
+    /// operation {0} from bundle {1} is either not indexed or indexed under a
+    /// different bundle.
+    OperationNotIndexed(OpId, BundleId),
+
+    /// stash contains {stashed} bundles, while {indexed} bundles are indexed.
+    BundleCountMismatch { stashed: usize, indexed: usize },
+}
+
+impl From<Infallible> for ConsistencyError {
+    fn from(_: Infallible) -> Self { unreachable!() }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
 #[display(inner)]
 pub enum InputError {
@@ -325,6 +582,14 @@ macro_rules! stock_err_conv {
                     StockError::StateInconsistency(e) => StockError::StateInconsistency(e),
                     StockError::IndexInconsistency(e) => StockError::IndexInconsistency(e),
                     StockError::WitnessUnresolved(id, e) => StockError::WitnessUnresolved(id, e),
+                    StockError::Timestamp(e) => StockError::Timestamp(e),
+                    StockError::Aborted => StockError::Aborted,
+                    StockError::PluginRejected(name, msg) => StockError::PluginRejected(name, msg),
+                    StockError::NetworkMismatch(id, net) => StockError::NetworkMismatch(id, net),
+                    StockError::RemovalUnsupported => StockError::RemovalUnsupported,
+                    StockError::OutgoingUnknown(id) => StockError::OutgoingUnknown(id),
+                    StockError::Quarantined(id) => StockError::Quarantined(id),
+                    StockError::Quota(e) => StockError::Quota(e),
                 }
             }
         }
@@ -346,11 +611,33 @@ impl From<Infallible> for FasciaError {
 impl From<Infallible> for ContractIfaceError {
     fn from(_: Infallible) -> Self { unreachable!() }
 }
+impl From<Infallible> for TransferError {
+    fn from(_: Infallible) -> Self { unreachable!() }
+}
+impl From<Infallible> for ArticlesError {
+    fn from(_: Infallible) -> Self { unreachable!() }
+}
+impl From<Infallible> for SubmitError {
+    fn from(_: Infallible) -> Self { unreachable!() }
+}
+impl From<Infallible> for AcceptError {
+    fn from(_: Infallible) -> Self { unreachable!() }
+}
 
 stock_err_conv!(Infallible, ComposeError);
 stock_err_conv!(Infallible, ConsignError);
 stock_err_conv!(Infallible, FasciaError);
 stock_err_conv!(Infallible, ContractIfaceError);
+stock_err_conv!(Infallible, ArticlesError);
+stock_err_conv!(Infallible, TransferError);
+stock_err_conv!(FasciaError, TransferError);
+stock_err_conv!(ConsignError, TransferError);
+stock_err_conv!(ConsignError, ArticlesError);
+stock_err_conv!(Infallible, SubmitError);
+stock_err_conv!(Infallible, AcceptError);
+stock_err_conv!(FasciaError, SubmitError);
+stock_err_conv!(ConsignError, SubmitError);
+stock_err_conv!(Infallible, ConsistencyError);
 stock_err_conv!(Infallible, InputError);
 stock_err_conv!(ComposeError, InputError);
 stock_err_conv!(ConsignError, InputError);
@@ -369,6 +656,15 @@ pub struct Stock<
     stash: Stash<S>,
     state: State<H>,
     index: Index<P>,
+    settings: StockSettings,
+    plugins: BTreeMap<SchemaId, Vec<Arc<dyn VerifyPlugin>>>,
+    default_signer: Option<Arc<dyn ContentSigner>>,
+    quota: Option<Arc<dyn StorageQuota>>,
+    subscribers: Vec<mpsc::Sender<StockEvent>>,
+    deferred: Option<Vec<StockEvent>>,
+    /// Fee and virtual size of witness transactions, see
+    /// [`Stock::witness_costs`].
+    costs: Mutex<HashMap<XWitnessId, (Sats, u32)>>,
 }
 
 impl<S: StashProvider, H: StateProvider, P: IndexProvider> CloneNoPersistence for Stock<S, H, P> {
@@ -377,6 +673,13 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> CloneNoPersistence fo
             stash: self.stash.clone_no_persistence(),
             state: self.state.clone_no_persistence(),
             index: self.index.clone_no_persistence(),
+            settings: self.settings.clone_no_persistence(),
+            plugins: self.plugins.clone(),
+            default_signer: self.default_signer.clone(),
+            quota: self.quota.clone(),
+            subscribers: none!(),
+            deferred: None,
+            costs: none!(),
         }
     }
 }
@@ -392,6 +695,13 @@ where
             stash: default!(),
             state: default!(),
             index: default!(),
+            settings: default!(),
+            plugins: empty!(),
+            default_signer: None,
+            quota: None,
+            subscribers: none!(),
+            deferred: None,
+            costs: none!(),
         }
     }
 }
@@ -404,16 +714,127 @@ impl Stock {
 }
 
 impl<S: StashProvider, H: StateProvider, I: IndexProvider> Stock<S, H, I> {
+    /// Loads the stock, checking that its stash, state and index data belong
+    /// to each other: each contract must have a state with a matching schema,
+    /// and the numbers of stashed and indexed bundles must match. The check
+    /// doesn't read the bundles; see [`Stock::check_consistency`] for the full
+    /// check.
     pub fn load<P>(provider: P, autosave: bool) -> Result<Self, PersistenceError>
+    where
+        P: Clone
+            + PersistenceProvider<S>
+            + PersistenceProvider<H>
+            + PersistenceProvider<I>
+            + PersistenceProvider<StockSettings>
+            + 'static,
+        StockError<S, H, I>: Send + 'static,
+    {
+        let stash = S::load(provider.clone(), autosave)?;
+        let state = H::load(provider.clone(), autosave)?;
+        let index = I::load(provider.clone(), autosave)?;
+        let mut stock = Self::with(stash, state, index);
+        stock.settings = StockSettings::load(provider, autosave)?;
+        if let Some(err) = stock
+            .find_inconsistency(false)
+            .map_err(PersistenceError::with)?
+        {
+            return Err(PersistenceError::with(err));
+        }
+        Ok(stock)
+    }
+
+    /// Loads the stock without checking consistency of its stash, state and
+    /// index data.
+    ///
+    /// Intended for repairing a stock which fails to [`Stock::load`] (for
+    /// instance, after a crash in the middle of a write): the caller may
+    /// inspect [`Stock::check_consistency`] result and purge or re-import the
+    /// affected contracts.
+    pub fn load_unchecked<P>(provider: P, autosave: bool) -> Result<Self, PersistenceError>
     where P: Clone
             + PersistenceProvider<S>
             + PersistenceProvider<H>
             + PersistenceProvider<I>
+            + PersistenceProvider<StockSettings>
             + 'static {
         let stash = S::load(provider.clone(), autosave)?;
         let state = H::load(provider.clone(), autosave)?;
-        let index = I::load(provider, autosave)?;
-        Ok(Self::with(stash, state, index))
+        let index = I::load(provider.clone(), autosave)?;
+        let mut stock = Self::with(stash, state, index);
+        stock.settings = StockSettings::load(provider, autosave)?;
+        Ok(stock)
+    }
+
+    /// Checks that stash, state and index data are consistent with each other:
+    /// each contract known to the stash has a state with a matching schema,
+    /// and each bundle and operation from the stash is indexed under a known
+    /// contract.
+    ///
+    /// Inconsistencies are reported as [`StockError::InvalidInput`]; other
+    /// errors are failures to read the stock data. Unlike the check done by
+    /// [`Stock::load`], which compares only the number of stashed and indexed
+    /// bundles, this reads all bundles from the stash.
+    pub fn check_consistency(&self) -> Result<(), StockError<S, H, I, ConsistencyError>> {
+        match self.find_inconsistency(true)? {
+            None => Ok(()),
+            Some(err) => Err(StockError::InvalidInput(err)),
+        }
+    }
+
+    fn find_inconsistency(
+        &self,
+        full: bool,
+    ) -> Result<Option<ConsistencyError>, StockError<S, H, I>> {
+        let mut contracts = HashSet::new();
+        for genesis in self.stash.geneses()? {
+            let contract_id = genesis.contract_id();
+            contracts.insert(contract_id);
+            // State providers report unknown contracts as read errors.
+            let Ok(state) = self.state.contract_state(contract_id) else {
+                return Ok(Some(ConsistencyError::StateAbsent(contract_id)));
+            };
+            if state.schema_id() != genesis.schema_id {
+                return Ok(Some(ConsistencyError::SchemaMismatch(
+                    contract_id,
+                    state.schema_id(),
+                    genesis.schema_id,
+                )));
+            }
+        }
+
+        let mut stashed = 0usize;
+        for bundle_id in self.stash.bundle_ids()? {
+            stashed += 1;
+            if !full {
+                continue;
+            }
+            let contract_id = match self.index.bundle_info(bundle_id) {
+                Ok((_, contract_id)) => contract_id,
+                Err(IndexError::Inconsistency(_)) => {
+                    return Ok(Some(ConsistencyError::BundleNotIndexed(bundle_id)));
+                }
+                Err(err) => return Err(err.into()),
+            };
+            if !contracts.contains(&contract_id) {
+                return Ok(Some(ConsistencyError::BundleContractAbsent(bundle_id, contract_id)));
+            }
+            let bundle = self.stash.bundle(bundle_id)?;
+            for opid in bundle.known_transitions.keys() {
+                match self.index.bundle_id_for_op(*opid) {
+                    Ok(id) if id == bundle_id => {}
+                    Ok(_) | Err(IndexError::Inconsistency(_)) => {
+                        return Ok(Some(ConsistencyError::OperationNotIndexed(*opid, bundle_id)));
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+
+        let indexed = self.index.bundle_count()?;
+        if stashed != indexed {
+            return Ok(Some(ConsistencyError::BundleCountMismatch { stashed, indexed }));
+        }
+        Ok(None)
     }
 
     pub fn make_persistent<P>(
@@ -426,6 +847,7 @@ impl<S: StashProvider, H: StateProvider, I: IndexProvider> Stock<S, H, I> {
             + PersistenceProvider<S>
             + PersistenceProvider<H>
             + PersistenceProvider<I>
+            + PersistenceProvider<StockSettings>
             + 'static,
     {
         let a = self
@@ -436,8 +858,9 @@ impl<S: StashProvider, H: StateProvider, I: IndexProvider> Stock<S, H, I> {
             .make_persistent(provider.clone(), autosave)?;
         let c = self
             .as_index_provider_mut()
-            .make_persistent(provider, autosave)?;
-        Ok(a && b && c)
+            .make_persistent(provider.clone(), autosave)?;
+        let d = self.settings.make_persistent(provider, autosave)?;
+        Ok(a && b && c && d)
     }
 
     pub fn store(&mut self) -> Result<(), PersistenceError> {
@@ -446,84 +869,507 @@ impl<S: StashProvider, H: StateProvider, I: IndexProvider> Stock<S, H, I> {
         self.as_stash_provider_mut().store()?;
         self.as_state_provider_mut().store()?;
         self.as_index_provider_mut().store()?;
+        self.settings.store()?;
 
         Ok(())
     }
-}
 
-impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
-    pub fn with(stash_provider: S, state_provider: H, index_provider: P) -> Self {
-        Stock {
-            stash: Stash::new(stash_provider),
-            state: State::new(state_provider),
-            index: Index::new(index_provider),
-        }
+    /// Creates a deep copy of the stock, persisted with a different
+    /// `provider` (like a fresh data directory). The stash, state and index
+    /// data are copied as-is, without re-importing and re-validating the
+    /// contracts, which makes it cheap to branch test scenarios from a common
+    /// fixture.
+    pub fn clone_to<P>(&self, provider: P, autosave: bool) -> Result<Self, PersistenceError>
+    where P: Clone
+            + PersistenceProvider<S>
+            + PersistenceProvider<H>
+            + PersistenceProvider<I>
+            + PersistenceProvider<StockSettings>
+            + 'static {
+        let mut stock = self.clone_no_persistence();
+        stock.make_persistent(provider, autosave)?;
+        stock.store()?;
+        Ok(stock)
     }
+}
 
-    #[doc(hidden)]
-    pub fn as_stash_provider(&self) -> &S { self.stash.as_provider() }
-    #[doc(hidden)]
-    pub fn as_state_provider(&self) -> &H { self.state.as_provider() }
-    #[doc(hidden)]
-    pub fn as_index_provider(&self) -> &P { self.index.as_provider() }
+/// Estimated size of a consignment, computed by
+/// [`Stock::estimate_consignment_size`] without constructing the consignment.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct ConsignmentEstimate {
+    /// Number of operations (including genesis) in the consignment.
+    pub operations: usize,
+    /// Number of witness transactions in the consignment.
+    pub witnesses: usize,
+    /// Approximate size of the serialized consignment, in bytes. Does not
+    /// account for supplements and signatures.
+    pub bytes: usize,
+}
 
-    #[doc(hidden)]
-    pub fn as_stash_provider_mut(&mut self) -> &mut S { self.stash.as_provider_mut() }
-    #[doc(hidden)]
-    pub fn as_state_provider_mut(&mut self) -> &mut H { self.state.as_provider_mut() }
-    #[doc(hidden)]
-    pub fn as_index_provider_mut(&mut self) -> &mut P { self.index.as_provider_mut() }
+impl ConsignmentEstimate {
+    /// Checks whether the consignment would exceed the size limit of a
+    /// transport, in bytes.
+    pub fn exceeds(&self, limit: usize) -> bool { self.bytes > limit }
+}
 
-    pub fn ifaces(&self) -> Result<impl Iterator<Item = IfaceInfo> + '_, StockError<S, H, P>> {
-        let names = self
-            .stash
-            .ifaces()?
-            .map(|iface| (iface.iface_id(), iface.name.clone()))
-            .collect::<HashMap<_, _>>();
-        Ok(self.stash.ifaces()?.map(move |iface| {
-            let suppl = self
-                .stash
-                .supplement(ContentRef::Iface(iface.iface_id()))
-                .ok()
-                .flatten();
-            IfaceInfo::new(iface, &names, suppl)
-        }))
-    }
-    pub fn iface(&self, iface: impl Into<IfaceRef>) -> Result<&Iface, StockError<S, H, P>> {
-        Ok(self.stash.iface(iface)?)
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum QuotaError {
+    /// storing additional {required} bytes of data would exceed the storage
+    /// quota of {quota} bytes, since {used} bytes are already used.
+    Exceeded {
+        used: u64,
+        required: u64,
+        quota: u64,
+    },
+
+    #[from]
+    #[display(inner)]
+    Io(io::Error),
+}
+
+/// Limit on the storage space taken by the stock data.
+///
+/// The stock checks the quota before consuming new data, estimating the
+/// required space by the size of the consumed data, such that data which
+/// would exceed the quota are rejected before anything gets written.
+pub trait StorageQuota: Debug + Send + Sync {
+    /// Checks whether additional `required` bytes of data fit into the quota.
+    fn check_space(&self, required: u64) -> Result<(), QuotaError>;
+}
+
+/// Writer which only counts the number of bytes written to it.
+#[derive(Default)]
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
     }
-    pub fn schemata(&self) -> Result<impl Iterator<Item = SchemaInfo> + '_, StockError<S, H, P>> {
-        Ok(self.stash.schemata()?.map(SchemaInfo::with))
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+fn encoded_len(data: &impl StrictEncode) -> usize {
+    let mut counter = ByteCounter::default();
+    data.strict_write(StreamWriter::new::<{ usize::MAX }>(&mut counter))
+        .expect("in-memory counter doesn't error");
+    counter.0
+}
+
+/// Progress of consuming a consignment into the [`Stock`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct ConsumeProgress {
+    /// Number of operations read from the consignment.
+    pub operations: usize,
+    /// Total number of operations in the consignment.
+    pub operations_total: usize,
+    /// Number of witnesses which were resolved and verified.
+    pub witnesses: usize,
+    /// Total number of witnesses in the consignment.
+    pub witnesses_total: usize,
+    /// Number of consignment bytes processed.
+    pub bytes: usize,
+    /// Total size of the strict-encoded consignment in bytes.
+    pub bytes_total: usize,
+    /// Whether the consignment data were written to the stock. The data are
+    /// committed only after the callback returns for this report, thus it is
+    /// still possible to abort the operation at this stage.
+    pub stored: bool,
+}
+
+/// Resolver caching witness positions resolved in advance by the
+/// stock before consuming a consignment.
+pub(super) struct ResolvedWitnesses<R: ResolveWitness> {
+    inner: R,
+    ords: HashMap<XWitnessId, WitnessOrd>,
+}
+
+impl<R: ResolveWitness> ResolvedWitnesses<R> {
+    pub(super) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            ords: empty!(),
+        }
     }
-    pub fn schema(&self, schema_id: SchemaId) -> Result<&SchemaIfaces, StockError<S, H, P>> {
-        Ok(self.stash.schema(schema_id)?)
+
+    pub(super) fn resolve(&mut self, witness_id: XWitnessId) -> Result<(), WitnessResolverError> {
+        if !self.ords.contains_key(&witness_id) {
+            let ord = self.inner.resolve_pub_witness_ord(witness_id)?;
+            self.ords.insert(witness_id, ord);
+        }
+        Ok(())
     }
+}
 
-    pub fn contracts(
+impl<R: ResolveWitness> ResolveWitness for ResolvedWitnesses<R> {
+    fn resolve_pub_witness(
         &self,
-    ) -> Result<impl Iterator<Item = ContractInfo> + '_, StockError<S, H, P>> {
-        Ok(self.stash.geneses()?.map(ContractInfo::with))
+        witness_id: XWitnessId,
+    ) -> Result<XWitnessTx, WitnessResolverError> {
+        self.inner.resolve_pub_witness(witness_id)
     }
 
-    #[allow(clippy::multiple_bound_locations, clippy::type_complexity)]
-    pub fn contracts_by<'a, C: IfaceClass + 'a>(
-        &'a self,
-    ) -> Result<
-        impl Iterator<
-                Item = <C::Wrapper<H::ContractRead<'a>> as IfaceWrapper<H::ContractRead<'a>>>::Info,
-            > + 'a,
-        StockError<S, H, P>,
-    > {
-        Ok(self.stash.geneses_by::<C>()?.filter_map(|genesis| {
-            self.contract_iface_class::<C>(genesis.contract_id())
-                .as_ref()
-                .map(<C::Wrapper<H::ContractRead<'_>> as IfaceWrapper<H::ContractRead<'_>>>::info)
-                .ok()
-        }))
+    fn resolve_pub_witness_ord(
+        &self,
+        witness_id: XWitnessId,
+    ) -> Result<WitnessOrd, WitnessResolverError> {
+        match self.ords.get(&witness_id) {
+            Some(ord) => Ok(*ord),
+            None => self.inner.resolve_pub_witness_ord(witness_id),
+        }
     }
+}
 
-    /// Iterates over ids of all contract assigning state to the provided set of
-    /// output seals.
+/// Contract history known to the stock, provided to the schema validation of
+/// operations which are not yet added to the stock.
+struct StockHistory<'s, S: StashProvider, H: StateProvider, P: IndexProvider> {
+    stock: &'s Stock<S, H, P>,
+    schema: &'s Schema,
+    genesis: &'s Genesis,
+    types: TypeSystem,
+    scripts: Scripts,
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> ConsignmentApi
+    for StockHistory<'_, S, H, P>
+{
+    fn schema(&self) -> &Schema { self.schema }
+
+    fn types(&self) -> &TypeSystem { &self.types }
+
+    fn scripts(&self) -> &Scripts { &self.scripts }
+
+    fn operation(&self, opid: OpId) -> Option<OpRef> {
+        if opid == self.genesis.id() {
+            return Some(OpRef::Genesis(self.genesis));
+        }
+        match self.stock.transition(opid) {
+            Ok(transition) => Some(OpRef::Transition(transition)),
+            Err(_) => self.stock.stash.extension(opid).ok().map(OpRef::Extension),
+        }
+    }
+
+    fn genesis(&self) -> &Genesis { self.genesis }
+
+    fn bundle_ids<'iter>(&self) -> impl Iterator<Item = BundleId> + 'iter { iter::empty() }
+
+    fn bundle(&self, _: BundleId) -> Option<&TransitionBundle> { None }
+
+    fn anchor(&self, _: BundleId) -> Option<(XWitnessId, &EAnchor)> { None }
+
+    fn op_witness_id(&self, _: OpId) -> Option<XWitnessId> { None }
+}
+
+/// Contract state provided to the schema validation of operations which are
+/// not yet added to the stock. The validation doesn't evolve the state.
+#[derive(Debug)]
+struct FrozenState<C: ContractStateAccess>(C);
+
+impl<C: ContractStateAccess> ContractStateAccess for FrozenState<C> {
+    fn global(
+        &self,
+        ty: GlobalStateType,
+    ) -> Result<GlobalContractState<impl GlobalStateIter>, UnknownGlobalStateType> {
+        self.0.global(ty)
+    }
+
+    fn rights(&self, outpoint: XOutpoint, ty: AssignmentType) -> u32 { self.0.rights(outpoint, ty) }
+
+    fn fungible(
+        &self,
+        outpoint: XOutpoint,
+        ty: AssignmentType,
+    ) -> impl DoubleEndedIterator<Item = FungibleState> {
+        self.0.fungible(outpoint, ty)
+    }
+
+    fn data(
+        &self,
+        outpoint: XOutpoint,
+        ty: AssignmentType,
+    ) -> impl DoubleEndedIterator<Item = impl Borrow<DataState>> {
+        self.0.data(outpoint, ty)
+    }
+
+    fn attach(
+        &self,
+        outpoint: XOutpoint,
+        ty: AssignmentType,
+    ) -> impl DoubleEndedIterator<Item = impl Borrow<AttachState>> {
+        self.0.attach(outpoint, ty)
+    }
+}
+
+impl<C: ContractStateAccess> ContractStateEvolve for FrozenState<C> {
+    type Context<'ctx> = C;
+
+    fn init(context: C) -> Self { Self(context) }
+
+    fn evolve_state(&mut self, _: OrdOpRef) -> Result<(), confinement::Error> { Ok(()) }
+}
+
+/// Constructs client-side part of the anchor for the bundle using the
+/// witness anchors matching the bundle close method.
+fn anchor_bundle(
+    contract_id: ContractId,
+    bundle: TransitionBundle,
+    witness: &SealWitness,
+) -> Result<ClientBundle, StashInconsistency> {
+    let bundle_id = bundle.bundle_id();
+    let (merkle_block, dbc) = match (bundle.close_method, &witness.anchors) {
+        (
+            CloseMethod::TapretFirst,
+            AnchorSet::Tapret(tapret) | AnchorSet::Double { tapret, .. },
+        ) => (&tapret.mpc_proof, DbcProof::Tapret(tapret.dbc_proof.clone())),
+        (CloseMethod::OpretFirst, AnchorSet::Opret(opret) | AnchorSet::Double { opret, .. }) => {
+            (&opret.mpc_proof, DbcProof::Opret(opret.dbc_proof))
+        }
+        _ => {
+            return Err(StashInconsistency::BundleMissedInAnchors(bundle_id, contract_id));
+        }
+    };
+    let Ok(mpc_proof) = merkle_block.to_merkle_proof(contract_id.into()) else {
+        return Err(StashInconsistency::WitnessMissesContract(
+            witness.witness_id(),
+            bundle_id,
+            contract_id,
+            CloseMethod::OpretFirst,
+        ));
+    };
+
+    // TODO: Conceal all transitions except the one we need
+
+    Ok(ClientBundle::new(mpc_proof, dbc, bundle))
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
+    pub fn with(stash_provider: S, state_provider: H, index_provider: P) -> Self {
+        Stock {
+            stash: Stash::new(stash_provider),
+            state: State::new(state_provider),
+            index: Index::new(index_provider),
+            settings: default!(),
+            plugins: empty!(),
+            default_signer: None,
+            quota: None,
+            subscribers: none!(),
+            deferred: None,
+            costs: none!(),
+        }
+    }
+
+    /// Sets how genesis timestamp sanity failures are handled when contracts
+    /// and transfers are consumed, or issued with the contract builders
+    /// created by the stock. Defaults to [`TimestampPolicy::Warn`].
+    ///
+    /// The policy is persisted together with other stock settings.
+    pub fn set_timestamp_policy(&mut self, policy: TimestampPolicy) {
+        self.settings.set_timestamp_policy(policy);
+    }
+
+    pub fn timestamp_policy(&self) -> TimestampPolicy { self.settings.timestamp_policy() }
+
+    /// Restricts the stock to contracts of a single network. Contract
+    /// builders created by the stock are configured for this network, and
+    /// consuming contracts and transfers issued for a different network type
+    /// (mainnet or testnet) fails with [`StockError::NetworkMismatch`].
+    ///
+    /// The network is persisted with the stock, such that a stock reloaded
+    /// from the data directory of one network can't silently accept contracts
+    /// of another one. Wallets managing several networks keep a separate stock
+    /// for each of them (e.g. using `FsBinStore::with_network`).
+    pub fn set_chain_net(&mut self, chain_net: ChainNet) {
+        self.settings.set_chain_net(Some(chain_net));
+    }
+
+    pub fn chain_net(&self) -> Option<ChainNet> { self.settings.chain_net() }
+
+    /// Limits the storage space taken by the stock data, for instance with
+    /// `FsBinStore` configured with a quota. Consuming contracts, transfers,
+    /// fascia and kits which don't fit into the quota fails with
+    /// [`StockError::Quota`], leaving the stock unchanged.
+    pub fn set_quota(&mut self, quota: impl StorageQuota + 'static) {
+        self.quota = Some(Arc::new(quota));
+    }
+
+    fn check_quota<E: Error>(
+        &self,
+        data: &impl StrictEncode,
+    ) -> Result<(), StockError<S, H, P, E>> {
+        if let Some(quota) = &self.quota {
+            quota.check_space(encoded_len(data) as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Subscribes to the changes of the stock data, allowing wallet UIs to
+    /// refresh without polling. Events are delivered to all subscribers;
+    /// subscribers are removed once their receivers are dropped.
+    pub fn subscribe(&mut self) -> mpsc::Receiver<StockEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    fn notify(&mut self, event: StockEvent) {
+        if let Some(deferred) = &mut self.deferred {
+            deferred.push(event);
+            return;
+        }
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Registers a wallet-local verification plugin which will be run on each
+    /// consumed contract or transfer consignment under the given schema.
+    pub fn register_plugin(&mut self, schema_id: SchemaId, plugin: Arc<dyn VerifyPlugin>) {
+        self.plugins.entry(schema_id).or_default().push(plugin);
+    }
+
+    /// Sets the issuer identity used by [`Self::default_contract_builder`]
+    /// and by [`Self::contract_builder`] when it is called with an anonymous
+    /// issuer, optionally with a signer producing signatures over the
+    /// contracts issued under this identity.
+    ///
+    /// The issuer identity is persisted with the stock. The signer holds
+    /// private keys and is never persisted: wallets provide it again with
+    /// [`Self::set_default_signer`] each time the stock is loaded.
+    pub fn set_default_issuer(&mut self, issuer: Identity, signer: Option<Arc<dyn ContentSigner>>) {
+        self.settings.set_default_issuer(Some(issuer));
+        self.default_signer = signer;
+    }
+
+    /// Sets the signer for the default issuer restored from the persisted
+    /// stock.
+    pub fn set_default_signer(&mut self, signer: Arc<dyn ContentSigner>) {
+        self.default_signer = Some(signer);
+    }
+
+    /// Removes the default issuer together with its signer.
+    pub fn reset_default_issuer(&mut self) {
+        self.settings.set_default_issuer(None);
+        self.default_signer = None;
+    }
+
+    pub fn default_issuer(&self) -> Option<&Identity> { self.settings.default_issuer() }
+
+    /// Returns verification plugins registered for the given schema.
+    pub fn plugins(&self, schema_id: SchemaId) -> &[Arc<dyn VerifyPlugin>] {
+        self.plugins
+            .get(&schema_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    #[doc(hidden)]
+    pub fn as_stash_provider(&self) -> &S { self.stash.as_provider() }
+    #[doc(hidden)]
+    pub fn as_state_provider(&self) -> &H { self.state.as_provider() }
+    #[doc(hidden)]
+    pub fn as_index_provider(&self) -> &P { self.index.as_provider() }
+
+    #[doc(hidden)]
+    pub fn as_stash_provider_mut(&mut self) -> &mut S { self.stash.as_provider_mut() }
+    #[doc(hidden)]
+    pub fn as_state_provider_mut(&mut self) -> &mut H { self.state.as_provider_mut() }
+    #[doc(hidden)]
+    pub fn as_index_provider_mut(&mut self) -> &mut P { self.index.as_provider_mut() }
+
+    pub fn ifaces(&self) -> Result<impl Iterator<Item = IfaceInfo> + '_, StockError<S, H, P>> {
+        let names = self
+            .stash
+            .ifaces()?
+            .map(|iface| (iface.iface_id(), iface.name.clone()))
+            .collect::<HashMap<_, _>>();
+        Ok(self.stash.ifaces()?.map(move |iface| {
+            let suppl = self
+                .stash
+                .supplement(ContentRef::Iface(iface.iface_id()))
+                .ok()
+                .flatten();
+            IfaceInfo::new(iface, &names, suppl)
+        }))
+    }
+
+    /// Lists interfaces implemented by the schema of a given contract, which
+    /// can be used to access the contract state via [`Self::contract_iface`].
+    ///
+    /// Includes implementations which were shipped with the consignments
+    /// consumed by the stock.
+    pub fn contract_ifaces(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<impl Iterator<Item = IfaceInfo> + '_, StockError<S, H, P>> {
+        let schema_id = self.stash.genesis(contract_id)?.schema_id;
+        let iface_ids = self
+            .stash
+            .schema(schema_id)?
+            .iimpls
+            .values()
+            .map(|iimpl| iimpl.iface_id)
+            .collect::<BTreeSet<_>>();
+        Ok(self
+            .ifaces()?
+            .filter(move |info| iface_ids.contains(&info.id)))
+    }
+    pub fn iface(&self, iface: impl Into<IfaceRef>) -> Result<&Iface, StockError<S, H, P>> {
+        Ok(self.stash.iface(iface)?)
+    }
+    pub fn schemata(&self) -> Result<impl Iterator<Item = SchemaInfo> + '_, StockError<S, H, P>> {
+        Ok(self.stash.schemata()?.map(SchemaInfo::with))
+    }
+    pub fn schema(&self, schema_id: SchemaId) -> Result<&SchemaIfaces, StockError<S, H, P>> {
+        Ok(self.stash.schema(schema_id)?)
+    }
+
+    pub fn contracts(
+        &self,
+    ) -> Result<impl Iterator<Item = ContractInfo> + '_, StockError<S, H, P>> {
+        let mut contracts = vec![];
+        for genesis in self.stash.geneses()? {
+            let tags = self.contract_tags(genesis.contract_id())?;
+            contracts.push(ContractInfo::with_tags(genesis, tags));
+        }
+        Ok(contracts.into_iter())
+    }
+
+    /// Lists contracts which were issued for a different network type than
+    /// the one the wallet operates on (mainnet contracts in a testnet wallet
+    /// or vice versa). Such contracts can't be operated by the wallet.
+    ///
+    /// NB: Contract genesis distinguishes only mainnet and testnet networks,
+    /// thus contracts can be moved between different test networks (like
+    /// regtest and signet) without any migration.
+    pub fn network_mismatch(&self, testnet: bool) -> Result<Vec<ContractId>, StockError<S, H, P>> {
+        Ok(self
+            .stash
+            .geneses()?
+            .filter(|genesis| genesis.testnet != testnet)
+            .map(|genesis| genesis.contract_id())
+            .collect())
+    }
+
+    #[allow(clippy::multiple_bound_locations, clippy::type_complexity)]
+    pub fn contracts_by<'a, C: IfaceClass + 'a>(
+        &'a self,
+    ) -> Result<
+        impl Iterator<
+                Item = <C::Wrapper<H::ContractRead<'a>> as IfaceWrapper<H::ContractRead<'a>>>::Info,
+            > + 'a,
+        StockError<S, H, P>,
+    > {
+        Ok(self.stash.geneses_by::<C>()?.filter_map(|genesis| {
+            self.contract_iface_class::<C>(genesis.contract_id())
+                .as_ref()
+                .map(<C::Wrapper<H::ContractRead<'_>> as IfaceWrapper<H::ContractRead<'_>>>::info)
+                .ok()
+        }))
+    }
+
+    /// Iterates over ids of all contract assigning state to the provided set of
+    /// output seals.
     pub fn contracts_assigning(
         &self,
         outputs: impl IntoIterator<Item = impl Into<XOutpoint>>,
@@ -550,7 +1396,39 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         &self,
         contract_id: ContractId,
     ) -> Result<ContractInfo, StockError<S, H, P>> {
-        Ok(ContractInfo::with(self.stash.genesis(contract_id)?))
+        let tags = self.contract_tags(contract_id)?;
+        Ok(ContractInfo::with_tags(self.stash.genesis(contract_id)?, tags))
+    }
+
+    /// Returns application-defined tags of the contract. Tags are local to
+    /// the stock and are never exported with consignments or kits.
+    pub fn contract_tags(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<ContractTags, StockError<S, H, P>> {
+        Ok(self
+            .stash
+            .supplements(ContentRef::Genesis(contract_id))?
+            .filter_map(|suppl| suppl.tags().map(|tags| (suppl.timestamp, tags)))
+            .max_by_key(|(timestamp, _)| *timestamp)
+            .map(|(_, tags)| tags)
+            .unwrap_or_default())
+    }
+
+    /// Replaces application-defined tags of the contract with the provided
+    /// ones.
+    pub fn set_contract_tags(
+        &mut self,
+        contract_id: ContractId,
+        tags: ContractTags,
+    ) -> Result<(), StockError<S, H, P, SerializeError>> {
+        self.stash.genesis(contract_id)?;
+        let mut suppl = Supplement::new(ContentRef::Genesis(contract_id), Identity::default());
+        suppl
+            .annotate_itself(SUPPL_ANNOT_TAGS, &tags)
+            .map_err(StockError::InvalidInput)?;
+        self.stash.store_supplement(suppl)?;
+        Ok(())
     }
 
     pub fn contract_state(
@@ -562,6 +1440,92 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
             .map_err(StockError::from)
     }
 
+    /// Returns ids of other contracts referenced by the global state of the
+    /// given contract.
+    ///
+    /// A reference is a global state value which schema defines to have the
+    /// [`ContractId`] type, like a collateral or an index contract.
+    pub fn dependencies(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<BTreeSet<ContractId>, StockError<S, H, P>> {
+        let state = self.contract_state(contract_id)?;
+        let schema = &self.stash.schema(state.schema_id())?.schema;
+        let sem_id = contract_id_sem_id();
+        let mut deps = bset![];
+        for (ty, global_schema) in &schema.global_types {
+            if global_schema.sem_id != sem_id {
+                continue;
+            }
+            let Ok(iter) = state.global(*ty) else {
+                continue;
+            };
+            deps.extend(
+                iter.filter_map(|data| ContractId::copy_from_slice(data.borrow().as_slice()).ok()),
+            );
+        }
+        deps.remove(&contract_id);
+        Ok(deps)
+    }
+
+    /// Returns ids of contracts referenced by the given contract (see
+    /// [`Self::dependencies`]) which are not known to the stock.
+    pub fn missing_dependencies(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<BTreeSet<ContractId>, StockError<S, H, P>> {
+        let mut deps = self.dependencies(contract_id)?;
+        for genesis in self.stash.geneses()? {
+            deps.remove(&genesis.contract_id());
+        }
+        Ok(deps)
+    }
+
+    /// Returns ids of contracts referenced by the global state of the contract
+    /// once the consignment is consumed (see [`Self::dependencies`]), which
+    /// are not known to the stock.
+    fn missing_consignment_dependencies<const TRANSFER: bool>(
+        &self,
+        consignment: &Consignment<TRANSFER>,
+        known: bool,
+    ) -> Result<BTreeSet<ContractId>, StockError<S, H, P>> {
+        let contract_id = consignment.contract_id();
+        let mut deps = if known { self.dependencies(contract_id)? } else { bset![] };
+
+        let sem_id = contract_id_sem_id();
+        let globals = iter::once(&consignment.genesis.globals)
+            .chain(consignment.extensions.iter().map(|ext| &ext.globals))
+            .chain(
+                consignment
+                    .bundles
+                    .iter()
+                    .flat_map(|wb| wb.known_transitions())
+                    .map(|transition| &transition.globals),
+            );
+        for (ty, values) in globals.flatten() {
+            if consignment
+                .schema
+                .global_types
+                .get(ty)
+                .map(|global_schema| global_schema.sem_id)
+                != Some(sem_id)
+            {
+                continue;
+            }
+            deps.extend(
+                values
+                    .iter()
+                    .filter_map(|data| ContractId::copy_from_slice(data.as_slice()).ok()),
+            );
+        }
+        deps.remove(&contract_id);
+
+        for genesis in self.stash.geneses()? {
+            deps.remove(&genesis.contract_id());
+        }
+        Ok(deps)
+    }
+
     #[allow(clippy::multiple_bound_locations, clippy::type_complexity)]
     pub fn contract_iface_class<C: IfaceClass>(
         &self,
@@ -607,6 +1571,64 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         })
     }
 
+    /// Returns id of the interface which is implemented by the contract, if
+    /// the contract implements exactly one known interface.
+    pub fn default_iface(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<Option<IfaceId>, StockError<S, H, P>> {
+        let genesis = self.stash.genesis(contract_id)?;
+        let schema_ifaces = self.stash.schema(genesis.schema_id)?;
+        Ok(schema_ifaces.default_iimpl().map(|iimpl| iimpl.iface_id))
+    }
+
+    /// Returns contract interface for the contract using its default
+    /// interface (see [`Self::default_iface`]).
+    pub fn contract_iface_default(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<ContractIface<H::ContractRead<'_>>, StockError<S, H, P, ContractIfaceError>> {
+        let iface_id = self
+            .default_iface(contract_id)?
+            .ok_or(ContractIfaceError::NoDefaultIface(contract_id))?;
+        self.contract_iface(contract_id, iface_id)
+    }
+
+    /// Collects fungible allocations of the contract default assignment type
+    /// held by the provided wallet outputs, allowing to detect whether they
+    /// need to be consolidated (see [`ConsolidationPolicy`]).
+    pub fn fragmentation(
+        &self,
+        contract_id: ContractId,
+        outpoints: impl IntoIterator<Item = impl Into<XOutpoint>>,
+    ) -> Result<Fragmentation, StockError<S, H, P, ComposeError>> {
+        let iface = self
+            .default_iface(contract_id)?
+            .ok_or(ComposeError::NoIface)?;
+        let builder = self.transition_builder(contract_id, iface, None::<FieldName>)?;
+        let assignment = builder.default_assignment()?.clone();
+        let assignment_type = builder
+            .assignments_type(&assignment)
+            .ok_or(BuilderError::InvalidStateField(assignment.clone()))?;
+
+        let mut allocations = BTreeMap::<XOutputSeal, Vec<Amount>>::new();
+        for (output, list) in self.contract_assignments_for(contract_id, outpoints)? {
+            for (opout, state) in list {
+                if let PersistedState::Amount(value, _, _) = state {
+                    if opout.ty == assignment_type {
+                        allocations.entry(output).or_default().push(value);
+                    }
+                }
+            }
+        }
+
+        Ok(Fragmentation {
+            contract_id,
+            assignment,
+            allocations,
+        })
+    }
+
     pub fn contract_assignments_for(
         &self,
         contract_id: ContractId,
@@ -665,46 +1687,364 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         Ok(res)
     }
 
-    pub fn contract_builder(
+    /// Returns all known allocations assigned to the provided outputs, grouped
+    /// by contract.
+    ///
+    /// Useful to analyze which inputs of a transaction carry RGB state before
+    /// signing it, such that no allocation gets unintentionally burned.
+    pub fn allocations_on(
         &self,
-        issuer: impl Into<Identity>,
-        schema_id: SchemaId,
-        iface: impl Into<IfaceRef>,
-    ) -> Result<ContractBuilder, StockError<S, H, P>> {
-        Ok(self
-            .stash
-            .contract_builder(issuer.into(), schema_id, iface)?)
+        outputs: impl IntoIterator<Item = impl Into<XOutpoint>>,
+    ) -> Result<HashMap<ContractId, ContractAssignments>, StockError<S, H, P>> {
+        let outputs = outputs
+            .into_iter()
+            .map(|o| o.into())
+            .collect::<BTreeSet<XOutpoint>>();
+        let mut res = HashMap::new();
+        for contract_id in self.contracts_assigning(outputs.iter().copied())? {
+            let assignments =
+                self.contract_assignments_for(contract_id, outputs.iter().copied())?;
+            if !assignments.is_empty() {
+                res.insert(contract_id, assignments);
+            }
+        }
+        Ok(res)
     }
 
-    pub fn transition_builder(
+    /// Returns contract allocations bound to the provided seals.
+    ///
+    /// Uses the index to find operation outputs assigned to the seals, such
+    /// that only matching assignments are materialized, and the contract state
+    /// is not read at all if no state was ever assigned to the seals.
+    pub fn state_filtered(
         &self,
         contract_id: ContractId,
-        iface: impl Into<IfaceRef>,
-        transition_name: Option<impl Into<FieldName>>,
-    ) -> Result<TransitionBuilder, StockError<S, H, P>> {
-        Ok(self
-            .stash
-            .transition_builder(contract_id, iface, transition_name)?)
-    }
+        seals: &[XOutputSeal],
+    ) -> Result<Vec<OwnedAllocation>, StockError<S, H, P>> {
+        let opouts = self
+            .index
+            .opouts_by_outputs(contract_id, seals.iter().copied())?;
+        if opouts.is_empty() {
+            return Ok(vec![]);
+        }
 
-    pub fn blank_builder(
-        &self,
-        contract_id: ContractId,
-        iface: impl Into<IfaceRef>,
-    ) -> Result<TransitionBuilder, StockError<S, H, P>> {
-        Ok(self.stash.blank_builder(contract_id, iface)?)
+        let state = self.contract_state(contract_id)?;
+        let filter = |seal: &XOutputSeal, opout: &Opout| -> bool {
+            opouts.contains(opout) && seals.contains(seal)
+        };
+        let allocations = state
+            .allocations_all()
+            .filter(|a| filter(&a.seal, &a.opout))
+            .collect();
+        Ok(allocations)
     }
 
-    pub fn export_schema(&self, schema_id: SchemaId) -> Result<ValidKit, StockError<S, H, P>> {
+    /// Returns allocations bound to the provided seals across all contracts
+    /// known to the stock, grouped by contract. Contracts without any state
+    /// on the seals are omitted.
+    pub fn wallet_state(
+        &self,
+        seals: impl IntoIterator<Item = XOutputSeal>,
+    ) -> Result<HashMap<ContractId, Vec<OwnedAllocation>>, StockError<S, H, P>> {
+        let seals = seals.into_iter().collect::<Vec<_>>();
+        let mut res = HashMap::new();
+        for contract_id in self.contracts_assigning(seals.iter().copied())? {
+            let allocations = self.state_filtered(contract_id, &seals)?;
+            if !allocations.is_empty() {
+                res.insert(contract_id, allocations);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns contract allocations bound to a single seal.
+    #[inline]
+    pub fn owned_by_seal(
+        &self,
+        contract_id: ContractId,
+        seal: XOutputSeal,
+    ) -> Result<Vec<OwnedAllocation>, StockError<S, H, P>> {
+        self.state_filtered(contract_id, &[seal])
+    }
+
+    /// Selects which of the wallet `seals` should be spent to pay `amount` of
+    /// the fungible state of `assignment_type`, using the provided coin
+    /// selection strategy. The selected seals are to be used as the previous
+    /// outputs for [`Self::compose`].
+    ///
+    /// Returns `None` if the seals do not hold enough state.
+    pub fn select_outputs(
+        &self,
+        contract_id: ContractId,
+        assignment_type: AssignmentType,
+        seals: &[XOutputSeal],
+        amount: Amount,
+        selector: impl CoinSelect,
+    ) -> Result<Option<Vec<XOutputSeal>>, StockError<S, H, P>> {
+        let mut amounts = BTreeMap::<XOutputSeal, Amount>::new();
+        for allocation in self.state_filtered(contract_id, seals)? {
+            if allocation.opout.ty != assignment_type {
+                continue;
+            }
+            if let AllocatedState::Amount(value) = allocation.state {
+                amounts
+                    .entry(allocation.seal)
+                    .or_default()
+                    .saturating_add_assign(value);
+            }
+        }
+        let candidates = amounts.into_iter().collect::<Vec<_>>();
+        Ok(selector.select(&candidates, amount))
+    }
+
+    /// Returns definitions of the seals holding the current contract state
+    /// (i.e. allocations with valid witnesses which were not spent by other
+    /// operations with valid witnesses), together with the witnesses which
+    /// assigned state to them, but without
+    /// any state data. The definitions may be passed to an external service
+    /// (like a watchtower) monitoring the blockchain for spends of the seals
+    /// without disclosing the contract state to it.
+    pub fn seal_definitions(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<BTreeSet<SealDefinition>, StockError<S, H, P>> {
+        let spent =
+            self.spent_opouts(contract_id, |witness_ids| self.has_valid_witness(witness_ids))?;
+        let state = self.contract_state(contract_id)?;
+        let definitions = state
+            .allocations_all()
+            .filter(|a| !spent.contains(&a.opout))
+            .map(|a| SealDefinition {
+                seal: a.seal,
+                witness: a.witness,
+            })
+            .collect();
+        Ok(definitions)
+    }
+
+    /// Returns operation outputs of the contract spent by state transitions
+    /// from the bundles for which `is_spending` returns `true` when given the
+    /// bundle witnesses.
+    fn spent_opouts(
+        &self,
+        contract_id: ContractId,
+        mut is_spending: impl FnMut(Vec<XWitnessId>) -> Result<bool, StockError<S, H, P>>,
+    ) -> Result<HashSet<Opout>, StockError<S, H, P>> {
+        let mut spent = HashSet::<Opout>::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witness_ids, id) = self.index.bundle_info(bundle_id)?;
+            if id != contract_id || !is_spending(witness_ids.collect())? {
+                continue;
+            }
+            let bundle = self.stash.bundle(bundle_id)?;
+            spent.extend(
+                bundle
+                    .known_transitions
+                    .values()
+                    .flat_map(|transition| &transition.inputs)
+                    .map(|input| input.prev_out),
+            );
+        }
+        Ok(spent)
+    }
+
+    /// Checks whether any of the witnesses is valid, i.e. not archived.
+    fn has_valid_witness(&self, witness_ids: Vec<XWitnessId>) -> Result<bool, StockError<S, H, P>> {
+        match self.state.select_valid_witness(witness_ids) {
+            Ok(_) => Ok(true),
+            Err(StateError::AbsentValidWitness) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns unspent contract allocations assigned to outputs of their own
+    /// witness transactions (i.e. created for [`Beneficiary::WitnessVout`]
+    /// invoices), which are controlled by the wallet according to `is_mine`.
+    ///
+    /// This allows a receiver without any UTXOs to discover the state it was
+    /// paid with: once the witness transaction is known to the `resolver`, the
+    /// output script is checked against the wallet descriptor. Allocations
+    /// with unresolvable witnesses are skipped, and are picked up by a later
+    /// call once the transaction is seen.
+    pub fn witness_output_allocations(
+        &self,
+        contract_id: ContractId,
+        is_mine: impl Fn(&ScriptPubkey) -> bool,
+        resolver: impl ResolveWitness,
+    ) -> Result<Vec<OwnedAllocation>, StockError<S, H, P>> {
+        let spent =
+            self.spent_opouts(contract_id, |witness_ids| self.has_valid_witness(witness_ids))?;
+        let state = self.contract_state(contract_id)?;
+        let allocations = state
+            .allocations_all()
+            .filter(|a| !spent.contains(&a.opout));
+
+        let mut witnesses = HashMap::<XWitnessId, Option<XWitnessTx>>::new();
+        let mut res = vec![];
+        for allocation in allocations {
+            let Some(witness_id) = allocation.witness else {
+                continue;
+            };
+            let seal = allocation.seal;
+            if seal.layer1() != witness_id.layer1()
+                || seal.as_reduced_unsafe().txid != *witness_id.as_reduced_unsafe()
+            {
+                continue;
+            }
+            let tx = witnesses
+                .entry(witness_id)
+                .or_insert_with(|| resolver.resolve_pub_witness(witness_id).ok());
+            let Some(tx) = tx else {
+                continue;
+            };
+            let vout = seal.as_reduced_unsafe().vout.to_usize();
+            if tx
+                .as_reduced_unsafe()
+                .outputs
+                .get(vout)
+                .is_some_and(|out| is_mine(&out.script_pubkey))
+            {
+                res.push(allocation);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns contract allocations as they were at the given block height:
+    /// allocations created by operations mined at or below the height, which
+    /// were not spent by the operations mined at or below the same height.
+    ///
+    /// Operations with unmined witness transactions are not taken into
+    /// account.
+    pub fn contract_allocations_at(
+        &self,
+        contract_id: ContractId,
+        height: u32,
+    ) -> Result<Vec<OwnedAllocation>, StockError<S, H, P>> {
+        let state = self.contract_state(contract_id)?;
+        let mined_at = |witness_id: Option<XWitnessId>| match witness_id {
+            None => true,
+            Some(id) => matches!(
+                state.witness_ord(id),
+                Some(WitnessOrd::Mined(pos)) if pos.height().get() <= height
+            ),
+        };
+
+        let spent = self.spent_opouts(contract_id, |witness_ids| {
+            Ok(witness_ids.into_iter().any(|w| mined_at(Some(w))))
+        })?;
+
+        let allocations = state
+            .allocations_all()
+            .filter(|a| mined_at(a.witness) && !spent.contains(&a.opout))
+            .collect();
+        Ok(allocations)
+    }
+
+    /// Creates a contract builder for the `issuer`. If the issuer is
+    /// anonymous and the stock has a default issuer (see
+    /// [`Self::set_default_issuer`]), the default issuer and its signer are
+    /// used instead. The builder is configured with the stock network and
+    /// timestamp policy.
+    pub fn contract_builder(
+        &self,
+        issuer: impl Into<Identity>,
+        schema_id: SchemaId,
+        iface: impl Into<IfaceRef>,
+    ) -> Result<ContractBuilder, StockError<S, H, P>> {
+        let mut issuer = issuer.into();
+        let mut signer = None;
+        if let Some(default) = self.default_issuer().filter(|_| issuer.is_anonymous()) {
+            issuer = default.clone();
+            signer = self.default_signer.clone();
+        }
+        let mut builder = self
+            .stash
+            .contract_builder(issuer, schema_id, iface)?
+            .set_timestamp_policy(self.timestamp_policy());
+        if let Some(chain_net) = self.chain_net() {
+            builder = builder
+                .set_chain_net(chain_net)
+                .expect("new builder has no alternative layers");
+        }
+        if let Some(signer) = signer {
+            builder = builder.sign_with(signer);
+        }
+        Ok(builder)
+    }
+
+    /// Creates a contract builder for the default issuer set with
+    /// [`Self::set_default_issuer`], such that repeated issuances by the same
+    /// organization use the same identity and signer.
+    ///
+    /// Returns `None` if no default issuer is set.
+    pub fn default_contract_builder(
+        &self,
+        schema_id: SchemaId,
+        iface: impl Into<IfaceRef>,
+    ) -> Result<Option<ContractBuilder>, StockError<S, H, P>> {
+        if self.default_issuer().is_none() {
+            return Ok(None);
+        }
+        self.contract_builder(Identity::default(), schema_id, iface)
+            .map(Some)
+    }
+
+    pub fn transition_builder(
+        &self,
+        contract_id: ContractId,
+        iface: impl Into<IfaceRef>,
+        transition_name: Option<impl Into<FieldName>>,
+    ) -> Result<TransitionBuilder, StockError<S, H, P>> {
+        Ok(self
+            .stash
+            .transition_builder(contract_id, iface, transition_name)?)
+    }
+
+    pub fn extension_builder(
+        &self,
+        contract_id: ContractId,
+        iface: impl Into<IfaceRef>,
+        extension_name: impl Into<FieldName>,
+    ) -> Result<ExtensionBuilder, StockError<S, H, P>> {
+        Ok(self
+            .stash
+            .extension_builder(contract_id, iface, extension_name)?)
+    }
+
+    pub fn blank_builder(
+        &self,
+        contract_id: ContractId,
+        iface: impl Into<IfaceRef>,
+    ) -> Result<BlankTransitionBuilder, StockError<S, H, P>> {
+        Ok(self.stash.blank_builder(contract_id, iface)?)
+    }
+
+    /// Exports schema together with all known interfaces, their
+    /// implementations, type libraries, scripts, supplements and signatures
+    /// as a [`Kit`], which can be distributed to other wallets and imported
+    /// with [`Self::import_kit`].
+    pub fn export_schema(&self, schema_id: SchemaId) -> Result<ValidKit, StockError<S, H, P>> {
         let mut kit = Kit::default();
         let schema_ifaces = self.schema(schema_id)?;
         kit.schemata
             .push(schema_ifaces.schema.clone())
             .expect("single item");
+        self.export_content(&mut kit, ContentId::Schema(schema_id), ContentRef::Schema(schema_id))?;
         for name in schema_ifaces.iimpls.keys() {
             let iface = self.stash.iface(name.clone())?;
+            let iface_id = iface.iface_id();
+            self.export_content(&mut kit, ContentId::Iface(iface_id), ContentRef::Iface(iface_id))?;
             kit.ifaces.push(iface.clone()).expect("type guarantees");
         }
+        for iimpl in schema_ifaces.iimpls.values() {
+            let impl_id = iimpl.impl_id();
+            self.export_content(
+                &mut kit,
+                ContentId::IfaceImpl(impl_id),
+                ContentRef::IfaceImpl(impl_id),
+            )?;
+        }
         kit.iimpls
             .extend(schema_ifaces.iimpls.values().cloned())
             .expect("type guarantees");
@@ -716,6 +2056,23 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         Ok(kit.validate().expect("stock produced invalid kit"))
     }
 
+    /// Adds known signatures and supplement for a piece of content to the kit.
+    fn export_content(
+        &self,
+        kit: &mut Kit,
+        content_id: ContentId,
+        content_ref: ContentRef,
+    ) -> Result<(), StockError<S, H, P>> {
+        if let Some(sigs) = self.stash.sigs_for(&content_id)? {
+            // Sigs are optional, so we skip them if the kit is full
+            kit.signatures.insert(content_id, sigs.clone()).ok();
+        }
+        if let Some(suppl) = self.stash.supplement(content_ref)? {
+            kit.supplements.push(suppl.clone()).ok();
+        }
+        Ok(())
+    }
+
     pub fn export_contract(
         &self,
         contract_id: ContractId,
@@ -724,16 +2081,225 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         Ok(consignment)
     }
 
+    /// Exports the contract with all its history known to the stock and
+    /// removes it from the stock with [`Self::purge_contract`]. The returned
+    /// contract is a portable archive which can be saved with
+    /// [`FileContent::save`] and imported back later.
+    ///
+    /// Unlike [`Self::export_contract`], the archive contains all the bundles
+    /// of the contract with a valid witness, and not just the history of the
+    /// state owned by the wallet.
+    pub fn archive_contract(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<Contract, StockError<S, H, P, ConsignError>> {
+        let contract = self.contract_history(contract_id)?;
+        self.purge_contract(contract_id)?;
+        Ok(contract)
+    }
+
+    /// Constructs contract consignment with all bundles of the contract which
+    /// have a valid witness.
+    fn contract_history(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<Contract, StockError<S, H, P, ConsignError>> {
+        let mut anchored_bundles = BTreeMap::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witness_ids, id) = self.index.bundle_info(bundle_id)?;
+            if id != contract_id {
+                continue;
+            }
+            match self.state.select_valid_witness(witness_ids) {
+                Ok(_) => {}
+                Err(StateError::AbsentValidWitness) => continue,
+                Err(err) => return Err(err.into()),
+            }
+            anchored_bundles.insert(bundle_id, self.client_bundle(bundle_id)?);
+        }
+        self.assemble_consignment(contract_id, anchored_bundles, bmap! {})
+    }
+
+    /// Removes the contract, its state, history and index from the stock,
+    /// allowing users to forget unwanted (e.g. spam) assets. Schemata,
+    /// interfaces and witness transactions, which may be used by other
+    /// contracts, are kept.
+    ///
+    /// Returns `false` if the contract was not known. Fails, leaving the
+    /// stock unchanged, if any of the persistence providers doesn't support
+    /// contract removal or doesn't know the contract known to the others.
+    pub fn purge_contract(&mut self, contract_id: ContractId) -> Result<bool, StockError<S, H, P>> {
+        let mut bundles = vec![];
+        for bundle_id in self.stash.bundle_ids()? {
+            if self.index.bundle_info(bundle_id)?.1 == contract_id {
+                bundles.push(bundle_id);
+            }
+        }
+        let mut known = false;
+        self.store_transaction(|stash, state, index| {
+            let in_stash = stash.remove_contract(contract_id, bundles)?;
+            let in_state = state.remove_contract(contract_id)?;
+            let in_index = index.remove_contract(contract_id)?;
+            match (in_stash, in_state, in_index) {
+                (true, true, true) | (false, false, false) => {}
+                (false, _, _) => return Err(StashInconsistency::ContractAbsent(contract_id).into()),
+                (true, false, _) => {
+                    return Err(StateInconsistency::UnknownContract(contract_id).into())
+                }
+                (true, true, false) => {
+                    return Err(IndexInconsistency::ContractAbsent(contract_id).into())
+                }
+            }
+            known = in_stash;
+            Ok(())
+        })?;
+        if known {
+            self.notify(StockEvent::ContractRemoved(contract_id));
+        }
+        Ok(known)
+    }
+
+    /// Writes contract articles: genesis together with its schema, interfaces,
+    /// supplements and signatures, but without any state history. Articles
+    /// are what a peer needs to know about the contract before it can accept
+    /// transfers, allowing issuers to publish a compact contract definition.
+    pub fn export_articles(
+        &self,
+        contract_id: ContractId,
+        writer: impl io::Write,
+    ) -> Result<(), StockError<S, H, P, ArticlesError>> {
+        let mut articles = self.consign::<false>(contract_id, [], None)?;
+        articles.bundles = none!();
+        articles.extensions = none!();
+        articles.terminals = none!();
+        articles.save(writer).map_err(ArticlesError::from)?;
+        Ok(())
+    }
+
     pub fn transfer(
         &self,
         contract_id: ContractId,
         outputs: impl AsRef<[XOutputSeal]>,
         secret_seal: Option<XChain<SecretSeal>>,
     ) -> Result<Transfer, StockError<S, H, P, ConsignError>> {
+        self.check_quarantine(contract_id)?;
         let consignment = self.consign(contract_id, outputs, secret_seal)?;
         Ok(consignment)
     }
 
+    /// Estimates the size of a transfer consignment for the provided outputs
+    /// and terminals before constructing it, such that a wallet may warn the
+    /// user about huge transfers.
+    ///
+    /// The estimate follows the same history traversal as [`Self::transfer`].
+    pub fn estimate_consignment_size(
+        &self,
+        contract_id: ContractId,
+        outputs: impl AsRef<[XOutputSeal]>,
+        secret_seal: Option<XChain<SecretSeal>>,
+    ) -> Result<ConsignmentEstimate, StockError<S, H, P, ConsignError>> {
+        let mut opouts = self.index.public_opouts(contract_id)?;
+        opouts.extend(
+            self.index
+                .opouts_by_outputs(contract_id, outputs.as_ref().iter().copied())?,
+        );
+        opouts.extend(self.index.opouts_by_terminals(secret_seal.into_iter())?);
+
+        let mut ops = BTreeSet::<OpId>::new();
+        let mut ids = opouts.into_iter().map(|opout| opout.op).collect::<Vec<_>>();
+        while let Some(id) = ids.pop() {
+            if id == contract_id || !ops.insert(id) {
+                continue;
+            }
+            let transition = self.transition(id)?;
+            ids.extend(transition.inputs().iter().map(|input| input.prev_out.op));
+        }
+
+        let mut bundle_ids = BTreeSet::<BundleId>::new();
+        for id in &ops {
+            bundle_ids.insert(self.index.bundle_id_for_op(*id)?);
+        }
+        let genesis = self.stash.genesis(contract_id)?;
+        let schema_ifaces = self.stash.schema(genesis.schema_id)?;
+        let mut bytes = encoded_len(genesis) + encoded_len(&schema_ifaces.schema);
+        let mut ifaces = Vec::with_capacity(schema_ifaces.iimpls.len());
+        for (iface_id, iimpl) in &schema_ifaces.iimpls {
+            let iface = self.stash.iface(*iface_id)?;
+            bytes += encoded_len(iface) + encoded_len(iimpl);
+            ifaces.push(iface);
+        }
+        let (types, scripts) = self.stash.extract(&schema_ifaces.schema, ifaces)?;
+        bytes += encoded_len(&types) + scripts.values().map(encoded_len).sum::<usize>();
+
+        let mut witness_ids = BTreeSet::<XWitnessId>::new();
+        for bundle_id in bundle_ids {
+            bytes += encoded_len(self.stash.bundle(bundle_id)?);
+            let witness_id = self
+                .state
+                .select_valid_witness(self.index.bundle_info(bundle_id)?.0)?;
+            if witness_ids.insert(witness_id) {
+                bytes += encoded_len(self.stash.witness(witness_id)?);
+            }
+        }
+
+        Ok(ConsignmentEstimate {
+            operations: ops.len() + 1,
+            witnesses: witness_ids.len(),
+            bytes,
+        })
+    }
+
+    /// Reports the storage space taken by the data specific to the contract,
+    /// in bytes: its genesis, state transition bundles and their witnesses.
+    /// Schemata, interfaces and types, which may be shared with other
+    /// contracts, are not counted.
+    pub fn contract_usage(&self, contract_id: ContractId) -> Result<u64, StockError<S, H, P>> {
+        let mut bytes = encoded_len(self.stash.genesis(contract_id)?);
+        let mut witness_ids = BTreeSet::<XWitnessId>::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witnesses, id) = self.index.bundle_info(bundle_id)?;
+            if id != contract_id {
+                continue;
+            }
+            witness_ids.extend(witnesses);
+            bytes += encoded_len(self.stash.bundle(bundle_id)?);
+        }
+        for witness_id in witness_ids {
+            bytes += encoded_len(self.stash.witness(witness_id)?);
+        }
+        Ok(bytes as u64)
+    }
+
+    /// Extracts proof of a document anchored in the witness transaction
+    /// alongside RGB operations (see [`DocCommitment`]).
+    ///
+    /// Returns `None` if the witness doesn't commit to the document, or the
+    /// document commitment is not known to the stored anchors.
+    pub fn doc_proof(
+        &self,
+        witness_id: XWitnessId,
+        doc: DocCommitment,
+    ) -> Result<Option<DocProof>, StockError<S, H, P>> {
+        let witness = self.stash.witness(witness_id)?;
+        Ok(witness.anchors.doc_proof(witness_id, doc))
+    }
+
+    /// Constructs transfers for multiple contracts having state allocated to
+    /// the same outputs, which can be shipped to the beneficiary as a single
+    /// [`MultiTransfer`] file.
+    pub fn transfer_multi(
+        &self,
+        contract_ids: impl IntoIterator<Item = ContractId>,
+        outputs: impl AsRef<[XOutputSeal]>,
+        secret_seal: Option<XChain<SecretSeal>>,
+    ) -> Result<MultiTransfer, StockError<S, H, P, ConsignError>> {
+        let outputs = outputs.as_ref();
+        contract_ids
+            .into_iter()
+            .map(|contract_id| self.transfer(contract_id, outputs, secret_seal))
+            .collect()
+    }
+
     fn consign<const TRANSFER: bool>(
         &self,
         contract_id: ContractId,
@@ -742,20 +2308,6 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
     ) -> Result<Consignment<TRANSFER>, StockError<S, H, P, ConsignError>> {
         let outputs = outputs.as_ref();
 
-        // Initialize supplements with btree set
-        let mut supplements = bset![];
-        // Initialize signatures with btree map
-        let mut signatures = bmap! {};
-        // Get genesis signature by contract id
-        self.stash
-            .sigs_for(&ContentId::Genesis(contract_id))?
-            .map(|genesis_sig| {
-                signatures.insert(ContentId::Genesis(contract_id), genesis_sig.clone())
-            });
-        // Get genesis supplement by contract id
-        self.stash
-            .supplement(ContentRef::Genesis(contract_id))?
-            .map(|genesis_suppl| supplements.insert(genesis_suppl.clone()));
         // 1. Collect initial set of anchored bundles
         // 1.1. Get all public outputs
         let mut opouts = self.index.public_opouts(contract_id)?;
@@ -768,16 +2320,21 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         opouts.extend(self.index.opouts_by_terminals(secret_seal.into_iter())?);
 
         // 1.3. Collect all state transitions assigning state to the provided outpoints
+        //
+        // We do not keep copies of the transitions, tracking just their ids, and visit each
+        // operation only once, such that the memory used by the export is bounded by the size
+        // of the bundles which get into the consignment.
         let mut anchored_bundles = BTreeMap::<BundleId, ClientBundle>::new();
-        let mut transitions = BTreeMap::<OpId, Transition>::new();
         let mut terminals = BTreeMap::<BundleId, XChain<SecretSeal>>::new();
+        let mut visited = BTreeSet::<OpId>::new();
+        let mut ids = vec![];
         for opout in opouts {
-            if opout.op == contract_id {
+            if opout.op == contract_id || !visited.insert(opout.op) {
                 continue; // we skip genesis since it will be present anywhere
             }
 
             let transition = self.transition(opout.op)?;
-            transitions.insert(opout.op, transition.clone());
+            ids.extend(transition.inputs().iter().map(|input| input.prev_out.op));
 
             let bundle_id = self.index.bundle_id_for_op(transition.id())?;
             // 2. Collect secret seals from terminal transitions to add to the consignment terminals
@@ -797,24 +2354,41 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         }
 
         // 2. Collect all state transitions between terminals and genesis
-        let mut ids = vec![];
-        for transition in transitions.values() {
-            ids.extend(transition.inputs().iter().map(|input| input.prev_out.op));
-        }
-        while let Some(id) = ids.pop() {
-            if id == contract_id {
-                continue; // we skip genesis since it will be present anywhere
+        self.reveal_ancestors(contract_id, ids, &mut visited, &mut anchored_bundles)?;
+
+        if let Some(secret) = secret_seal {
+            if !terminals.values().any(|seal| *seal == secret) {
+                return Err(ConsignError::NoTerminal(secret).into());
             }
-            let transition = self.transition(id)?;
-            ids.extend(transition.inputs().iter().map(|input| input.prev_out.op));
-            transitions.insert(id, transition.clone());
-            let bundle_id = self.index.bundle_id_for_op(transition.id())?;
-            anchored_bundles
-                .entry(bundle_id)
-                .or_insert(self.client_bundle(bundle_id)?.clone())
-                .reveal_transition(transition.clone())?;
         }
 
+        self.assemble_consignment(contract_id, anchored_bundles, terminals)
+    }
+
+    /// Constructs consignment of the contract from the anchored bundles, adding
+    /// genesis, schema and interfaces together with their supplements and
+    /// signatures.
+    fn assemble_consignment<const TRANSFER: bool>(
+        &self,
+        contract_id: ContractId,
+        anchored_bundles: BTreeMap<BundleId, ClientBundle>,
+        terminals: BTreeMap<BundleId, XChain<SecretSeal>>,
+    ) -> Result<Consignment<TRANSFER>, StockError<S, H, P, ConsignError>> {
+        // Initialize supplements with btree set
+        let mut supplements = bset![];
+        // Initialize signatures with btree map
+        let mut signatures = bmap! {};
+        // Get genesis signature by contract id
+        self.stash
+            .sigs_for(&ContentId::Genesis(contract_id))?
+            .map(|genesis_sig| {
+                signatures.insert(ContentId::Genesis(contract_id), genesis_sig.clone())
+            });
+        // Get genesis supplement by contract id
+        self.stash
+            .supplement(ContentRef::Genesis(contract_id))?
+            .map(|genesis_suppl| supplements.insert(genesis_suppl.clone()));
+
         let genesis = self.stash.genesis(contract_id)?.clone();
         // Get schema signature by schema id
         self.stash
@@ -856,18 +2430,7 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         }
         let ifaces = Confined::from_checked(ifaces);
 
-        let mut bundles = BTreeMap::<XWitnessId, WitnessBundle>::new();
-        for anchored_bundle in anchored_bundles.into_values() {
-            let witness_ids = self.index.bundle_info(anchored_bundle.bundle_id())?.0;
-            let witness_id = self.state.select_valid_witness(witness_ids)?;
-            let pub_witness = self.stash.witness(witness_id)?.public.clone();
-            let wb = match bundles.remove(&witness_id) {
-                Some(bundle) => bundle.into_double(anchored_bundle)?,
-                None => WitnessBundle::with(pub_witness, anchored_bundle),
-            };
-            let res = bundles.insert(witness_id, wb);
-            debug_assert!(res.is_none());
-        }
+        let bundles = self.witness_bundles(anchored_bundles)?;
         let bundles = Confined::try_from_iter(bundles.into_values())
             .map_err(|_| ConsignError::TooManyBundles)?;
         let terminals =
@@ -882,7 +2445,7 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         // TODO: Conceal everything we do not need
         // TODO: Add known sigs to the consignment
 
-        Ok(Consignment {
+        let mut consignment = Consignment {
             version: ContainerVer::V2,
             transfer: TRANSFER,
 
@@ -898,10 +2461,304 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
             supplements,
             types,
             scripts,
-        })
+        };
+        consignment.sort_bundles().map_err(ConsignError::from)?;
+        consignment.verify_terminals().map_err(ConsignError::from)?;
+
+        Ok(consignment)
     }
 
-    /// Composes a batch of state transitions updating state for the provided
+    /// Walks the history back from the provided operations to genesis, adding
+    /// all visited state transitions to their anchored bundles.
+    fn reveal_ancestors(
+        &self,
+        contract_id: ContractId,
+        mut ids: Vec<OpId>,
+        visited: &mut BTreeSet<OpId>,
+        anchored_bundles: &mut BTreeMap<BundleId, ClientBundle>,
+    ) -> Result<(), StockError<S, H, P, ConsignError>> {
+        while let Some(id) = ids.pop() {
+            if id == contract_id || !visited.insert(id) {
+                continue; // we skip genesis since it will be present anywhere
+            }
+            let transition = self.transition(id)?;
+            ids.extend(transition.inputs().iter().map(|input| input.prev_out.op));
+            let bundle_id = self.index.bundle_id_for_op(transition.id())?;
+            let anchored_bundle = match anchored_bundles.entry(bundle_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.client_bundle(bundle_id)?),
+            };
+            anchored_bundle.reveal_transition(transition.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Groups anchored bundles by their valid witness transactions.
+    fn witness_bundles(
+        &self,
+        anchored_bundles: BTreeMap<BundleId, ClientBundle>,
+    ) -> Result<BTreeMap<XWitnessId, WitnessBundle>, StockError<S, H, P, ConsignError>> {
+        let mut bundles = BTreeMap::<XWitnessId, WitnessBundle>::new();
+        for anchored_bundle in anchored_bundles.into_values() {
+            let witness_ids = self.index.bundle_info(anchored_bundle.bundle_id())?.0;
+            let witness_id = self.state.select_valid_witness(witness_ids)?;
+            let pub_witness = self.stash.witness(witness_id)?.public.clone();
+            let wb = match bundles.remove(&witness_id) {
+                Some(bundle) => bundle.into_double(anchored_bundle)?,
+                None => WitnessBundle::with(pub_witness, anchored_bundle),
+            };
+            let res = bundles.insert(witness_id, wb);
+            debug_assert!(res.is_none());
+        }
+        Ok(bundles)
+    }
+
+    /// Constructs a differential transfer, which omits witness bundles
+    /// containing only operations already known to the beneficiary (for
+    /// instance, from previous transfers between the same parties).
+    ///
+    /// Bundles holding the transfer terminals are always included. The
+    /// beneficiary must restore the full history with
+    /// [`Self::expand_transfer`] before validating the transfer.
+    pub fn transfer_since(
+        &self,
+        contract_id: ContractId,
+        outputs: impl AsRef<[XOutputSeal]>,
+        secret_seal: Option<XChain<SecretSeal>>,
+        known_ops: impl IntoIterator<Item = OpId>,
+    ) -> Result<Transfer, StockError<S, H, P, ConsignError>> {
+        let known_ops = known_ops.into_iter().collect::<BTreeSet<_>>();
+        let mut transfer = self.transfer(contract_id, outputs, secret_seal)?;
+        let terminals = transfer.terminals.keys().copied().collect::<BTreeSet<_>>();
+        let bundles = transfer.bundles.into_iter().filter(|wb| {
+            wb.anchored_bundles
+                .bundles()
+                .any(|bundle| terminals.contains(&bundle.bundle_id()))
+                || wb
+                    .known_transitions()
+                    .any(|transition| !known_ops.contains(&transition.id()))
+        });
+        transfer.bundles = Confined::from_iter_checked(bundles);
+        Ok(transfer)
+    }
+
+    /// Restores the history omitted from a differential transfer (see
+    /// [`Self::transfer_since`]) using operations already stored in the
+    /// stock, such that the transfer can be validated and accepted.
+    ///
+    /// Fails if some of the omitted operations are not known to the stock.
+    pub fn expand_transfer(
+        &self,
+        mut transfer: Transfer,
+    ) -> Result<Transfer, StockError<S, H, P, ConsignError>> {
+        let contract_id = transfer.contract_id();
+        let mut visited = transfer
+            .bundles
+            .iter()
+            .flat_map(|wb| wb.known_transitions().map(Transition::id))
+            .collect::<BTreeSet<_>>();
+        let ids = transfer
+            .bundles
+            .iter()
+            .flat_map(|wb| wb.known_transitions())
+            .flat_map(|transition| transition.inputs().iter().map(|input| input.prev_out.op))
+            .filter(|id| !visited.contains(id))
+            .collect();
+
+        // Ancestors may belong to the bundles already present in the transfer,
+        // or be anchored to the same witness as them, thus we start with the
+        // anchored bundles from the transfer and regroup them by witnesses
+        // once the history is revealed.
+        let mut pub_witnesses = BTreeMap::new();
+        let mut anchored_bundles = BTreeMap::new();
+        for wb in &transfer.bundles {
+            for (anchor, bundle) in wb.anchored_bundles.clone() {
+                let bundle_id = bundle.bundle_id();
+                pub_witnesses.insert(bundle_id, wb.pub_witness.clone());
+                anchored_bundles.insert(
+                    bundle_id,
+                    ClientBundle::new(anchor.mpc_proof, anchor.dbc_proof, bundle),
+                );
+            }
+        }
+        self.reveal_ancestors(contract_id, ids, &mut visited, &mut anchored_bundles)?;
+
+        let mut bundles = BTreeMap::<XWitnessId, WitnessBundle>::new();
+        for (bundle_id, anchored_bundle) in anchored_bundles {
+            let pub_witness = match pub_witnesses.remove(&bundle_id) {
+                Some(pub_witness) => pub_witness,
+                None => {
+                    let witness_ids = self.index.bundle_info(bundle_id)?.0;
+                    let witness_id = self.state.select_valid_witness(witness_ids)?;
+                    self.stash.witness(witness_id)?.public.clone()
+                }
+            };
+            let witness_id = pub_witness.to_witness_id();
+            let wb = match bundles.remove(&witness_id) {
+                Some(bundle) => bundle.into_double(anchored_bundle)?,
+                None => WitnessBundle::with(pub_witness, anchored_bundle),
+            };
+            bundles.insert(witness_id, wb);
+        }
+        transfer.bundles = Confined::try_from_iter(bundles.into_values())
+            .map_err(|_| ConsignError::TooManyBundles)?;
+        Ok(transfer)
+    }
+
+    /// Reconstructs transfers produced by a past payment closed by the given
+    /// witness, such that they can be resent to a beneficiary who has lost
+    /// the original consignment.
+    ///
+    /// A transfer is produced for each blinded seal assigned by the contract
+    /// transitions under the witness whose secret is not known to the stock,
+    /// i.e. for each beneficiary which has provided a blinded seal. Transfers
+    /// to beneficiaries paid to a witness output are not distinguishable from
+    /// the change and must be rebuilt with [`Self::transfer`] instead.
+    pub fn rebuild_transfers(
+        &self,
+        contract_id: ContractId,
+        witness_id: XWitnessId,
+    ) -> Result<Vec<(XChain<SecretSeal>, Transfer)>, StockError<S, H, P, ConsignError>> {
+        let mut secrets = BTreeSet::new();
+        let bundle_ids = self
+            .stash
+            .witness(witness_id)?
+            .anchors
+            .contract_bundle_ids(contract_id);
+        for bundle_id in bundle_ids {
+            let bundle = self.stash.bundle(bundle_id)?;
+            for assigns in bundle
+                .known_transitions
+                .values()
+                .flat_map(|t| t.assignments.values())
+            {
+                let revealed = assigns
+                    .filter_revealed_seals()
+                    .into_iter()
+                    .map(|seal| seal.conceal())
+                    .collect::<BTreeSet<_>>();
+                secrets.extend(
+                    assigns
+                        .to_confidential_seals()
+                        .into_iter()
+                        .filter(|secret| !revealed.contains(secret)),
+                );
+            }
+        }
+
+        let mut transfers = Vec::with_capacity(secrets.len());
+        for secret in secrets {
+            if self.stash.seal_secret(secret)?.is_some() {
+                continue;
+            }
+            transfers.push((secret, self.transfer(contract_id, [], Some(secret))?));
+        }
+        Ok(transfers)
+    }
+
+    /// Composes a transfer paying the invoice, see [`Self::compose`].
+    ///
+    /// The returned draft keeps track of the invoice beneficiary, such that
+    /// after the witness transaction committing to [`TransferDraft::batch`]
+    /// is signed, [`Self::complete_transfer`] registers it and produces the
+    /// consignment for exactly that beneficiary.
+    pub fn compose_transfer(
+        &self,
+        invoice: &RgbInvoice,
+        prev_outputs: impl IntoIterator<Item = impl Into<XOutputSeal>>,
+        method: CloseMethod,
+        beneficiary_vout: Option<impl Into<Vout>>,
+        allocator: impl Fn(ContractId, AssignmentType, VelocityHint) -> Option<Vout>,
+    ) -> Result<TransferDraft, StockError<S, H, P, ComposeError>> {
+        let contract_id = invoice.contract.ok_or(ComposeError::NoContract)?;
+        let beneficiary_vout = beneficiary_vout.map(Into::into);
+        let beneficiary = match (&invoice.beneficiary.into_inner(), beneficiary_vout) {
+            (Beneficiary::BlindedSeal(seal), _) => {
+                DraftBeneficiary::Terminal(XChain::with(invoice.layer1(), *seal))
+            }
+            (Beneficiary::WitnessVout(_), Some(vout)) => DraftBeneficiary::WitnessVout(vout),
+            (Beneficiary::WitnessVout(_), None) => {
+                return Err(ComposeError::NoBeneficiaryOutput.into());
+            }
+        };
+        let batch = self.compose(invoice, prev_outputs, method, beneficiary_vout, allocator)?;
+        Ok(TransferDraft {
+            contract_id,
+            beneficiary,
+            batch,
+        })
+    }
+
+    /// Completes the transfer composed with [`Self::compose_transfer`]: takes
+    /// the signed witness transaction with its anchors, registers it in the
+    /// stock (see [`Self::consume_fascia`]), and constructs the consignment
+    /// for the invoice beneficiary.
+    ///
+    /// The fascia must commit to all state transitions of the draft batch.
+    /// The operation is atomic: if the consignment can't be constructed, the
+    /// fascia is not registered in the stock.
+    pub fn complete_transfer<R: ResolveWitness>(
+        &mut self,
+        draft: TransferDraft,
+        fascia: Fascia,
+        resolver: R,
+    ) -> Result<Transfer, StockError<S, H, P, TransferError>> {
+        let contract_id = draft.contract_id;
+        if !fascia.bundles.contains_key(&contract_id) {
+            return Err(TransferError::UnrelatedFascia(contract_id).into());
+        }
+        let committed = fascia
+            .clone()
+            .into_bundles()
+            .into_iter()
+            .flat_map(|(id, bundle)| {
+                bundle
+                    .known_transitions
+                    .release()
+                    .into_keys()
+                    .map(move |opid| (id, opid))
+            })
+            .collect::<BTreeSet<_>>();
+        for info in draft.batch {
+            let id = info.transition.contract_id;
+            if !committed.contains(&(id, info.id)) {
+                return Err(TransferError::UncommittedTransition(id, info.id).into());
+            }
+        }
+
+        let witness_id = fascia.witness_id();
+        self.atomic(|stock| {
+            stock.consume_fascia(fascia, resolver)?;
+            stock.draft_transfer(contract_id, draft.beneficiary, witness_id)
+        })
+    }
+
+    fn draft_transfer(
+        &self,
+        contract_id: ContractId,
+        beneficiary: DraftBeneficiary,
+        witness_id: XWitnessId,
+    ) -> Result<Transfer, StockError<S, H, P, TransferError>> {
+        let transfer = match beneficiary {
+            DraftBeneficiary::Terminal(secret) => self.transfer(contract_id, [], Some(secret))?,
+            DraftBeneficiary::WitnessVout(vout) => {
+                let state = self.contract_state(contract_id)?;
+                let outputs = state
+                    .allocations_all()
+                    .filter(|a| {
+                        a.witness == Some(witness_id) && a.seal.as_reduced_unsafe().vout == vout
+                    })
+                    .map(|a| a.seal)
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                self.transfer(contract_id, outputs, None)?
+            }
+        };
+        Ok(transfer)
+    }
+
+    /// Composes a batch of state transitions updating state for the provided
     /// set of previous outputs, satisfying requirements of the invoice, paying
     /// the change back and including the necessary blank state transitions.
     #[allow(clippy::result_large_err)]
@@ -951,9 +2808,9 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
             |id: ContractId,
              assignment_type: AssignmentType|
              -> Result<BuilderSeal<GraphSeal>, StockError<S, H, P, ComposeError>> {
-                let mut suppl = self.stash.supplements(ContentRef::Genesis(id))?;
-                let velocity = suppl
-                    .next()
+                let velocity = self
+                    .stash
+                    .supplement(ContentRef::Genesis(id))?
                     .and_then(|suppl| {
                         suppl
                             .get(
@@ -980,7 +2837,18 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
             }
         }
         let contract_id = invoice.contract.ok_or(ComposeError::NoContract)?;
-        let iface = invoice.iface.as_ref().ok_or(ComposeError::NoIface)?;
+        self.check_quarantine(contract_id)?;
+        let genesis = self.stash.genesis(contract_id)?;
+        if invoice.is_prod() == genesis.testnet {
+            return Err(StockError::NetworkMismatch(contract_id, invoice.chain_network()));
+        }
+        let iface = match &invoice.iface {
+            Some(iface) => IfaceRef::from(iface.clone()),
+            None => self
+                .default_iface(contract_id)?
+                .map(IfaceRef::from)
+                .ok_or(ComposeError::NoIface)?,
+        };
         let mut main_builder =
             self.transition_builder(contract_id, iface.clone(), invoice.operation.clone())?;
         let assignment_name = invoice
@@ -1142,12 +3010,8 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
                     }
                 }
             },
-            _ => {
-                todo!(
-                    "only PersistedState::Amount and PersistedState::Allocation are currently \
-                     supported"
-                )
-            }
+            InvoiceState::Void => return Err(ComposeError::NoState.into()),
+            InvoiceState::Attach(_) => return Err(ComposeError::UnsupportedState.into()),
         }
 
         // 3. Prepare other transitions
@@ -1182,14 +3046,12 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
                     let seal = output_for_assignment(id, opout.ty)?;
                     match output.method() {
                         Method::TapretFirst => {
-                            blank_builder_tapret = blank_builder_tapret
-                                .add_input(opout, state.clone())?
-                                .add_owned_state_raw(opout.ty, seal, state)?
+                            blank_builder_tapret =
+                                blank_builder_tapret.add_input(opout, state, seal)?
                         }
                         Method::OpretFirst => {
-                            blank_builder_opret = blank_builder_opret
-                                .add_input(opout, state.clone())?
-                                .add_owned_state_raw(opout.ty, seal, state)?
+                            blank_builder_opret =
+                                blank_builder_opret.add_input(opout, state, seal)?
                         }
                     }
                 }
@@ -1221,20 +3083,19 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
                 (false, true) => (alt_builder, alt_inputs, None, main_inputs),
                 (false, false) => return Err(ComposeError::InsufficientState.into()),
             };
-        let first = TransitionInfo::new(first_builder.complete_transition()?, first_inputs)
-            .map_err(|e| {
+        let first = first_builder.complete_transition()?;
+        self.check_transition(&first)?;
+        let first = TransitionInfo::new(first, first_inputs).map_err(|e| {
+            debug_assert!(!matches!(e, TransitionInfoError::CloseMethodDivergence(_)));
+            ComposeError::TooManyInputs
+        })?;
+        let second = if let Some(second_builder) = second_builder {
+            let second = second_builder.complete_transition()?;
+            self.check_transition(&second)?;
+            Some(TransitionInfo::new(second, second_inputs).map_err(|e| {
                 debug_assert!(!matches!(e, TransitionInfoError::CloseMethodDivergence(_)));
                 ComposeError::TooManyInputs
-            })?;
-        let second = if let Some(second_builder) = second_builder {
-            Some(
-                TransitionInfo::new(second_builder.complete_transition()?, second_inputs).map_err(
-                    |e| {
-                        debug_assert!(!matches!(e, TransitionInfoError::CloseMethodDivergence(_)));
-                        ComposeError::TooManyInputs
-                    },
-                )?,
-            )
+            })?)
         } else {
             None
         };
@@ -1246,6 +3107,50 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         Ok(batch)
     }
 
+    /// Validates a state transition, which is not yet added to the stock,
+    /// against the contract schema and the contract history known to the
+    /// stock, as the beneficiary does once the transition is received. Unlike
+    /// [`TransitionBuilder::complete_transition`], this runs the schema
+    /// validation scripts.
+    ///
+    /// The transition is not anchored yet, thus its witness is not validated.
+    pub fn validate_transition(
+        &self,
+        transition: &Transition,
+    ) -> Result<validation::Status, StockError<S, H, P>> {
+        let contract_id = transition.contract_id;
+        let genesis = self.stash.genesis(contract_id)?;
+        let schema = &self.stash.schema(genesis.schema_id)?.schema;
+        let (types, scripts) = self.stash.extract(schema, iter::empty::<&Iface>())?;
+        let history = StockHistory {
+            stock: self,
+            schema,
+            genesis,
+            types,
+            scripts,
+        };
+        let state = FrozenState(self.contract_state(contract_id)?);
+        // The witness is never accessed by the validation of a single operation.
+        let witness_id = XChain::Bitcoin(bp::Txid::from([0; 32]));
+        let op = OrdOpRef::Transition(transition, witness_id, WitnessOrd::Tentative);
+        Ok(schema.validate_state(
+            &CheckedConsignment::new(&history),
+            op,
+            Rc::new(RefCell::new(state)),
+        ))
+    }
+
+    fn check_transition(
+        &self,
+        transition: &Transition,
+    ) -> Result<(), StockError<S, H, P, ComposeError>> {
+        let status = self.validate_transition(transition)?;
+        if !status.failures.is_empty() {
+            return Err(BuilderError::SchemaViolation(SchemaViolation::Rejected(status)).into());
+        }
+        Ok(())
+    }
+
     fn store_transaction<E: Error>(
         &mut self,
         f: impl FnOnce(
@@ -1262,7 +3167,11 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
             self.state.rollback_transaction();
             self.stash.rollback_transaction();
         })?;
-        f(&mut self.stash, &mut self.state, &mut self.index)?;
+        f(&mut self.stash, &mut self.state, &mut self.index).inspect_err(|_| {
+            self.state.rollback_transaction();
+            self.stash.rollback_transaction();
+            self.index.rollback_transaction();
+        })?;
         self.index
             .commit_transaction()
             .map_err(StockError::from)
@@ -1275,8 +3184,62 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
             })
     }
 
+    /// Runs `f` as a single atomic operation: the data written by `f` are
+    /// committed only if it succeeds and are rolled back otherwise. Events
+    /// emitted by `f` are delivered to the subscribers only after the commit.
+    fn atomic<T, E: Error>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, StockError<S, H, P, E>>,
+    ) -> Result<T, StockError<S, H, P, E>> {
+        let outer = self.deferred.is_none();
+        if outer {
+            self.deferred = Some(vec![]);
+        }
+        let res = self.atomic_inner(f);
+        if outer {
+            let events = self.deferred.take().unwrap_or_default();
+            if res.is_ok() {
+                for event in events {
+                    self.notify(event);
+                }
+            }
+        }
+        res
+    }
+
+    fn atomic_inner<T, E: Error>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, StockError<S, H, P, E>>,
+    ) -> Result<T, StockError<S, H, P, E>> {
+        self.state.begin_transaction()?;
+        self.stash
+            .begin_transaction()
+            .inspect_err(|_| self.state.rollback_transaction())?;
+        self.index.begin_transaction().inspect_err(|_| {
+            self.state.rollback_transaction();
+            self.stash.rollback_transaction();
+        })?;
+        let res = f(self).inspect_err(|_| {
+            self.state.rollback_transaction();
+            self.stash.rollback_transaction();
+            self.index.rollback_transaction();
+        })?;
+        self.index
+            .commit_transaction()
+            .map_err(StockError::from)
+            .and_then(|_| self.state.commit_transaction().map_err(StockError::from))
+            .and_then(|_| self.stash.commit_transaction().map_err(StockError::from))
+            .inspect_err(|_| {
+                self.state.rollback_transaction();
+                self.stash.rollback_transaction();
+                self.index.rollback_transaction();
+            })?;
+        Ok(res)
+    }
+
     pub fn import_kit(&mut self, kit: ValidKit) -> Result<validation::Status, StockError<S, H, P>> {
         let (kit, status) = kit.split();
+        self.check_quota(&kit)?;
         self.stash.begin_transaction()?;
         self.stash.consume_kit(kit)?;
         self.stash.commit_transaction()?;
@@ -1288,7 +3251,41 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         contract: ValidContract,
         resolver: R,
     ) -> Result<validation::Status, StockError<S, H, P>> {
-        self.consume_consignment(contract, resolver)
+        self.consume_consignment(contract, resolver, |_| ControlFlow::Continue(()))
+    }
+
+    /// Imports contract reporting the progress to the provided callback.
+    ///
+    /// If the callback returns [`ControlFlow::Break`] the import is aborted
+    /// with [`StockError::Aborted`] before any data are committed to the
+    /// stock.
+    pub fn import_contract_with_progress<R: ResolveWitness>(
+        &mut self,
+        contract: ValidContract,
+        resolver: R,
+        progress: impl FnMut(ConsumeProgress) -> ControlFlow<()>,
+    ) -> Result<validation::Status, StockError<S, H, P>> {
+        self.consume_consignment(contract, resolver, progress)
+    }
+
+    /// Imports contract articles previously written with
+    /// [`Self::export_articles`]. Since articles have no witnesses, no
+    /// resolver is required.
+    pub fn import_articles(
+        &mut self,
+        reader: impl io::Read,
+    ) -> Result<ContractId, StockError<S, H, P, ArticlesError>> {
+        let articles = Contract::load(reader).map_err(ArticlesError::from)?;
+        if !articles.bundles.is_empty() || !articles.extensions.is_empty() {
+            return Err(ArticlesError::NotArticles.into());
+        }
+        let contract_id = articles.contract_id();
+        let testnet = articles.genesis.testnet;
+        let articles = articles
+            .validate(&DumbResolver, testnet)
+            .map_err(|(status, _)| ArticlesError::Invalid(status))?;
+        self.import_contract(articles, DumbResolver)?;
+        Ok(contract_id)
     }
 
     pub fn accept_transfer<R: ResolveWitness>(
@@ -1296,25 +3293,249 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         contract: ValidTransfer,
         resolver: R,
     ) -> Result<validation::Status, StockError<S, H, P>> {
-        self.consume_consignment(contract, resolver)
+        self.consume_consignment(contract, resolver, |_| ControlFlow::Continue(()))
+    }
+
+    /// Accepts transfer reporting the progress to the provided callback.
+    ///
+    /// If the callback returns [`ControlFlow::Break`] the transfer acceptance
+    /// is aborted with [`StockError::Aborted`] before any data are committed
+    /// to the stock.
+    pub fn accept_transfer_with_progress<R: ResolveWitness>(
+        &mut self,
+        contract: ValidTransfer,
+        resolver: R,
+        progress: impl FnMut(ConsumeProgress) -> ControlFlow<()>,
+    ) -> Result<validation::Status, StockError<S, H, P>> {
+        self.consume_consignment(contract, resolver, progress)
+    }
+
+    /// Accepts transfer read from an asynchronous reader (like a network
+    /// socket), resolving its witnesses with an asynchronous resolver.
+    ///
+    /// Neither reading the data nor resolving the witnesses blocks the thread.
+    /// Since strict decoding is synchronous, the transfer is buffered in memory
+    /// before being decoded (see [`FileContent::load_async`]). Once all
+    /// witnesses are resolved, the transfer is validated for the network
+    /// specified by `testnet` (as with [`Consignment::validate`]) and stored
+    /// synchronously.
+    ///
+    /// Witnesses which fail to resolve don't abort the acceptance right away:
+    /// they are reported by the validation, together with other issues, in
+    /// the status of [`AcceptError::Invalid`].
+    #[cfg(feature = "async")]
+    pub async fn accept_transfer_async(
+        &mut self,
+        data: impl tokio::io::AsyncRead + Unpin + Send,
+        resolver: &(impl AsyncResolveWitness + Sync),
+        testnet: bool,
+    ) -> Result<validation::Status, StockError<S, H, P, AcceptError>> {
+        let transfer = Transfer::load_async(data)
+            .await
+            .map_err(AcceptError::from)?;
+        let witness_ids = transfer.bundles.iter().map(WitnessBundle::witness_id);
+        let resolver = PrefetchedResolver::prefetch(resolver, witness_ids).await;
+        let transfer = transfer
+            .validate(&resolver, testnet)
+            .map_err(|(status, _)| AcceptError::Invalid(status))?;
+        Ok(self.accept_transfer(transfer, resolver)?)
+    }
+
+    /// Accepts transfers of multiple contracts shipped as a single
+    /// [`MultiTransfer`] file, after each of them was validated.
+    ///
+    /// The transfers are accepted in a single atomic operation: if any of
+    /// them fails, none of them is added to the stock.
+    ///
+    /// Returns validation statuses in the same order as the transfers were
+    /// provided.
+    pub fn accept_transfers<R: ResolveWitness + Clone>(
+        &mut self,
+        transfers: impl IntoIterator<Item = ValidTransfer>,
+        resolver: R,
+    ) -> Result<Vec<validation::Status>, StockError<S, H, P>> {
+        self.atomic(|stock| {
+            transfers
+                .into_iter()
+                .map(|transfer| stock.accept_transfer(transfer, resolver.clone()))
+                .collect()
+        })
+    }
+
+    /// Registers a transfer created by this stock (see [`Self::transfer`]) and
+    /// sent to some other party, updating the sender-side state.
+    ///
+    /// Marks the state spent by the transfer and registers all change
+    /// allocations (seals for which are known to the stock) in a single
+    /// atomic operation. The transfer is subject to the same network,
+    /// timestamp and plugin checks as the one accepted by a receiver.
+    ///
+    /// Fails with [`StockError::OutgoingUnknown`] if any of the transfer
+    /// bundles was not previously added with [`Self::consume_fascia`]; use
+    /// [`Self::accept_transfer`] for consignments received from other
+    /// parties.
+    pub fn register_outgoing<R: ResolveWitness>(
+        &mut self,
+        transfer: ValidTransfer,
+        resolver: R,
+    ) -> Result<validation::Status, StockError<S, H, P>> {
+        self.check_outgoing(&transfer)?;
+        self.consume_consignment(transfer, resolver, |_| ControlFlow::Continue(()))
+    }
+
+    fn check_outgoing(&self, transfer: &Transfer) -> Result<(), StockError<S, H, P>> {
+        let contract_id = transfer.contract_id();
+        self.stash.genesis(contract_id)?;
+        for bundle in transfer
+            .bundles
+            .iter()
+            .flat_map(|wb| wb.anchored_bundles.bundles())
+        {
+            let bundle_id = bundle.bundle_id();
+            match self.index.bundle_info(bundle_id) {
+                Ok(_) => {}
+                Err(IndexError::Inconsistency(_)) => {
+                    return Err(StockError::OutgoingUnknown(bundle_id));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
     }
 
     fn consume_consignment<R: ResolveWitness, const TRANSFER: bool>(
         &mut self,
         consignment: ValidConsignment<TRANSFER>,
         resolver: R,
+        progress: impl FnMut(ConsumeProgress) -> ControlFlow<()>,
     ) -> Result<validation::Status, StockError<S, H, P>> {
-        let (mut consignment, status) = consignment.split();
+        let (consignment, mut status) = consignment.split();
+
+        self.check_genesis(&consignment, &mut status)?;
+        self.run_plugins(&consignment, &mut status)?;
+
+        let contract_id = consignment.contract_id();
+        let known = self.stash.genesis(contract_id).is_ok();
+        // Dependencies are checked before the consignment is stored, such that
+        // a failure to read them doesn't get reported after the data were
+        // already written.
+        let missing = self.missing_consignment_dependencies(&consignment, known)?;
+        let opids = consignment
+            .bundles
+            .iter()
+            .flat_map(|wb| wb.known_transitions().map(Transition::id))
+            .chain(consignment.extensions.iter().map(Extension::id))
+            .collect();
+        self.consume_consignment_unchecked(consignment, resolver, progress)?;
+        if !known {
+            self.notify(StockEvent::ContractAdded(contract_id));
+        }
+        self.notify(StockEvent::StateChanged { contract_id, opids });
+
+        for dep in missing {
+            status.add_warning(Warning::Custom(format!(
+                "contract {contract_id} references contract {dep} which is not known locally"
+            )));
+        }
+
+        Ok(status)
+    }
+
+    /// Checks the consignment genesis against the stock network and the
+    /// timestamp policy. Timestamp failures are added to the `status` as
+    /// warnings unless the policy is [`TimestampPolicy::Strict`].
+    fn check_genesis<const TRANSFER: bool>(
+        &self,
+        consignment: &Consignment<TRANSFER>,
+        status: &mut validation::Status,
+    ) -> Result<(), StockError<S, H, P>> {
+        if let Some(chain_net) = self.chain_net() {
+            if chain_net.is_prod() == consignment.genesis.testnet {
+                return Err(StockError::NetworkMismatch(consignment.contract_id(), chain_net));
+            }
+        }
+        if let Err(err) = check_timestamp(consignment.genesis.timestamp, Utc::now().timestamp()) {
+            match self.timestamp_policy() {
+                TimestampPolicy::Strict => return Err(err.into()),
+                TimestampPolicy::Warn => status.add_warning(Warning::Custom(err.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the plugins registered for the consignment schema, adding their
+    /// warnings to the `status`.
+    fn run_plugins<const TRANSFER: bool>(
+        &self,
+        consignment: &Consignment<TRANSFER>,
+        status: &mut validation::Status,
+    ) -> Result<(), StockError<S, H, P>> {
+        for plugin in self.plugins(consignment.schema_id()) {
+            let warnings = plugin
+                .verify(ConsignmentView::from(consignment))
+                .map_err(|msg| StockError::PluginRejected(plugin.name().to_owned(), msg))?;
+            for warning in warnings {
+                status.add_warning(Warning::Custom(format!("{}: {warning}", plugin.name())));
+            }
+        }
+        Ok(())
+    }
+
+    fn consume_consignment_unchecked<R: ResolveWitness, const TRANSFER: bool>(
+        &mut self,
+        mut consignment: Consignment<TRANSFER>,
+        resolver: R,
+        mut progress: impl FnMut(ConsumeProgress) -> ControlFlow<()>,
+    ) -> Result<(), StockError<S, H, P>> {
+        self.check_quota(&consignment)?;
+
+        // We resolve all witnesses before starting the transaction, such that
+        // the caller may abort the process without leaving the stock in a
+        // partially-updated state.
+        let bytes_total = encoded_len(&consignment);
+        let mut report = ConsumeProgress {
+            operations: 1 + consignment.extensions.len(),
+            operations_total: 1
+                + consignment.extensions.len()
+                + consignment
+                    .bundles
+                    .iter()
+                    .map(|wb| wb.known_transitions().count())
+                    .sum::<usize>(),
+            witnesses: 0,
+            witnesses_total: consignment.bundles.len(),
+            bytes: bytes_total - consignment.bundles.iter().map(encoded_len).sum::<usize>(),
+            bytes_total,
+            stored: false,
+        };
+        if progress(report).is_break() {
+            return Err(StockError::Aborted);
+        }
+        let mut resolver = ResolvedWitnesses::new(resolver);
+        for witness_bundle in &consignment.bundles {
+            let witness_id = witness_bundle.pub_witness.to_witness_id();
+            resolver
+                .resolve(witness_id)
+                .map_err(|e| StockError::WitnessUnresolved(witness_id, e))?;
+            report.operations += witness_bundle.known_transitions().count();
+            report.witnesses += 1;
+            report.bytes += encoded_len(witness_bundle);
+            if progress(report).is_break() {
+                return Err(StockError::Aborted);
+            }
+        }
 
         consignment = self.stash.resolve_secrets(consignment)?;
         self.store_transaction(move |stash, state, index| {
             state.update_from_consignment(&consignment, &resolver)?;
             index.index_consignment(&consignment)?;
             stash.consume_consignment(consignment)?;
+            report.stored = true;
+            if progress(report).is_break() {
+                return Err(StockError::Aborted);
+            }
             Ok(())
-        })?;
-
-        Ok(status)
+        })
     }
 
     /// Imports fascia into the stash, index and inventory.
@@ -1330,8 +3551,17 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
         fascia: Fascia,
         resolver: R,
     ) -> Result<(), StockError<S, H, P, FasciaError>> {
+        self.check_quota(&fascia)?;
+        let witness_id = fascia.witness_id();
+        let mut changes = BTreeMap::<ContractId, BTreeSet<OpId>>::new();
+        for (contract_id, bundle) in fascia.clone().into_bundles() {
+            changes
+                .entry(contract_id)
+                .or_default()
+                .extend(bundle.known_transitions.keys().copied());
+        }
+
         self.store_transaction(move |stash, state, index| {
-            let witness_id = fascia.witness_id();
             stash
                 .consume_witness(SealWitness::new(fascia.witness.clone(), fascia.anchor.clone()))?;
 
@@ -1351,114 +3581,3484 @@ impl<S: StashProvider, H: StateProvider, P: IndexProvider> Stock<S, H, P> {
                 stash.consume_bundle(bundle)?;
             }
             Ok(())
-        })
+        })?;
+
+        self.notify(StockEvent::WitnessAttested(witness_id));
+        for (contract_id, opids) in changes {
+            self.notify(StockEvent::StateChanged { contract_id, opids });
+        }
+        Ok(())
     }
 
-    fn transition(&self, opid: OpId) -> Result<&Transition, StockError<S, H, P, ConsignError>> {
-        let bundle_id = self.index.bundle_id_for_op(opid)?;
-        let bundle = self.stash.bundle(bundle_id)?;
-        bundle
-            .known_transitions
-            .get(&opid)
+    /// Adds state transitions constructed outside of the stock (e.g. by
+    /// third-party tooling or a hardware device) together with their witness,
+    /// after full validation.
+    ///
+    /// Unlike [`Self::consume_fascia`], which trusts the data produced by the
+    /// wallet itself, each of the bundles is validated together with its
+    /// history from the contract genesis known to the stock, as it would be
+    /// done for a received transfer: the history is validated for the network
+    /// of the stock and is subject to the same timestamp and plugin checks.
+    /// The witness transaction must be known to the resolver.
+    pub fn submit_external<R: ResolveWitness>(
+        &mut self,
+        fascia: Fascia,
+        resolver: R,
+    ) -> Result<validation::Status, StockError<S, H, P, SubmitError>> {
+        let witness = SealWitness::new(fascia.witness.clone(), fascia.anchor.clone());
+        let mut status = validation::Status::new();
+        for (contract_id, bundle) in fascia.clone().into_bundles() {
+            let bundle_id = bundle.bundle_id();
+            let mut contract = self.bundle_history(contract_id, &bundle)?;
+            let client_bundle = anchor_bundle(contract_id, bundle, &witness)
+                .map_err(|_| SubmitError::InvalidAnchor(contract_id, bundle_id))?;
+            contract
+                .bundles
+                .push(WitnessBundle::with(fascia.witness.clone(), client_bundle))
+                .map_err(|_| ConsignError::TooManyBundles)?;
+            // Once the genesis passes the check, its network matches the one
+            // of the stock, if the latter is set.
+            self.check_genesis(&contract, &mut status)?;
+            let testnet = contract.genesis.testnet;
+            let (contract, valid) = contract
+                .validate(&resolver, testnet)
+                .map_err(|(status, _)| SubmitError::Invalid(contract_id, status))?
+                .split();
+            for warning in valid.warnings {
+                status.add_warning(warning);
+            }
+            self.run_plugins(&contract, &mut status)?;
+        }
+        self.consume_fascia(fascia, resolver)?;
+        Ok(status)
+    }
+
+    /// Constructs contract consignment with the history of the bundle, i.e.
+    /// all state transitions between genesis and the bundle inputs, which is
+    /// required to validate the bundle.
+    fn bundle_history(
+        &self,
+        contract_id: ContractId,
+        bundle: &TransitionBundle,
+    ) -> Result<Contract, StockError<S, H, P, ConsignError>> {
+        let ids = bundle
+            .known_transitions
+            .values()
+            .flat_map(|transition| transition.inputs().iter().map(|input| input.prev_out.op))
+            .collect();
+        let mut anchored_bundles = BTreeMap::new();
+        self.reveal_ancestors(contract_id, ids, &mut bset![], &mut anchored_bundles)?;
+        self.assemble_consignment(contract_id, anchored_bundles, bmap! {})
+    }
+
+    fn transition(&self, opid: OpId) -> Result<&Transition, StockError<S, H, P, ConsignError>> {
+        let bundle_id = self.index.bundle_id_for_op(opid)?;
+        let bundle = self.stash.bundle(bundle_id)?;
+        bundle
+            .known_transitions
+            .get(&opid)
             .ok_or(ConsignError::Concealed(bundle_id, opid).into())
     }
 
-    fn client_bundle(&self, bundle_id: BundleId) -> Result<ClientBundle, StockError<S, H, P>> {
-        let (witness_ids, contract_id) = self.index.bundle_info(bundle_id)?;
+    fn client_bundle(&self, bundle_id: BundleId) -> Result<ClientBundle, StockError<S, H, P>> {
+        let (witness_ids, contract_id) = self.index.bundle_info(bundle_id)?;
+
+        let bundle = self.stash.bundle(bundle_id)?.clone();
+        let witness_id = self.state.select_valid_witness(witness_ids)?;
+        let witness = self.stash.witness(witness_id)?;
+        Ok(anchor_bundle(contract_id, bundle, witness)?)
+    }
+
+    /// Stores reveal data for a secret seal. State which was already accepted
+    /// on the seal while it was unknown to the stock gets claimed, i.e. added
+    /// to the contract state (see [`Self::claim_pending`]).
+    pub fn store_secret_seal(
+        &mut self,
+        seal: XChain<GraphSeal>,
+    ) -> Result<bool, StockError<S, H, P>> {
+        let stored = self.stash.store_secret_seal(seal)?;
+        if stored {
+            self.claim_seals([seal])?;
+        }
+        Ok(stored)
+    }
+
+    /// Creates a new blinded seal over the given outpoint using a random
+    /// blinding factor, stores its reveal data and returns the concealed seal,
+    /// which can be used as an invoice beneficiary.
+    ///
+    /// Since the reveal data are kept in the stash, the seal gets
+    /// automatically revealed when a transfer assigning state to it is
+    /// accepted, and there is no need to track blinding factors externally.
+    pub fn blind_seal(
+        &mut self,
+        method: CloseMethod,
+        outpoint: impl Into<XOutpoint>,
+    ) -> Result<XChain<SecretSeal>, StockError<S, H, P>> {
+        let seal = outpoint
+            .into()
+            .map(|outpoint| GraphSeal::new_random(method, outpoint.txid, outpoint.vout));
+        self.store_secret_seal(seal)?;
+        Ok(seal.conceal())
+    }
+
+    /// Looks up reveal data for a concealed seal previously stored with
+    /// [`Self::store_secret_seal`] or created with [`Self::blind_seal`].
+    pub fn reveal_secret_seal(
+        &self,
+        secret: XChain<SecretSeal>,
+    ) -> Result<Option<XChain<GraphSeal>>, StockError<S, H, P>> {
+        Ok(self.stash.seal_secret(secret)?)
+    }
+
+    /// Returns all supplements known for the given content, including the
+    /// local one carrying contract tags.
+    pub fn supplements(
+        &self,
+        content_ref: ContentRef,
+    ) -> Result<Vec<Supplement>, StockError<S, H, P>> {
+        Ok(self.stash.supplements(content_ref)?.collect())
+    }
+
+    /// Returns attachment data, if known to the stock.
+    pub fn attachment(&self, id: AttachId) -> Result<Option<&MediumBlob>, StockError<S, H, P>> {
+        Ok(self.stash.attachment(id)?)
+    }
+
+    /// Lists attachments assigned by the contract state (such as token media
+    /// or issuer documents) with their media types and, if known, their data.
+    pub fn contract_attachments(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<Vec<(AttachState, Option<&MediumBlob>)>, StockError<S, H, P>> {
+        let files = self
+            .contract_state(contract_id)?
+            .attach_all()
+            .map(|a| a.state.file.clone())
+            .collect::<Vec<_>>();
+        let mut res = Vec::with_capacity(files.len());
+        for file in files {
+            let data = self.stash.attachment(file.id)?;
+            res.push((file, data));
+        }
+        Ok(res)
+    }
+
+    /// Lists all secret seals generated by the wallet for the invoices (i.e.
+    /// stored with [`Self::store_secret_seal`]) together with the status of
+    /// their use, allowing to display pending receives and to discard seals
+    /// which were never paid to.
+    pub fn issued_seals(&self) -> Result<Vec<IssuedSeal>, StockError<S, H, P>> {
+        let seals = self
+            .stash
+            .secret_seals()?
+            .map(|seal| (seal.conceal(), seal))
+            .collect::<BTreeMap<_, _>>();
+
+        // Seals may be already revealed in the stash, so we can't use the
+        // terminal index and have to look into the bundles themselves.
+        let mut received = BTreeMap::<_, BTreeSet<(ContractId, Opout)>>::new();
+        let mut spent = BTreeSet::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let bundle = self.stash.bundle(bundle_id)?;
+            let (witness_ids, _) = self.index.bundle_info(bundle_id)?;
+            let valid = match self.state.select_valid_witness(witness_ids) {
+                Ok(_) => true,
+                Err(StateError::AbsentValidWitness) => false,
+                Err(err) => return Err(err.into()),
+            };
+            for (opid, transition) in &bundle.known_transitions {
+                if valid {
+                    spent.extend(transition.inputs().iter().map(|input| input.prev_out));
+                }
+                for (ty, assigns) in transition.assignments.iter() {
+                    for (no, secret) in assigns.to_confidential_seals().into_iter().enumerate() {
+                        if seals.contains_key(&secret) {
+                            let opout = Opout::new(*opid, *ty, no as u16);
+                            received
+                                .entry(secret)
+                                .or_default()
+                                .insert((transition.contract_id, opout));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut res = Vec::with_capacity(seals.len());
+        for (secret, seal) in seals {
+            let opouts = received.remove(&secret).unwrap_or_default();
+            let status = if opouts.is_empty() {
+                SealStatus::Unused
+            } else if opouts.iter().all(|(_, opout)| spent.contains(opout)) {
+                SealStatus::Spent
+            } else {
+                SealStatus::Paid
+            };
+            res.push(IssuedSeal {
+                seal,
+                secret,
+                contracts: opouts
+                    .into_iter()
+                    .map(|(contract_id, _)| contract_id)
+                    .collect(),
+                status,
+            });
+        }
+        Ok(res)
+    }
+
+    /// Finds secret seals stored with [`Self::store_secret_seal`] which are
+    /// not needed anymore and removes them from the stock, unless `dry_run`
+    /// is set. Returns the list of found seals.
+    ///
+    /// A seal is orphaned if all operations assigning state to it belong to
+    /// bundles without a valid witness, i.e. they were invalidated by a reorg
+    /// or by a replacement of the witness transaction. Seals which were never
+    /// used are indistinguishable from the seals of pending invoices; they
+    /// are pruned only if `unused` is set. Seals holding state assigned by a
+    /// genesis or a state extension, which have no witness, are never pruned.
+    pub fn prune_seals(
+        &mut self,
+        unused: bool,
+        dry_run: bool,
+    ) -> Result<Vec<XChain<GraphSeal>>, StockError<S, H, P>> {
+        let mut unwitnessed = self
+            .stash
+            .geneses()?
+            .map(|genesis| genesis.id())
+            .collect::<BTreeSet<_>>();
+        unwitnessed.extend(self.stash.extension_ids()?);
+
+        let mut orphans = vec![];
+        for seal in self.stash.secret_seals()? {
+            let opouts = self.index.opouts_by_terminals([seal.conceal()])?;
+            let mut orphaned = unused;
+            for opout in opouts {
+                if unwitnessed.contains(&opout.op) {
+                    orphaned = false;
+                    break;
+                }
+                let bundle_id = self.index.bundle_id_for_op(opout.op)?;
+                let (witness_ids, _) = self.index.bundle_info(bundle_id)?;
+                match self.state.select_valid_witness(witness_ids) {
+                    Ok(_) => {
+                        orphaned = false;
+                        break;
+                    }
+                    Err(StateError::AbsentValidWitness) => orphaned = true,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            if orphaned {
+                orphans.push(seal);
+            }
+        }
+
+        if !dry_run && !orphans.is_empty() {
+            let seals = orphans.clone();
+            self.store_transaction(move |stash, _, _| {
+                for seal in seals {
+                    stash.remove_secret_seal(seal)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(orphans)
+    }
+
+    /// Claims state which was received on secret seals unknown to the stock at
+    /// the time of consignment acceptance.
+    ///
+    /// Such state is kept in the stash under concealed seals and is not
+    /// reflected in the contract state and index. The state is claimed
+    /// automatically once its seal is imported with [`Self::store_secret_seal`]
+    /// or [`Self::blind_seal`]; this method re-claims state for all the secret
+    /// seals known to the stash, which is needed only for stocks populated
+    /// before the automatic claiming. Returns the number of bundles which got
+    /// new state claimed.
+    pub fn claim_pending(&mut self) -> Result<usize, StockError<S, H, P>> {
+        let secrets = self.stash.secret_seals()?.collect::<Vec<_>>();
+        self.claim_seals(secrets)
+    }
+
+    /// Reveals the given seals in the bundles assigning state to them under a
+    /// concealed form, and adds the revealed state to the contract state and
+    /// index. Bundles are looked up via the terminal index; bundles without a
+    /// valid witness are left concealed.
+    fn claim_seals(
+        &mut self,
+        seals: impl IntoIterator<Item = XChain<GraphSeal>>,
+    ) -> Result<usize, StockError<S, H, P>> {
+        let secrets = seals
+            .into_iter()
+            .map(|seal| (seal.conceal(), seal))
+            .collect::<BTreeMap<_, _>>();
+        let opouts = self.index.opouts_by_terminals(secrets.keys().copied())?;
+        if opouts.is_empty() {
+            return Ok(0);
+        }
+
+        // Genesis and state extensions are not part of any bundle
+        let mut unwitnessed = self
+            .stash
+            .geneses()?
+            .map(|genesis| genesis.id())
+            .collect::<BTreeSet<_>>();
+        unwitnessed.extend(self.stash.extension_ids()?);
+        let mut bundle_ids = BTreeSet::new();
+        for opout in opouts {
+            if !unwitnessed.contains(&opout.op) {
+                bundle_ids.insert(self.index.bundle_id_for_op(opout.op)?);
+            }
+        }
+
+        let mut claims = vec![];
+        for bundle_id in bundle_ids {
+            let (witness_ids, contract_id) = self.index.bundle_info(bundle_id)?;
+            let witness_id = match self.state.select_valid_witness(witness_ids) {
+                Ok(witness_id) => witness_id,
+                Err(StateError::AbsentValidWitness) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let mut bundle = self.stash.bundle(bundle_id)?.clone();
+            let mut claimed = false;
+            for assigns in bundle
+                .known_transitions
+                .values_mut()
+                .flat_map(|t| t.assignments.values_mut())
+            {
+                let revealed = assigns
+                    .filter_revealed_seals()
+                    .into_iter()
+                    .map(|seal| seal.conceal())
+                    .collect::<BTreeSet<_>>();
+                for secret in assigns.to_confidential_seals() {
+                    if revealed.contains(&secret) {
+                        continue;
+                    }
+                    if let Some(seal) = secrets.get(&secret) {
+                        assigns.reveal_seal(*seal);
+                        claimed = true;
+                    }
+                }
+            }
+            if claimed {
+                let ord = self
+                    .contract_state(contract_id)?
+                    .witness_ord(witness_id)
+                    .ok_or(StateError::<H>::AbsentValidWitness)?;
+                claims.push((contract_id, witness_id, ord, bundle));
+            }
+        }
+
+        let count = claims.len();
+        let mut changes = BTreeMap::<ContractId, BTreeSet<OpId>>::new();
+        for (contract_id, _, _, bundle) in &claims {
+            changes
+                .entry(*contract_id)
+                .or_default()
+                .extend(bundle.known_transitions.keys().copied());
+        }
+        if count > 0 {
+            self.store_transaction(move |stash, state, index| {
+                for (contract_id, witness_id, ord, bundle) in claims {
+                    // The witness position is already known to the stock
+                    let resolver = ResolvedWitnesses {
+                        inner: DumbResolver,
+                        ords: map! { witness_id => ord },
+                    };
+                    index.index_bundle(contract_id, &bundle, witness_id)?;
+                    state.update_from_bundle(contract_id, &bundle, witness_id, &resolver)?;
+                    stash.consume_bundle(bundle)?;
+                }
+                Ok(())
+            })?;
+        }
+        for (contract_id, opids) in changes {
+            self.notify(StockEvent::StateChanged { contract_id, opids });
+        }
+        Ok(count)
+    }
+
+    /// Checks every witness of the contract known to the stock against the
+    /// blockchain using the provided resolver, without updating the stock.
+    ///
+    /// Useful as an operational health check to detect re-orged or missing
+    /// witnesses; use [`Self::update_witnesses`] to bring the stock in sync.
+    pub fn audit_witnesses(
+        &self,
+        contract_id: ContractId,
+        resolver: impl ResolveWitness,
+    ) -> Result<BTreeMap<XWitnessId, WitnessAudit>, StockError<S, H, P>> {
+        let state = self.contract_state(contract_id)?;
+        let mut res = BTreeMap::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witness_ids, id) = self.index.bundle_info(bundle_id)?;
+            if id != contract_id {
+                continue;
+            }
+            for witness_id in witness_ids {
+                if res.contains_key(&witness_id) {
+                    continue;
+                }
+                let recorded = state.witness_ord(witness_id);
+                let audit = match resolver.resolve_pub_witness_ord(witness_id) {
+                    Ok(actual) if recorded == Some(actual) => WitnessAudit::Matching(actual),
+                    Ok(actual) => WitnessAudit::Changed { recorded, actual },
+                    Err(err) => WitnessAudit::Missing(err.to_string()),
+                };
+                res.insert(witness_id, audit);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Collects a redacted summary of the stock, suitable for attaching to bug
+    /// reports. The report contains public contract information, the number
+    /// of known bundles and witnesses per witness status for each contract,
+    /// and the result of the stock consistency check. It contains no seals,
+    /// allocations, witness transaction ids, application-defined contract
+    /// tags or other private state; use [`Self::audit_witnesses`] when the
+    /// witness ids are needed.
+    pub fn support_report(&self) -> Result<SupportReport, StockError<S, H, P>> {
+        let mut contracts = BTreeMap::<ContractId, ContractReport>::new();
+        for mut info in self.contracts()? {
+            info.tags = none!();
+            contracts.insert(info.id, ContractReport {
+                info,
+                bundles: 0,
+                witnesses: none!(),
+            });
+        }
+        let mut seen = BTreeSet::<(ContractId, XWitnessId)>::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witness_ids, contract_id) = self.index.bundle_info(bundle_id)?;
+            let witness_ids = witness_ids.collect::<Vec<_>>();
+            let state = self.contract_state(contract_id)?;
+            let Some(report) = contracts.get_mut(&contract_id) else {
+                continue;
+            };
+            report.bundles += 1;
+            for witness_id in witness_ids {
+                if !seen.insert((contract_id, witness_id)) {
+                    continue;
+                }
+                let counter = match state.witness_ord(witness_id) {
+                    Some(WitnessOrd::Mined(_)) => &mut report.witnesses.mined,
+                    Some(WitnessOrd::Tentative) => &mut report.witnesses.tentative,
+                    Some(WitnessOrd::Archived) => &mut report.witnesses.archived,
+                    None => &mut report.witnesses.unknown,
+                };
+                *counter += 1;
+            }
+        }
+        Ok(SupportReport {
+            contracts: contracts.into_values().collect(),
+            consistency: self.find_inconsistency(true)?,
+        })
+    }
+
+    /// Detects contracts which history was anchored to a different chain than
+    /// the one known to the `resolver`, as it happens after a signet or
+    /// testnet reset, or when the wallet gets connected to a wrong network.
+    ///
+    /// A contract is reported if the stock has witnesses recorded as mined
+    /// for it, but none of them is known to the resolver. Such contracts
+    /// should not be used for composing new transfers until the backend
+    /// configuration is checked, since their state would not validate; see
+    /// [`Self::quarantine_chain_mismatches`].
+    ///
+    /// Resolver failures other than an unknown witness (like a backend being
+    /// offline) are returned as [`StockError::WitnessUnresolved`], since they
+    /// say nothing about the chain the witnesses belong to.
+    pub fn chain_mismatches(
+        &self,
+        resolver: impl ResolveWitness,
+    ) -> Result<BTreeSet<ContractId>, StockError<S, H, P>> {
+        self.find_chain_mismatches(resolver)
+    }
+
+    fn find_chain_mismatches<E: Error>(
+        &self,
+        resolver: impl ResolveWitness,
+    ) -> Result<BTreeSet<ContractId>, StockError<S, H, P, E>> {
+        let mut mined = BTreeMap::<ContractId, bool>::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witness_ids, contract_id) = self.index.bundle_info(bundle_id)?;
+            let witness_ids = witness_ids.collect::<Vec<_>>();
+            let state = self.contract_state(contract_id)?;
+            for witness_id in witness_ids {
+                if !matches!(state.witness_ord(witness_id), Some(WitnessOrd::Mined(_))) {
+                    continue;
+                }
+                let known = match resolver.resolve_pub_witness_ord(witness_id) {
+                    Ok(_) => true,
+                    Err(WitnessResolverError::Unknown(_)) => false,
+                    Err(err) => return Err(StockError::WitnessUnresolved(witness_id, err)),
+                };
+                *mined.entry(contract_id).or_default() |= known;
+            }
+        }
+        Ok(mined
+            .into_iter()
+            .filter(|(_, known)| !known)
+            .map(|(contract_id, _)| contract_id)
+            .collect())
+    }
+
+    /// Detects contracts with [`Self::chain_mismatches`] and puts them into
+    /// the quarantine, returning the newly quarantined contracts.
+    pub fn quarantine_chain_mismatches(
+        &mut self,
+        resolver: impl ResolveWitness,
+    ) -> Result<BTreeSet<ContractId>, StockError<S, H, P, confinement::Error>> {
+        let mut quarantined = bset![];
+        for contract_id in self.find_chain_mismatches(resolver)? {
+            if self.quarantine(contract_id)? {
+                quarantined.insert(contract_id);
+            }
+        }
+        Ok(quarantined)
+    }
+
+    /// Puts the contract into the quarantine, such that composing and
+    /// consigning transfers of its state fails with
+    /// [`StockError::Quarantined`]. Incoming transfers are still accepted.
+    /// The quarantine is persisted with the stock.
+    ///
+    /// Returns whether the contract was not quarantined before.
+    pub fn quarantine(
+        &mut self,
+        contract_id: ContractId,
+    ) -> Result<bool, StockError<S, H, P, confinement::Error>> {
+        self.stash.genesis(contract_id)?;
+        self.settings
+            .quarantine(contract_id)
+            .map_err(StockError::InvalidInput)
+    }
+
+    /// Releases the contract from the quarantine, returning whether it was
+    /// quarantined.
+    pub fn release_quarantine(&mut self, contract_id: ContractId) -> bool {
+        self.settings.release(contract_id)
+    }
+
+    /// Lists quarantined contracts.
+    pub fn quarantined(&self) -> BTreeSet<ContractId> { self.settings.quarantined().collect() }
+
+    fn check_quarantine<E: Error>(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<(), StockError<S, H, P, E>> {
+        if self.settings.is_quarantined(contract_id) {
+            return Err(StockError::Quarantined(contract_id));
+        }
+        Ok(())
+    }
+
+    /// Re-runs validation of the history of all contracts known to the stock,
+    /// which should be done after upgrading the validation VM or schema
+    /// libraries. For each contract the history leading to all currently
+    /// allocated state is validated.
+    ///
+    /// The `progress` callback is called before each contract gets validated;
+    /// returning [`ControlFlow::Break`] from it aborts the process with
+    /// [`StockError::Aborted`].
+    ///
+    /// Returns contracts which no longer validate, with the validation
+    /// failures grouped by the operations they are reported for. A contract
+    /// which history can't be collected for the validation is reported with
+    /// [`Revalidation::Unconsigned`], and the other contracts are still
+    /// validated.
+    #[allow(clippy::type_complexity)]
+    pub fn revalidate_all(
+        &self,
+        resolver: impl ResolveWitness,
+        mut progress: impl FnMut(ContractId) -> ControlFlow<()>,
+    ) -> Result<
+        BTreeMap<ContractId, Revalidation<StockError<S, H, P, ConsignError>>>,
+        StockError<S, H, P>,
+    > {
+        let contract_ids = self
+            .stash
+            .geneses()?
+            .map(|genesis| genesis.contract_id())
+            .collect::<Vec<_>>();
+        let mut res = BTreeMap::new();
+        for contract_id in contract_ids {
+            if progress(contract_id).is_break() {
+                return Err(StockError::Aborted);
+            }
+            let state = self.contract_state(contract_id)?;
+            let outputs = state
+                .allocations_all()
+                .map(|a| a.seal)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            let contract = match self.consign::<false>(contract_id, outputs, None) {
+                Ok(contract) => contract,
+                Err(err) => {
+                    res.insert(contract_id, Revalidation::Unconsigned(err));
+                    continue;
+                }
+            };
+            let bundles = contract
+                .bundles
+                .iter()
+                .map(|wb| (wb.bundle_id(), wb.known_transitions().map(Transition::id).collect()))
+                .collect::<BTreeMap<_, Vec<_>>>();
+            let testnet = contract.genesis.testnet;
+            let Err((status, _)) = contract.validate(&resolver, testnet) else {
+                continue;
+            };
+            let mut failures = BTreeMap::<Option<OpId>, Vec<validation::Failure>>::new();
+            for failure in status.failures {
+                let opids = failed_operations(&failure, &bundles);
+                if opids.is_empty() {
+                    failures.entry(None).or_default().push(failure);
+                    continue;
+                }
+                for opid in opids {
+                    failures
+                        .entry(Some(opid))
+                        .or_default()
+                        .push(failure.clone());
+                }
+            }
+            res.insert(contract_id, Revalidation::Invalid(failures));
+        }
+        Ok(res)
+    }
+
+    /// Reports fee, virtual size and blockchain position of the witness
+    /// transactions of the contract, for the wallet to show the costs of its
+    /// transfers and their confirmation times.
+    ///
+    /// Fees require transactions spent by the witness to be resolvable by the
+    /// `resolver`; otherwise [`WitnessCost::fee`] is `None`. Witnesses which
+    /// can't be resolved are skipped. Fees and sizes are cached by the stock
+    /// for its lifetime, such that subsequent calls only resolve blockchain
+    /// positions of the already known witnesses.
+    pub fn witness_costs(
+        &self,
+        contract_id: ContractId,
+        resolver: impl ResolveWitness,
+    ) -> Result<BTreeMap<XWitnessId, WitnessCost>, StockError<S, H, P>> {
+        let mut res = BTreeMap::new();
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witness_ids, id) = self.index.bundle_info(bundle_id)?;
+            if id != contract_id {
+                continue;
+            }
+            for witness_id in witness_ids {
+                if res.contains_key(&witness_id) {
+                    continue;
+                }
+                if let Some(cost) = self.witness_cost(witness_id, &resolver) {
+                    res.insert(witness_id, cost);
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns the wallet history of the contract (see
+    /// [`ContractIface::history`]) together with the costs of the witness
+    /// transactions of each operation (see [`Self::witness_costs`]). Issue
+    /// operations and operations which witness can't be resolved have no
+    /// costs.
+    pub fn history_costs(
+        &self,
+        contract_id: ContractId,
+        iface: impl Into<IfaceRef>,
+        filter_outpoints: impl AssignmentsFilter + Clone,
+        filter_witnesses: impl AssignmentsFilter + Clone,
+        resolver: impl ResolveWitness,
+    ) -> Result<Vec<(ContractOp, Option<WitnessCost>)>, StockError<S, H, P, ContractIfaceError>>
+    {
+        let history = self
+            .contract_iface(contract_id, iface)?
+            .history(filter_outpoints, filter_witnesses);
+        Ok(history
+            .into_iter()
+            .map(|op| {
+                let cost = op
+                    .witness
+                    .and_then(|witness| self.witness_cost(witness.id, &resolver));
+                (op, cost)
+            })
+            .collect())
+    }
+
+    fn witness_cost(
+        &self,
+        witness_id: XWitnessId,
+        resolver: &impl ResolveWitness,
+    ) -> Option<WitnessCost> {
+        let ord = resolver.resolve_pub_witness_ord(witness_id).ok()?;
+        let cached = self
+            .costs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&witness_id)
+            .copied();
+        if let Some((fee, vsize)) = cached {
+            return Some(WitnessCost {
+                fee: Some(fee),
+                vsize,
+                ord,
+            });
+        }
+
+        let tx = resolver.resolve_pub_witness(witness_id).ok()?;
+        let tx = tx.as_reduced_unsafe();
+        let mut fee = Some(Sats::ZERO);
+        for input in &tx.inputs {
+            let prev_id = XChain::with(witness_id.layer1(), input.prev_output.txid);
+            let value = resolver.resolve_pub_witness(prev_id).ok().and_then(|prev| {
+                let vout = input.prev_output.vout.to_usize();
+                prev.as_reduced_unsafe()
+                    .outputs
+                    .get(vout)
+                    .map(|out| out.value)
+            });
+            fee = fee
+                .zip(value)
+                .and_then(|(fee, value)| fee.checked_add(value));
+        }
+        let spent = tx.outputs.iter().map(|out| out.value).sum::<Sats>();
+        let fee = fee.and_then(|fee| fee.checked_sub(spent));
+        let vsize = tx.weight_units().to_vbytes_ceil().to_u32();
+        // Fees which can't be computed yet are not cached, since transactions
+        // spent by the witness may become resolvable later.
+        if let Some(fee) = fee {
+            self.costs
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(witness_id, (fee, vsize));
+        }
+        Some(WitnessCost { fee, vsize, ord })
+    }
+
+    /// Updates blockchain positions of all witnesses which are not mined
+    /// below `after_height`, using the provided resolver. Subscribers are
+    /// notified about each witness which has changed its position.
+    pub fn update_witnesses(
+        &mut self,
+        resolver: impl ResolveWitness,
+        after_height: u32,
+    ) -> Result<UpdateRes, StockError<S, H, P>> {
+        let res = self.state.update_witnesses(resolver, after_height)?;
+        for (witness_id, ord) in &res.changed {
+            self.notify(StockEvent::WitnessUpdated(*witness_id, *ord));
+        }
+        Ok(res)
+    }
+
+    /// Updates the blockchain position of a single witness, for instance on a
+    /// notification from a blockchain indexer. Contract state is recomputed
+    /// accordingly: allocations created by witnesses which were re-orged out
+    /// or dropped from the mempool become invalid.
+    ///
+    /// Returns `false` if the witness is not known to the stock.
+    pub fn update_witness_status(
+        &mut self,
+        witness_id: XWitnessId,
+        ord: WitnessOrd,
+    ) -> Result<bool, StockError<S, H, P>> {
+        let known = self.state.update_witness(witness_id, ord)?;
+        if known {
+            self.notify(StockEvent::WitnessUpdated(witness_id, ord));
+        }
+        Ok(known)
+    }
+
+    /// Cancels a transfer which was registered with [`Self::consume_fascia`],
+    /// but whose witness transaction was never mined (for instance, it was
+    /// abandoned before broadcast).
+    ///
+    /// The witness gets archived, which excludes the state assigned by the
+    /// transfer to the beneficiary and to the change from the contract state.
+    /// The allocations spent by the transfer are no longer considered spent
+    /// by the stock (see [`Self::seal_definitions`]), so they can be used for
+    /// new transfers once the wallet releases their outputs. The operations
+    /// and the change seals are kept, so if the transaction gets mined after
+    /// all, the transfer is restored by [`Self::update_witnesses`].
+    ///
+    /// Returns `false` if the witness is unknown or is already mined.
+    pub fn cancel_transfer(&mut self, witness_id: XWitnessId) -> Result<bool, StockError<S, H, P>> {
+        let mut ord = None;
+        for bundle_id in self.stash.bundle_ids()? {
+            let (mut witness_ids, contract_id) = self.index.bundle_info(bundle_id)?;
+            if witness_ids.any(|id| id == witness_id) {
+                ord = self.contract_state(contract_id)?.witness_ord(witness_id);
+                break;
+            }
+        }
+        match ord {
+            None | Some(WitnessOrd::Mined(_)) => Ok(false),
+            Some(_) => self.update_witness_status(witness_id, WitnessOrd::Archived),
+        }
+    }
+
+    /// Rolls back witnesses removed from the blockchain by a re-org.
+    ///
+    /// Operations anchored to these witnesses are kept in the stock, but the
+    /// witnesses get archived, so the state they create is excluded from the
+    /// contract state. Witnesses anchoring operations which spend the state
+    /// created by the operations left without a valid witness are rolled back
+    /// as well. If a transaction gets mined again, its operations are
+    /// restored by [`Self::update_witnesses`] or
+    /// [`Self::update_witness_status`].
+    ///
+    /// The rollback is atomic. Returns witnesses which were known to the stock
+    /// and got rolled back, including the descendant ones.
+    pub fn rollback(
+        &mut self,
+        witness_ids: impl IntoIterator<Item = XWitnessId>,
+    ) -> Result<BTreeSet<XWitnessId>, StockError<S, H, P>> {
+        // Witnesses of each bundle together with the operations it contains
+        // and the operations it spends.
+        let mut bundles = vec![];
+        for bundle_id in self.stash.bundle_ids()? {
+            let (witness_ids, _) = self.index.bundle_info(bundle_id)?;
+            let witness_ids = witness_ids.collect::<BTreeSet<_>>();
+            let bundle = self.stash.bundle(bundle_id)?;
+            let opids = bundle
+                .known_transitions
+                .keys()
+                .copied()
+                .collect::<BTreeSet<_>>();
+            let spent = bundle
+                .known_transitions
+                .values()
+                .flat_map(|transition| transition.inputs.iter().map(|input| input.prev_out.op))
+                .collect::<BTreeSet<_>>();
+            bundles.push((witness_ids, opids, spent));
+        }
+
+        let mut queue = witness_ids.into_iter().collect::<Vec<_>>();
+        let mut rolled_back = bset![];
+        self.store_transaction(|_, state, _| {
+            while let Some(witness_id) = queue.pop() {
+                if rolled_back.contains(&witness_id)
+                    || !state.update_witness(witness_id, WitnessOrd::Archived)?
+                {
+                    continue;
+                }
+                rolled_back.insert(witness_id);
+
+                let mut invalid = BTreeSet::new();
+                for (witness_ids, opids, _) in &bundles {
+                    if !witness_ids.contains(&witness_id) {
+                        continue;
+                    }
+                    match state.select_valid_witness(witness_ids) {
+                        Ok(_) => {}
+                        Err(StateError::AbsentValidWitness) => invalid.extend(opids),
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+                for (witness_ids, _, spent) in &bundles {
+                    if !spent.is_disjoint(&invalid) {
+                        queue.extend(witness_ids);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        for witness_id in &rolled_back {
+            self.notify(StockEvent::WitnessUpdated(*witness_id, WitnessOrd::Archived));
+        }
+        Ok(rolled_back)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct UpdateRes {
+    pub succeeded: usize,
+    pub failed: HashMap<XWitnessId, String>,
+    /// Witnesses which have changed their blockchain position, with the new
+    /// position.
+    pub changed: BTreeMap<XWitnessId, WitnessOrd>,
+}
+
+/// Costs and blockchain position of a witness transaction, see
+/// [`Stock::witness_costs`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct WitnessCost {
+    /// Fee paid by the witness transaction, if all transactions it spends are
+    /// known.
+    pub fee: Option<Sats>,
+    /// Virtual size of the witness transaction.
+    pub vsize: u32,
+    /// Position of the witness transaction in the blockchain; for mined
+    /// transactions it provides confirmation height and block timestamp.
+    pub ord: WitnessOrd,
+}
+
+impl WitnessCost {
+    /// Fee rate in sats per virtual byte, if the fee is known.
+    pub fn fee_rate(&self) -> Option<f64> {
+        self.fee
+            .map(|fee| fee.sats() as f64 / self.vsize.max(1) as f64)
+    }
+}
+
+impl Display for WitnessCost {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.fee, self.fee_rate()) {
+            (Some(fee), Some(rate)) => write!(f, "{} sats\t{rate:.1} sat/vB", fee.sats())?,
+            _ => f.write_str("~\t~")?,
+        }
+        write!(f, "\t{} vB\t{}", self.vsize, self.ord)
+    }
+}
+
+/// Fungible allocations of a contract held by the wallet, see
+/// [`Stock::fragmentation`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Fragmentation {
+    pub contract_id: ContractId,
+    /// Name of the assignment holding the allocations.
+    pub assignment: FieldName,
+    pub allocations: BTreeMap<XOutputSeal, Vec<Amount>>,
+}
+
+impl Fragmentation {
+    /// Number of separate allocations.
+    pub fn count(&self) -> usize { self.allocations.values().map(Vec::len).sum() }
+
+    /// Total amount of all allocations.
+    pub fn total(&self) -> Amount { self.allocations.values().flatten().copied().sum() }
+
+    /// Constructs an invoice for a self-transfer consolidating all the
+    /// allocations into a single one, assigned to the provided beneficiary.
+    ///
+    /// The invoice must be composed with the wallet outputs holding the
+    /// allocations as inputs, like any other payment.
+    pub fn consolidation_invoice(
+        &self,
+        beneficiary: impl Into<XChainNet<Beneficiary>>,
+    ) -> RgbInvoice {
+        RgbInvoiceBuilder::with(self.contract_id, beneficiary)
+            .set_assignment(self.assignment.clone())
+            .set_amount_raw(self.total())
+            .finish()
+    }
+}
+
+/// Opt-in policy deciding when fragmented allocations are worth being
+/// consolidated with a self-transfer.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ConsolidationPolicy {
+    /// Minimal number of allocations triggering the consolidation.
+    pub min_allocations: usize,
+    /// Maximal fee rate, in sats per virtual byte, at which the consolidation
+    /// is performed.
+    pub max_fee_rate: f64,
+    /// Minimal interval between consolidations, in seconds.
+    pub min_interval: i64,
+}
+
+impl Default for ConsolidationPolicy {
+    fn default() -> Self {
+        ConsolidationPolicy {
+            min_allocations: 16,
+            max_fee_rate: 2.0,
+            min_interval: 24 * 60 * 60,
+        }
+    }
+}
+
+impl ConsolidationPolicy {
+    /// Checks whether the allocations should be consolidated given the current
+    /// fee rate and the UTC timestamp of the last consolidation, if any.
+    pub fn is_due(&self, fragmentation: &Fragmentation, fee_rate: f64, last: Option<i64>) -> bool {
+        fragmentation.count() >= self.min_allocations
+            && fee_rate <= self.max_fee_rate
+            && last.map_or(true, |last| Utc::now().timestamp() - last >= self.min_interval)
+    }
+}
+
+/// Redacted summary of the stock contents, see [`Stock::support_report`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SupportReport {
+    pub contracts: Vec<ContractReport>,
+    /// Stock consistency error, if any.
+    pub consistency: Option<ConsistencyError>,
+}
+
+/// Part of [`SupportReport`] describing a single contract.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ContractReport {
+    pub info: ContractInfo,
+    /// Number of state transition bundles known for the contract.
+    pub bundles: usize,
+    /// Number of witnesses of the contract bundles per their status.
+    pub witnesses: WitnessSummary,
+}
+
+/// Number of witnesses per their status, see [`ContractReport`].
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+pub struct WitnessSummary {
+    pub mined: usize,
+    pub tentative: usize,
+    pub archived: usize,
+    /// Witnesses with no status known to the stock.
+    pub unknown: usize,
+}
+
+impl Display for SupportReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.consistency {
+            None => writeln!(f, "Consistency: ok")?,
+            Some(err) => writeln!(f, "Consistency: {err}")?,
+        }
+        for contract in &self.contracts {
+            writeln!(f)?;
+            write!(f, "{}", contract.info)?;
+            writeln!(f, "  Bundles: {}", contract.bundles)?;
+            let witnesses = contract.witnesses;
+            writeln!(
+                f,
+                "  Witnesses: {} mined, {} tentative, {} archived, {} unknown",
+                witnesses.mined, witnesses.tentative, witnesses.archived, witnesses.unknown
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of [`Stock::revalidate_all`] for a contract which no longer
+/// validates.
+#[derive(Debug)]
+pub enum Revalidation<E> {
+    /// Validation failures, grouped by the operations they are reported for.
+    /// Failures not related to a specific operation, like schema or network
+    /// mismatches, are listed under `None`.
+    Invalid(BTreeMap<Option<OpId>, Vec<validation::Failure>>),
+    /// History of the contract can't be collected for the validation.
+    Unconsigned(E),
+}
+
+/// Returns operations a validation failure is reported for; failures related
+/// to a bundle are reported for all bundle transitions.
+fn failed_operations(
+    failure: &validation::Failure,
+    bundles: &BTreeMap<BundleId, Vec<OpId>>,
+) -> Vec<OpId> {
+    use validation::Failure;
+
+    let bundle_id = match failure {
+        Failure::SchemaUnknownExtensionType(opid, _)
+        | Failure::SchemaUnknownTransitionType(opid, _)
+        | Failure::SchemaUnknownMetaType(opid, _)
+        | Failure::SchemaUnknownGlobalStateType(opid, _)
+        | Failure::SchemaUnknownAssignmentType(opid, _)
+        | Failure::SchemaUnknownValencyType(opid, _)
+        | Failure::SchemaGlobalStateOccurrences(opid, ..)
+        | Failure::SchemaGlobalStateLimit(opid, ..)
+        | Failure::SchemaNoMetadata(opid, _)
+        | Failure::SchemaInvalidMetadata(opid, _)
+        | Failure::SchemaInvalidGlobalValue(opid, ..)
+        | Failure::SchemaInvalidOwnedValue(opid, ..)
+        | Failure::SchemaInputOccurrences(opid, ..)
+        | Failure::SchemaAssignmentOccurrences(opid, ..)
+        | Failure::CyclicGraph(opid)
+        | Failure::OperationAbsent(opid)
+        | Failure::ContractMismatch(opid, _)
+        | Failure::BundleExtraTransition(_, opid)
+        | Failure::BundleInvalidInput(_, opid, _)
+        | Failure::BundleInvalidCommitment(.., opid)
+        | Failure::NoPrevState { opid, .. }
+        | Failure::NoPrevOut(opid, _)
+        | Failure::SealsUnvalidated(opid)
+        | Failure::ValencyNoParent { opid, .. }
+        | Failure::NoPrevValency { opid, .. }
+        | Failure::StateTypeMismatch { opid, .. }
+        | Failure::MediaTypeMismatch { opid, .. }
+        | Failure::FungibleTypeMismatch { opid, .. }
+        | Failure::BulletproofsInvalid(opid, ..)
+        | Failure::ScriptFailure(opid, ..)
+        | Failure::ContractStateFilled(opid) => return vec![*opid],
+        Failure::ConfidentialSeal(opout) => return vec![opout.op],
+        Failure::BundleAbsent(bundle_id)
+        | Failure::AnchorAbsent(bundle_id)
+        | Failure::WitnessIdAbsent(bundle_id)
+        | Failure::WitnessUnresolved(bundle_id, ..)
+        | Failure::SealNoPubWitness(bundle_id, ..)
+        | Failure::SealInvalidMethod(bundle_id, _)
+        | Failure::SealsInvalid(bundle_id, ..)
+        | Failure::AnchorMethodMismatch(bundle_id)
+        | Failure::MpcInvalid(bundle_id, ..) => bundle_id,
+        _ => return vec![],
+    };
+    bundles.get(bundle_id).cloned().unwrap_or_default()
+}
+
+/// Change of the stock data, see [`Stock::subscribe`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum StockEvent {
+    /// A new contract was added to the stock.
+    ContractAdded(ContractId),
+    /// The contract and all its data were removed from the stock.
+    ContractRemoved(ContractId),
+    /// Contract state was updated with the given operations.
+    StateChanged {
+        contract_id: ContractId,
+        opids: BTreeSet<OpId>,
+    },
+    /// A witness transaction created by the wallet was registered.
+    WitnessAttested(XWitnessId),
+    /// Blockchain position of a witness transaction was changed.
+    WitnessUpdated(XWitnessId, WitnessOrd),
+}
+
+/// Use status of a secret seal issued by the wallet, see
+/// [`Stock::issued_seals`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum SealStatus {
+    /// No state is known to be assigned to the seal.
+    Unused,
+    /// The seal holds state received by the wallet.
+    Paid,
+    /// State received on the seal was spent afterwards by a transition with a
+    /// valid witness.
+    Spent,
+}
+
+/// Public definition of a seal holding contract state, without the state
+/// data, see [`Stock::seal_definitions`].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct SealDefinition {
+    pub seal: XOutputSeal,
+    /// Witness transaction which has assigned state to the seal, or `None`
+    /// for the seals defined in the contract genesis.
+    pub witness: Option<XWitnessId>,
+}
+
+/// Secret seal issued by the wallet, see [`Stock::issued_seals`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct IssuedSeal {
+    /// Revealed seal definition known to the wallet.
+    pub seal: XChain<GraphSeal>,
+    /// Concealed seal used in the invoices.
+    pub secret: XChain<SecretSeal>,
+    /// Contracts which assigned state to the seal.
+    pub contracts: BTreeSet<ContractId>,
+    pub status: SealStatus,
+}
+
+/// Result of checking a witness stored in the stock against the current
+/// blockchain state, see [`Stock::audit_witnesses`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum WitnessAudit {
+    /// Witness position in the blockchain matches the stored one.
+    Matching(WitnessOrd),
+    /// Witness position has changed since the last update (for instance due
+    /// to a re-org or a newly mined transaction).
+    Changed {
+        recorded: Option<WitnessOrd>,
+        actual: WitnessOrd,
+    },
+    /// Witness can't be resolved.
+    Missing(String),
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use std::str::FromStr;
+
+    use baid64::FromBaid64Str;
+    use commit_verify::{Conceal, DigestExt, Sha256};
+    use strict_encoding::TypeName;
+
+    use super::*;
+    use crate::containers::ConsignmentExt;
+
+    #[test]
+    fn test_consign() {
+        let mut stock = Stock::in_memory();
+        let seal = XChain::with(
+            rgbcore::Layer1::Bitcoin,
+            GraphSeal::new_random_vout(bp::dbc::Method::OpretFirst, Vout::from_u32(0)),
+        );
+        let secret_seal = seal.conceal();
+
+        stock.store_secret_seal(seal).unwrap();
+        let contract_id =
+            ContractId::from_baid64_str("rgb:qFuT6DN8-9AuO95M-7R8R8Mc-AZvs7zG-obum1Va-BRnweKk")
+                .unwrap();
+        if let Ok(transfer) = stock.consign::<true>(contract_id, [], Some(secret_seal)) {
+            println!("{:?}", transfer.supplements)
+        }
+    }
+
+    #[test]
+    fn test_consistency() {
+        use strict_encoding::StrictDumb;
+
+        let stock = Stock::in_memory();
+        assert!(stock.check_consistency().is_ok());
+
+        let contract_id = Contract::strict_dumb().contract_id();
+        let import = || {
+            let mut contract = Contract::strict_dumb();
+            let first = transition(contract_id, vec![]);
+            let second =
+                transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+            contract.bundles = Confined::from_checked(vec![
+                witness_bundle(contract_id, first),
+                witness_bundle(contract_id, second),
+            ]);
+            let mut stock = Stock::in_memory();
+            stock
+                .consume_consignment_unchecked(
+                    contract,
+                    DumbResolver,
+                    |_| ControlFlow::Continue(()),
+                )
+                .unwrap();
+            stock
+        };
+        let stock = import();
+        assert!(stock.check_consistency().is_ok());
+        assert_eq!(stock.find_inconsistency(false).unwrap(), None);
+
+        let mut broken = import();
+        broken.state = Stock::in_memory().state;
+        assert!(matches!(
+            broken.check_consistency(),
+            Err(StockError::InvalidInput(ConsistencyError::StateAbsent(id))) if id == contract_id
+        ));
+
+        let mut broken = import();
+        broken.index = Stock::in_memory().index;
+        assert!(matches!(
+            broken.check_consistency(),
+            Err(StockError::InvalidInput(ConsistencyError::BundleNotIndexed(_)))
+        ));
+        // The check on load doesn't read the bundles, but detects the missing
+        // ones.
+        assert_eq!(
+            broken.find_inconsistency(false).unwrap(),
+            Some(ConsistencyError::BundleCountMismatch {
+                stashed: 2,
+                indexed: 0
+            })
+        );
+
+        let mut broken = Stock::in_memory();
+        broken.index = import().index;
+        assert!(matches!(
+            broken.check_consistency(),
+            Err(StockError::InvalidInput(ConsistencyError::BundleCountMismatch {
+                stashed: 0,
+                indexed: 2
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_in_memory_rollback() {
+        let mut stash = MemStash::in_memory();
+        let seal = XChain::with(
+            rgbcore::Layer1::Bitcoin,
+            GraphSeal::new_random_vout(bp::dbc::Method::OpretFirst, Vout::from_u32(0)),
+        );
+
+        stash.begin_transaction().unwrap();
+        stash.add_secret_seal(seal).unwrap();
+        stash.rollback_transaction();
+        assert_eq!(stash.secret_seals().unwrap().count(), 0);
+
+        stash.begin_transaction().unwrap();
+        stash.add_secret_seal(seal).unwrap();
+        stash.commit_transaction().unwrap();
+        assert_eq!(stash.secret_seals().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_nested_rollback() {
+        let mut stash = MemStash::in_memory();
+        let seal1 = XChain::with(
+            rgbcore::Layer1::Bitcoin,
+            GraphSeal::new_random_vout(bp::dbc::Method::OpretFirst, Vout::from_u32(0)),
+        );
+        let seal2 = XChain::with(
+            rgbcore::Layer1::Bitcoin,
+            GraphSeal::new_random_vout(bp::dbc::Method::OpretFirst, Vout::from_u32(1)),
+        );
+
+        stash.begin_transaction().unwrap();
+        stash.add_secret_seal(seal1).unwrap();
+        stash.begin_transaction().unwrap();
+        stash.add_secret_seal(seal2).unwrap();
+        stash.remove_secret_seal(seal1).unwrap();
+        stash.rollback_transaction();
+        assert_eq!(stash.secret_seals().unwrap().collect::<Vec<_>>(), vec![seal1]);
+        stash.commit_transaction().unwrap();
+        assert_eq!(stash.secret_seals().unwrap().collect::<Vec<_>>(), vec![seal1]);
+
+        stash.begin_transaction().unwrap();
+        stash.begin_transaction().unwrap();
+        stash.add_secret_seal(seal2).unwrap();
+        stash.commit_transaction().unwrap();
+        stash.rollback_transaction();
+        assert_eq!(stash.secret_seals().unwrap().collect::<Vec<_>>(), vec![seal1]);
+    }
+
+    #[test]
+    fn test_rollback_unknown() {
+        let mut stock = Stock::in_memory();
+        let witness_id = XChain::with(rgbcore::Layer1::Bitcoin, bp::Txid::from([0u8; 32]));
+        assert!(stock.rollback([witness_id]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_issued_seals() {
+        let mut stock = Stock::in_memory();
+        let seal = XChain::with(
+            rgbcore::Layer1::Bitcoin,
+            GraphSeal::new_random_vout(bp::dbc::Method::OpretFirst, Vout::from_u32(0)),
+        );
+        stock.store_secret_seal(seal).unwrap();
+        let issued = stock.issued_seals().unwrap();
+        assert_eq!(issued.len(), 1);
+        assert_eq!(issued[0].secret, seal.conceal());
+        assert_eq!(issued[0].status, SealStatus::Unused);
+        assert!(issued[0].contracts.is_empty());
+    }
+
+    #[test]
+    fn test_issued_seals_status() {
+        use rgb::{Assign, Assignments, Input, Inputs, TypedAssigns, VoidState};
+        use strict_encoding::StrictDumb;
+
+        fn add_bundle(stock: &mut Stock, contract_id: ContractId, transition: Transition) {
+            let witness_id =
+                XChain::with(Layer1::Bitcoin, bp::Txid::from(transition.id().to_byte_array()));
+            let mut bundle = TransitionBundle::strict_dumb();
+            bundle.known_transitions =
+                Confined::from_checked(bmap! { transition.id() => transition });
+            stock
+                .store_transaction::<Infallible>(|stash, state, index| {
+                    index.index_bundle(contract_id, &bundle, witness_id)?;
+                    // Witness gets archived by the dumb resolver
+                    state.update_from_bundle(contract_id, &bundle, witness_id, DumbResolver)?;
+                    stash.consume_bundle(bundle)?;
+                    Ok(())
+                })
+                .unwrap();
+        }
+
+        let mut stock = Stock::in_memory();
+        let contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        stock
+            .store_transaction::<Infallible>(|_, state, index| {
+                state.update_from_consignment(&contract, DumbResolver)?;
+                index.index_consignment(&contract)?;
+                Ok(())
+            })
+            .unwrap();
+
+        let seal = XChain::with(
+            Layer1::Bitcoin,
+            GraphSeal::new_random_vout(Method::OpretFirst, Vout::from_u32(0)),
+        );
+        stock.store_secret_seal(seal).unwrap();
+
+        let ty = AssignmentType::with(1);
+        let mut receive = Transition::strict_dumb();
+        receive.contract_id = contract_id;
+        receive.assignments = Assignments::from_inner(tiny_bmap! {
+            ty => TypedAssigns::Declarative(small_vec![Assign::revealed(seal, VoidState::default())])
+        });
+        let receive_witness =
+            XChain::with(Layer1::Bitcoin, bp::Txid::from(receive.id().to_byte_array()));
+        let opout = Opout::new(receive.id(), ty, 0);
+        add_bundle(&mut stock, contract_id, receive);
+        stock
+            .update_witness_status(receive_witness, WitnessOrd::Tentative)
+            .unwrap();
+
+        let issued = stock.issued_seals().unwrap();
+        assert_eq!(issued.len(), 1);
+        assert_eq!(issued[0].status, SealStatus::Paid);
+        assert_eq!(issued[0].contracts, bset![contract_id]);
+
+        // Spending transition without a valid witness doesn't spend the state
+        let mut spend = Transition::strict_dumb();
+        spend.contract_id = contract_id;
+        spend.inputs = Inputs::from_inner(small_bset![Input::with(opout)]);
+        let spend_witness =
+            XChain::with(Layer1::Bitcoin, bp::Txid::from(spend.id().to_byte_array()));
+        add_bundle(&mut stock, contract_id, spend);
+        assert_eq!(stock.issued_seals().unwrap()[0].status, SealStatus::Paid);
+
+        stock
+            .update_witness_status(spend_witness, WitnessOrd::Tentative)
+            .unwrap();
+        assert_eq!(stock.issued_seals().unwrap()[0].status, SealStatus::Spent);
+    }
+
+    #[test]
+    fn test_claim_pending() {
+        use rgb::{Assign, Assignments, TypedAssigns, VoidState};
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let seal = XChain::with(
+            Layer1::Bitcoin,
+            GraphSeal::new_random_vout(Method::OpretFirst, Vout::from_u32(0)),
+        );
+        let ty = AssignmentType::with(1);
+        let mut receive = transition(contract_id, vec![]);
+        receive.assignments = Assignments::from_inner(tiny_bmap! {
+            ty => TypedAssigns::Declarative(small_vec![Assign::ConfidentialSeal {
+                seal: seal.conceal(),
+                state: VoidState::default(),
+                lock: default!(),
+            }])
+        });
+        let opout = Opout::new(receive.id(), ty, 0);
+        let bundle = witness_bundle(contract_id, receive);
+        let witness_id = bundle.witness_id();
+        contract.bundles = Confined::from_checked(vec![bundle]);
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        stock
+            .update_witness_status(witness_id, WitnessOrd::Tentative)
+            .unwrap();
+        let is_owned = |stock: &Stock| {
+            stock
+                .contract_state(contract_id)
+                .unwrap()
+                .rights_all()
+                .any(|a| a.opout == opout && a.seal == seal.try_to_output_seal(witness_id).unwrap())
+        };
+        assert!(!is_owned(&stock));
+
+        // Importing the seal claims the state without an explicit call
+        assert!(stock.store_secret_seal(seal).unwrap());
+        assert!(is_owned(&stock));
+        assert_eq!(stock.claim_pending().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_select_outputs() {
+        use rgb::{AssetTag, Assign, Assignments, RevealedValue, TypedAssigns};
+        use strict_encoding::StrictDumb;
+
+        use crate::interface::SelectStrategy;
+
+        let ty = AssignmentType::with(1);
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let seals = [1u32, 2, 3].map(|vout| {
+            XChain::with(
+                Layer1::Bitcoin,
+                GraphSeal::new_random_vout(Method::OpretFirst, Vout::from_u32(vout)),
+            )
+        });
+        let mut receive = transition(contract_id, vec![]);
+        let assigns = seals.iter().zip([10u64, 20, 30]).map(|(seal, amount)| {
+            Assign::revealed(
+                *seal,
+                RevealedValue::new_random_blinding(amount, AssetTag::strict_dumb()),
+            )
+        });
+        receive.assignments = Assignments::from_inner(tiny_bmap! {
+            ty => TypedAssigns::Fungible(Confined::from_iter_checked(assigns))
+        });
+        let bundle = witness_bundle(contract_id, receive);
+        let witness_id = bundle.witness_id();
+        contract.bundles = Confined::from_checked(vec![bundle]);
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        stock
+            .update_witness_status(witness_id, WitnessOrd::Tentative)
+            .unwrap();
+        let outputs = seals.map(|seal| seal.try_to_output_seal(witness_id).unwrap());
+
+        let select = |ty: AssignmentType, outputs: &[XOutputSeal], amount: u64| {
+            stock
+                .select_outputs(
+                    contract_id,
+                    ty,
+                    outputs,
+                    Amount::from(amount),
+                    SelectStrategy::SmallestFirst,
+                )
+                .unwrap()
+        };
+        assert_eq!(select(ty, &outputs, 25), Some(vec![outputs[0], outputs[1]]));
+        // Only the provided seals are used
+        assert_eq!(select(ty, &outputs[2..], 25), Some(vec![outputs[2]]));
+        assert_eq!(select(ty, &outputs[..2], 31), None);
+        assert_eq!(select(ty, &outputs, 61), None);
+        // State of other types is not selected
+        assert_eq!(select(AssignmentType::with(2), &outputs, 1), None);
+    }
+
+    // Witness bundle with a single transition anchored with an opret
+    // commitment.
+    pub(in crate::persistence) fn witness_bundle(
+        contract_id: ContractId,
+        transition: Transition,
+    ) -> WitnessBundle {
+        use bp::dbc::opret::OpretProof;
+        use commit_verify::{mpc, TryCommitVerify};
+        use strict_encoding::StrictDumb;
+
+        use crate::containers::PubWitness;
+
+        let mut bundle = TransitionBundle::strict_dumb();
+        bundle.close_method = Method::OpretFirst;
+        bundle.input_map = rgb::InputMap::with(Vout::from_u32(0), transition.id());
+        bundle.known_transitions = Confined::from_checked(bmap! { transition.id() => transition });
+        let protocol_id = mpc::ProtocolId::from(contract_id);
+        let source = mpc::MultiSource {
+            messages: Confined::from_checked(bmap! {
+                protocol_id => mpc::Message::from(bundle.bundle_id())
+            }),
+            static_entropy: Some(0),
+            ..default!()
+        };
+        let mpc_proof = mpc::MerkleBlock::from(&mpc::MerkleTree::try_commit(&source).unwrap())
+            .to_merkle_proof(protocol_id)
+            .unwrap();
+        let txid = bp::Txid::from(bundle.bundle_id().to_byte_array());
+        WitnessBundle::with(
+            XChain::Bitcoin(PubWitness::Txid(txid)),
+            ClientBundle::new(mpc_proof, DbcProof::Opret(OpretProof::default()), bundle),
+        )
+    }
+
+    // Transition assigning a single right to a random seal.
+    pub(in crate::persistence) fn transition(
+        contract_id: ContractId,
+        inputs: Vec<Opout>,
+    ) -> Transition {
+        use rgb::{Assign, Assignments, Input, Inputs, TypedAssigns, VoidState};
+        use strict_encoding::StrictDumb;
+
+        let seal = XChain::with(
+            Layer1::Bitcoin,
+            GraphSeal::new_random_vout(Method::OpretFirst, Vout::from_u32(0)),
+        );
+        let mut transition = Transition::strict_dumb();
+        transition.contract_id = contract_id;
+        transition.inputs =
+            Inputs::from_inner(Confined::from_iter_checked(inputs.into_iter().map(Input::with)));
+        transition.assignments = Assignments::from_inner(tiny_bmap! {
+            AssignmentType::with(1) => TypedAssigns::Declarative(small_vec![Assign::revealed(seal, VoidState::default())])
+        });
+        transition
+    }
+
+    #[test]
+    fn test_bundle_history() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let ty = AssignmentType::with(1);
+
+        // The submitted transition spends the output of `second`, which spends
+        // the output of `first`; none of them are owned by the wallet.
+        let first = transition(contract_id, vec![]);
+        let second = transition(contract_id, vec![Opout::new(first.id(), ty, 0)]);
+        let submitted = transition(contract_id, vec![Opout::new(second.id(), ty, 0)]);
+        let opids = bset![first.id(), second.id()];
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for witness_id in witness_ids {
+            stock
+                .update_witness_status(witness_id, WitnessOrd::Tentative)
+                .unwrap();
+        }
+
+        let mut bundle = TransitionBundle::strict_dumb();
+        bundle.known_transitions = Confined::from_checked(bmap! { submitted.id() => submitted });
+        let history = stock.bundle_history(contract_id, &bundle).unwrap();
+        assert_eq!(
+            history
+                .bundles
+                .iter()
+                .flat_map(|wb| wb.known_transitions().map(|t| t.id()))
+                .collect::<BTreeSet<_>>(),
+            opids
+        );
+    }
+
+    #[test]
+    fn test_blind_seal() {
+        let mut stock = Stock::in_memory();
+        let outpoint = bp::Outpoint::new(bp::Txid::from([0x5a; 32]), 1u32);
+        let secret = stock
+            .blind_seal(CloseMethod::OpretFirst, XChain::with(rgbcore::Layer1::Bitcoin, outpoint))
+            .unwrap();
+        let seal = stock.reveal_secret_seal(secret).unwrap().unwrap();
+        assert_eq!(seal.conceal(), secret);
+        assert_eq!(seal.as_reduced_unsafe().vout, Vout::from_u32(1));
+        assert_eq!(stock.issued_seals().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prune_seals() {
+        let mut stock = Stock::in_memory();
+        let outpoint = bp::Outpoint::new(bp::Txid::from([0x5a; 32]), 1u32);
+        let secret = stock
+            .blind_seal(CloseMethod::OpretFirst, XChain::with(rgbcore::Layer1::Bitcoin, outpoint))
+            .unwrap();
+        assert!(stock.prune_seals(false, false).unwrap().is_empty());
+        let pruned = stock.prune_seals(true, true).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].conceal(), secret);
+        assert!(stock.reveal_secret_seal(secret).unwrap().is_some());
+        assert_eq!(stock.prune_seals(true, false).unwrap(), pruned);
+        assert!(stock.reveal_secret_seal(secret).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prune_issued_seal() {
+        use rgb::{Assign, Assignments, TypedAssigns, VoidState};
+        use strict_encoding::StrictDumb;
+
+        let mut stock = Stock::in_memory();
+        let outpoint = bp::Outpoint::new(bp::Txid::from([0x5a; 32]), 1u32);
+        let secret = stock
+            .blind_seal(CloseMethod::OpretFirst, XChain::with(rgbcore::Layer1::Bitcoin, outpoint))
+            .unwrap();
+        let mut contract = Contract::strict_dumb();
+        contract.genesis.assignments = Assignments::from_inner(tiny_bmap! {
+            AssignmentType::with(1) => TypedAssigns::Declarative(small_vec![Assign::ConfidentialSeal {
+                seal: secret,
+                state: VoidState::default(),
+                lock: default!(),
+            }])
+        });
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+
+        assert!(stock.prune_seals(true, false).unwrap().is_empty());
+        assert!(stock.reveal_secret_seal(secret).unwrap().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_quota_refuses_consume() {
+        use strict_encoding::StrictDumb;
+
+        use crate::persistence::fs::FsBinStore;
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("rgb-std-stock-quota-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FsBinStore::new(dir.clone()).unwrap();
+        let mut stock = Stock::in_memory();
+        stock.make_persistent(store.clone(), true).unwrap();
+        stock.store().unwrap();
+        let usage = store.disk_usage().unwrap();
+
+        let contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let required = encoded_len(&contract) as u64;
+        let mut quota = store.clone();
+        quota.quota = Some(usage.total() + required - 1);
+        stock.set_quota(quota);
+        let err = stock
+            .consume_consignment_unchecked(contract.clone(), DumbResolver, |_| {
+                ControlFlow::Continue(())
+            })
+            .unwrap_err();
+        assert!(matches!(err, StockError::Quota(QuotaError::Exceeded { .. })));
+        assert_eq!(store.disk_usage().unwrap(), usage);
+        let loaded: Stock = Stock::load(store.clone(), false).unwrap();
+        assert!(loaded.contract_state(contract_id).is_err());
+
+        let mut quota = store.clone();
+        quota.quota = Some(usage.total() + required);
+        stock.set_quota(quota);
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        let loaded: Stock = Stock::load(store, false).unwrap();
+        assert!(loaded.contract_state(contract_id).is_ok());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_consolidation_policy() {
+        let contract_id =
+            ContractId::from_baid64_str("rgb:qFuT6DN8-9AuO95M-7R8R8Mc-AZvs7zG-obum1Va-BRnweKk")
+                .unwrap();
+        let outpoint = bp::Outpoint::new(bp::Txid::from([0x5a; 32]), 1u32);
+        let seal = XChain::with(
+            rgbcore::Layer1::Bitcoin,
+            bp::seals::txout::ExplicitSeal::new(CloseMethod::OpretFirst, outpoint),
+        );
+        let fragmentation = Fragmentation {
+            contract_id,
+            assignment: fname!("assetOwner"),
+            allocations: bmap! { seal => vec![Amount::from(10u64); 20] },
+        };
+        assert_eq!(fragmentation.count(), 20);
+        assert_eq!(fragmentation.total(), Amount::from(200u64));
+
+        let policy = ConsolidationPolicy::default();
+        let now = Utc::now().timestamp();
+        assert!(policy.is_due(&fragmentation, 1.0, None));
+        assert!(!policy.is_due(&fragmentation, 10.0, None));
+        assert!(!policy.is_due(&fragmentation, 1.0, Some(now)));
+        assert!(policy.is_due(&fragmentation, 1.0, Some(now - policy.min_interval)));
+    }
+
+    #[test]
+    fn test_register_plugin() {
+        #[derive(Debug)]
+        struct RejectAll;
+        impl VerifyPlugin for RejectAll {
+            fn name(&self) -> &str { "reject-all" }
+            fn verify(&self, _: ConsignmentView) -> Result<Vec<String>, String> {
+                Err(s!("rejected"))
+            }
+        }
+
+        let schema_id = SchemaId::from(Sha256::default());
+        let mut stock = Stock::in_memory();
+        assert!(stock.plugins(schema_id).is_empty());
+        stock.register_plugin(schema_id, Arc::new(RejectAll));
+        assert_eq!(stock.plugins(schema_id).len(), 1);
+        assert_eq!(stock.plugins(schema_id)[0].name(), "reject-all");
+        assert_eq!(stock.clone_no_persistence().plugins(schema_id).len(), 1);
+    }
+
+    #[test]
+    fn test_plugins_on_consume() {
+        use strict_encoding::StrictDumb;
+
+        #[derive(Debug)]
+        struct MaxBundles(usize);
+        impl VerifyPlugin for MaxBundles {
+            fn name(&self) -> &str { "max-bundles" }
+            fn verify(&self, view: ConsignmentView) -> Result<Vec<String>, String> {
+                match view.bundles.len() {
+                    len if len > self.0 => Err(format!("{len} bundles")),
+                    len => Ok(vec![format!("{len} of {} bundles", self.0)]),
+                }
+            }
+        }
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let schema_id = contract.schema_id();
+        contract.bundles = Confined::from_checked(vec![witness_bundle(
+            contract_id,
+            transition(contract_id, vec![]),
+        )]);
+
+        let mut stock = Stock::in_memory();
+        stock.register_plugin(schema_id, Arc::new(MaxBundles(0)));
+        assert!(matches!(
+            stock.import_contract(ValidContract::assume_valid(contract.clone()), DumbResolver),
+            Err(StockError::PluginRejected(name, msg)) if name == "max-bundles" && msg == "1 bundles"
+        ));
+        assert!(stock.contract_state(contract_id).is_err());
+
+        // Plugins registered for other schemata are not run.
+        let mut stock = Stock::in_memory();
+        stock.register_plugin(SchemaId::from(Sha256::default()), Arc::new(MaxBundles(0)));
+        stock.register_plugin(schema_id, Arc::new(MaxBundles(1)));
+        let status = stock
+            .import_contract(ValidContract::assume_valid(contract), DumbResolver)
+            .unwrap();
+        assert!(status
+            .warnings
+            .contains(&Warning::Custom(s!("max-bundles: 1 of 1 bundles"))));
+        assert!(stock.contract_state(contract_id).is_ok());
+    }
+
+    #[test]
+    fn test_contract_ifaces() {
+        use strict_encoding::StrictDumb;
+
+        let contract =
+            Contract::from_str(include_str!("../../asset/armored_contract.default")).unwrap();
+        let contract_id = contract.contract_id();
+        let schema_id = contract.schema_id();
+        let expected = contract
+            .ifaces
+            .iter()
+            .filter(|(iface, iimpl)| {
+                iimpl.schema_id == schema_id && iimpl.iface_id == iface.iface_id()
+            })
+            .map(|(_, iimpl)| iimpl.iface_id)
+            .collect::<BTreeSet<_>>();
+        assert!(!expected.is_empty());
+        let dumb = Contract::strict_dumb();
+        let dumb_id = dumb.contract_id();
+
+        let mut stock = Stock::in_memory();
+        assert!(stock.contract_ifaces(contract_id).is_err());
+        stock
+            .import_contract(ValidContract::assume_valid(contract), DumbResolver)
+            .unwrap();
+        stock
+            .import_contract(ValidContract::assume_valid(dumb), DumbResolver)
+            .unwrap();
+
+        let ifaces = stock
+            .contract_ifaces(contract_id)
+            .unwrap()
+            .map(|info| info.id)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(ifaces, expected);
+        // Interfaces known to the stock are not listed for contracts which
+        // schema doesn't implement them.
+        assert!(stock.ifaces().unwrap().count() > 0);
+        assert_eq!(stock.contract_ifaces(dumb_id).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_export_contract() {
+        let stock = Stock::in_memory();
+        let contract_id =
+            ContractId::from_baid64_str("rgb:qFuT6DN8-9AuO95M-7R8R8Mc-AZvs7zG-obum1Va-BRnweKk")
+                .unwrap();
+        if let Ok(contract) = stock.export_contract(contract_id) {
+            println!("{:?}", contract.contract_id())
+        }
+    }
+
+    #[test]
+    fn test_purge_unknown_contract() {
+        let mut stock = Stock::in_memory();
+        let contract_id =
+            ContractId::from_baid64_str("rgb:qFuT6DN8-9AuO95M-7R8R8Mc-AZvs7zG-obum1Va-BRnweKk")
+                .unwrap();
+        assert!(!stock.purge_contract(contract_id).unwrap());
+        assert!(stock.archive_contract(contract_id).is_err());
+    }
+
+    #[test]
+    fn test_archive_contract() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let import =
+            |stock: &mut Stock, contract: Contract| {
+                stock
+                    .consume_consignment_unchecked(contract, DumbResolver, |_| {
+                        ControlFlow::Continue(())
+                    })
+                    .unwrap();
+                for witness_id in &witness_ids {
+                    stock
+                        .update_witness_status(*witness_id, WitnessOrd::Tentative)
+                        .unwrap();
+                }
+                stock
+                    .contract_state(contract_id)
+                    .unwrap()
+                    .rights_all()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            };
+
+        let mut stock = Stock::in_memory();
+        let rights = import(&mut stock, contract);
+        assert_eq!(rights.len(), 2);
+
+        let archive = stock.archive_contract(contract_id).unwrap();
+        assert_eq!(archive.bundles.len(), 2);
+        assert!(stock.contract_state(contract_id).is_err());
+
+        let mut data = vec![];
+        archive.save(&mut data).unwrap();
+        let archive = Contract::load(&data[..]).unwrap();
+        assert_eq!(import(&mut stock, archive), rights);
+    }
+
+    #[test]
+    fn test_contract_tags() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let issuer = Supplement::new(ContentRef::Genesis(contract_id), "ssi:issuer");
+        let mut tags = ContractTags::default();
+        tags.insert(tiny_s!("customer"), tiny_s!("42")).unwrap();
+        let mut local = Supplement::new(ContentRef::Genesis(contract_id), "ssi:issuer");
+        local.annotate_itself(SUPPL_ANNOT_TAGS, &tags).unwrap();
+        contract.supplements = Confined::from_checked(bset![issuer.clone(), local]);
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert_eq!(stock.contract_info(contract_id).unwrap().tags, tags);
+        assert_eq!(stock.contracts().unwrap().next().unwrap().tags, tags);
+
+        let exported = stock.export_contract(contract_id).unwrap();
+        assert_eq!(exported.supplements.iter().collect::<Vec<_>>(), vec![&issuer]);
+
+        let mut replaced = ContractTags::default();
+        replaced.insert(tiny_s!("customer"), tiny_s!("43")).unwrap();
+        stock
+            .set_contract_tags(contract_id, replaced.clone())
+            .unwrap();
+        assert_eq!(stock.contract_tags(contract_id).unwrap(), replaced);
+        assert_eq!(
+            stock
+                .supplements(ContentRef::Genesis(contract_id))
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_support_report() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        stock
+            .update_witness_status(witness_ids[0], WitnessOrd::Tentative)
+            .unwrap();
+        let mut tags = ContractTags::default();
+        tags.insert(tiny_s!("customer"), tiny_s!("42")).unwrap();
+        stock.set_contract_tags(contract_id, tags).unwrap();
+
+        let report = stock.support_report().unwrap();
+        assert_eq!(report.contracts.len(), 1);
+        let contract = &report.contracts[0];
+        assert_eq!(contract.info.id, contract_id);
+        assert_eq!(contract.info.tags, ContractTags::default());
+        assert_eq!(contract.bundles, 2);
+        assert_eq!(contract.witnesses.tentative, 1);
+        assert_eq!(
+            contract.witnesses.mined + contract.witnesses.archived + contract.witnesses.unknown,
+            1
+        );
+
+        let text = report.to_string();
+        assert!(!text.contains("customer"));
+        for witness_id in witness_ids {
+            assert!(!text.contains(&witness_id.as_reduced_unsafe().to_string()));
+        }
+    }
+
+    #[test]
+    fn test_revalidate_all() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let opids = contract
+            .bundles
+            .iter()
+            .flat_map(|wb| wb.known_transitions().map(Transition::id))
+            .chain([contract.genesis.id()])
+            .collect::<BTreeSet<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+
+        assert!(matches!(
+            stock.revalidate_all(DumbResolver, |_| ControlFlow::Break(())),
+            Err(StockError::Aborted)
+        ));
+
+        let mut visited = vec![];
+        let res = stock
+            .revalidate_all(DumbResolver, |id| {
+                visited.push(id);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(visited, vec![contract_id]);
+        let Some(Revalidation::Invalid(failures)) = res.get(&contract_id) else {
+            panic!("contract with dumb witnesses must not validate");
+        };
+        assert!(!failures.is_empty());
+        assert!(failures.keys().flatten().all(|opid| opids.contains(opid)));
+    }
+
+    #[test]
+    fn test_failed_operations() {
+        use rgb::validation::Failure;
+
+        let opid = OpId::from_byte_array([1; 32]);
+        let other = OpId::from_byte_array([2; 32]);
+        let bundle_id = BundleId::from_byte_array([3; 32]);
+        let bundles = bmap! { bundle_id => vec![opid, other] };
+
+        assert_eq!(failed_operations(&Failure::CyclicGraph(opid), &bundles), vec![opid]);
+        assert_eq!(
+            failed_operations(
+                &Failure::ConfidentialSeal(Opout::new(other, AssignmentType::with(1), 0)),
+                &bundles
+            ),
+            vec![other]
+        );
+        assert_eq!(failed_operations(&Failure::AnchorMethodMismatch(bundle_id), &bundles), vec![
+            opid, other
+        ]);
+        assert_eq!(
+            failed_operations(&Failure::AnchorAbsent(BundleId::from_byte_array([4; 32])), &bundles),
+            vec![]
+        );
+        assert_eq!(failed_operations(&Failure::NetworkMismatch(true), &bundles), vec![]);
+    }
+
+    #[test]
+    fn test_register_unknown_outgoing() {
+        use strict_encoding::StrictDumb;
+
+        let contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+
+        let bundle = witness_bundle(contract_id, transition(contract_id, vec![]));
+        let bundle_id = bundle
+            .anchored_bundles
+            .bundles()
+            .next()
+            .unwrap()
+            .bundle_id();
+        let mut transfer = Transfer::strict_dumb();
+        transfer.bundles = Confined::from_checked(vec![bundle.clone()]);
+        assert!(matches!(
+            stock.check_outgoing(&transfer),
+            Err(StockError::OutgoingUnknown(id)) if id == bundle_id
+        ));
+
+        let mut contract = Contract::strict_dumb();
+        contract.bundles = Confined::from_checked(vec![bundle]);
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        stock.check_outgoing(&transfer).unwrap();
+    }
+
+    #[test]
+    fn test_accept_transfers_atomic() {
+        use strict_encoding::StrictDumb;
+
+        let mut first = Transfer::strict_dumb();
+        let contract_id = first.contract_id();
+        first.bundles = Confined::from_checked(vec![witness_bundle(
+            contract_id,
+            transition(contract_id, vec![]),
+        )]);
+        // The genesis of the second transfer is a testnet one.
+        let mut second = Transfer::strict_dumb();
+        second.genesis.testnet = true;
+        let second_id = second.contract_id();
+
+        let mut stock = Stock::in_memory();
+        stock.set_chain_net(ChainNet::BitcoinMainnet);
+        let events = stock.subscribe();
+        assert!(matches!(
+            stock.accept_transfers(
+                [ValidTransfer::assume_valid(first.clone()), ValidTransfer::assume_valid(second)],
+                DumbResolver
+            ),
+            Err(StockError::NetworkMismatch(id, ChainNet::BitcoinMainnet)) if id == second_id
+        ));
+        assert!(stock.contract_info(contract_id).is_err());
+        assert!(events.try_recv().is_err());
+
+        let statuses = stock
+            .accept_transfers([ValidTransfer::assume_valid(first)], DumbResolver)
+            .unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert!(stock.contract_info(contract_id).is_ok());
+        assert!(events.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_consume_progress() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        contract.bundles = Confined::from_checked(vec![witness_bundle(
+            contract_id,
+            transition(contract_id, vec![]),
+        )]);
+
+        let mut stock = Stock::in_memory();
+        let mut reports = vec![];
+        let err = stock
+            .consume_consignment_unchecked(contract.clone(), DumbResolver, |report| {
+                reports.push(report);
+                if report.stored {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap_err();
+        assert!(matches!(err, StockError::Aborted));
+        assert!(stock.contract_info(contract_id).is_err());
+
+        assert_eq!(reports.len(), 3);
+        let last = reports.last().unwrap();
+        assert_eq!(last.witnesses, last.witnesses_total);
+        assert_eq!(last.operations, last.operations_total);
+        assert_eq!(last.bytes, last.bytes_total);
+        assert!(reports.windows(2).all(|w| w[0].bytes <= w[1].bytes));
+
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert!(stock.contract_info(contract_id).is_ok());
+    }
+
+    #[test]
+    fn test_complete_transfer() {
+        use amplify::confinement::NonEmptyOrdMap;
+        use strict_encoding::StrictDumb;
 
-        let bundle = self.stash.bundle(bundle_id)?.clone();
-        let witness_id = self.state.select_valid_witness(witness_ids)?;
-        let witness = self.stash.witness(witness_id)?;
-        let (merkle_block, dbc) = match (bundle.close_method, &witness.anchors) {
-            (
-                CloseMethod::TapretFirst,
-                AnchorSet::Tapret(tapret) | AnchorSet::Double { tapret, .. },
-            ) => (&tapret.mpc_proof, DbcProof::Tapret(tapret.dbc_proof.clone())),
-            (
-                CloseMethod::OpretFirst,
-                AnchorSet::Opret(opret) | AnchorSet::Double { opret, .. },
-            ) => (&opret.mpc_proof, DbcProof::Opret(opret.dbc_proof)),
-            _ => {
-                return Err(
-                    StashInconsistency::BundleMissedInAnchors(bundle_id, contract_id).into()
-                );
+        use crate::containers::BundleDichotomy;
+
+        let contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+
+        let committed = transition(contract_id, vec![]);
+        let wb = witness_bundle(contract_id, committed.clone());
+        let bundle = wb.anchored_bundles.bundles().next().unwrap().clone();
+        let fascia = Fascia {
+            witness: wb.pub_witness.clone(),
+            anchor: AnchorSet::strict_dumb(),
+            bundles: NonEmptyOrdMap::with_key_value(contract_id, BundleDichotomy::single(bundle)),
+        };
+        let witness_id = fascia.witness_id();
+        let draft = |transition: Transition| TransferDraft {
+            contract_id,
+            beneficiary: DraftBeneficiary::Terminal(
+                XChain::with(
+                    Layer1::Bitcoin,
+                    GraphSeal::new_random_vout(Method::OpretFirst, Vout::from_u32(0)),
+                )
+                .conceal(),
+            ),
+            batch: Batch {
+                main: TransitionDichotomy::single(
+                    TransitionInfo::new(transition, [strict_dumb!()]).unwrap(),
+                ),
+                blanks: none!(),
+            },
+        };
+
+        let other = transition(contract_id, vec![]);
+        let other_id = other.id();
+        assert!(matches!(
+            stock.complete_transfer(draft(other), fascia.clone(), DumbResolver),
+            Err(StockError::InvalidInput(TransferError::UncommittedTransition(id, opid)))
+                if id == contract_id && opid == other_id
+        ));
+        assert!(stock.stash.witness(witness_id).is_err());
+
+        // The beneficiary seal is not assigned by the transfer, thus the
+        // consignment can't be constructed and the fascia must not be kept.
+        let events = stock.subscribe();
+        assert!(matches!(
+            stock.complete_transfer(draft(committed), fascia, DumbResolver),
+            Err(StockError::InvalidInput(TransferError::Consign(_)))
+        ));
+        assert!(stock.stash.witness(witness_id).is_err());
+        assert!(stock
+            .index
+            .bundle_info(wb.anchored_bundles.bundles().next().unwrap().bundle_id())
+            .is_err());
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_rollback_descendants() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        let unrelated = transition(contract_id, vec![]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+            witness_bundle(contract_id, unrelated),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for witness_id in &witness_ids {
+            stock
+                .update_witness_status(*witness_id, WitnessOrd::Tentative)
+                .unwrap();
+        }
+
+        let rolled_back = stock.rollback([witness_ids[0]]).unwrap();
+        assert_eq!(rolled_back, bset![witness_ids[0], witness_ids[1]]);
+        let state = stock.contract_state(contract_id).unwrap();
+        assert_eq!(state.witness_ord(witness_ids[0]), Some(WitnessOrd::Archived));
+        assert_eq!(state.witness_ord(witness_ids[1]), Some(WitnessOrd::Archived));
+        assert_eq!(state.witness_ord(witness_ids[2]), Some(WitnessOrd::Tentative));
+    }
+
+    #[test]
+    fn test_seal_definitions() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for witness_id in &witness_ids {
+            stock
+                .update_witness_status(*witness_id, WitnessOrd::Tentative)
+                .unwrap();
+        }
+
+        // The seal of `first` is spent by `second`.
+        let witnesses = |stock: &Stock| {
+            stock
+                .seal_definitions(contract_id)
+                .unwrap()
+                .into_iter()
+                .map(|definition| definition.witness)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(witnesses(&stock), vec![Some(witness_ids[1])]);
+
+        // Once the spending witness is archived, the seal of `first` holds the
+        // state again.
+        stock
+            .update_witness_status(witness_ids[1], WitnessOrd::Archived)
+            .unwrap();
+        assert_eq!(witnesses(&stock), vec![Some(witness_ids[0])]);
+    }
+
+    #[test]
+    fn test_validate_transition() {
+        use rgb::validation::Failure;
+        use rgb::{Occurrences, OwnedStateSchema, TransitionSchema, TransitionType};
+        use strict_encoding::StrictDumb;
+
+        let ty = AssignmentType::with(1);
+        let transition_type = Transition::strict_dumb().transition_type;
+        let mut contract = Contract::strict_dumb();
+        contract.schema.owned_types = tiny_bmap! { ty => OwnedStateSchema::Declarative };
+        contract.schema.transitions = tiny_bmap! { transition_type => TransitionSchema {
+            metadata: none!(),
+            globals: none!(),
+            inputs: tiny_bmap! { ty => Occurrences::Once },
+            assignments: tiny_bmap! { ty => Occurrences::Once },
+            valencies: none!(),
+            validator: None,
+        } };
+        contract.genesis.schema_id = contract.schema.schema_id();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let first_id = first.id();
+        contract.bundles = Confined::from_checked(vec![witness_bundle(contract_id, first)]);
+        let witness_id = contract.bundles[0].witness_id();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        stock
+            .update_witness_status(witness_id, WitnessOrd::Tentative)
+            .unwrap();
+
+        let valid = transition(contract_id, vec![Opout::new(first_id, ty, 0)]);
+        assert_eq!(stock.validate_transition(&valid).unwrap().failures, vec![]);
+
+        let unknown = Opout::new(OpId::from([1; 32]), ty, 0);
+        let orphan = transition(contract_id, vec![unknown]);
+        let status = stock.validate_transition(&orphan).unwrap();
+        assert!(status
+            .failures
+            .contains(&Failure::OperationAbsent(unknown.op)));
+
+        let mut untyped = transition(contract_id, vec![Opout::new(first_id, ty, 0)]);
+        untyped.transition_type = TransitionType::with(7);
+        assert_eq!(stock.validate_transition(&untyped).unwrap().failures, vec![
+            Failure::SchemaUnknownTransitionType(untyped.id(), TransitionType::with(7))
+        ]);
+    }
+
+    #[test]
+    fn test_contract_allocations_at() {
+        use std::num::NonZeroU32;
+
+        use rgb::vm::WitnessPos;
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for (witness_id, height) in witness_ids.iter().zip([100, 200]) {
+            let pos = WitnessPos::bitcoin(NonZeroU32::new(height).unwrap(), 1_700_000_000).unwrap();
+            stock
+                .update_witness_status(*witness_id, WitnessOrd::Mined(pos))
+                .unwrap();
+        }
+
+        let witnesses = |height: u32| {
+            stock
+                .contract_allocations_at(contract_id, height)
+                .unwrap()
+                .into_iter()
+                .map(|allocation| allocation.witness)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(witnesses(50), vec![]);
+        // `second` is not mined yet, so the seal of `first` is unspent.
+        assert_eq!(witnesses(150), vec![Some(witness_ids[0])]);
+        assert_eq!(witnesses(200), vec![Some(witness_ids[1])]);
+    }
+
+    #[test]
+    fn test_witness_output_allocations() {
+        use bp::{LockTime, Tx, TxOut, TxVer};
+        use strict_encoding::StrictDumb;
+
+        struct TxResolver(ScriptPubkey);
+        impl ResolveWitness for TxResolver {
+            fn resolve_pub_witness(
+                &self,
+                _: XWitnessId,
+            ) -> Result<XWitnessTx, WitnessResolverError> {
+                Ok(XChain::Bitcoin(Tx {
+                    version: TxVer::V2,
+                    inputs: none!(),
+                    outputs: Confined::from_checked(vec![TxOut::new(self.0.clone(), 1000u64)]),
+                    lock_time: LockTime::ZERO,
+                }))
+            }
+            fn resolve_pub_witness_ord(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<WitnessOrd, WitnessResolverError> {
+                Err(WitnessResolverError::Unknown(witness_id))
             }
+        }
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for witness_id in &witness_ids {
+            stock
+                .update_witness_status(*witness_id, WitnessOrd::Tentative)
+                .unwrap();
+        }
+
+        let mine = ScriptPubkey::op_return(&[0x01]);
+        let resolver = TxResolver(mine.clone());
+        let witnesses = |stock: &Stock| {
+            stock
+                .witness_output_allocations(contract_id, |spk| *spk == mine, &resolver)
+                .unwrap()
+                .into_iter()
+                .map(|allocation| allocation.witness)
+                .collect::<Vec<_>>()
         };
-        let Ok(mpc_proof) = merkle_block.to_merkle_proof(contract_id.into()) else {
-            return Err(StashInconsistency::WitnessMissesContract(
-                witness_id,
-                bundle_id,
-                contract_id,
-                CloseMethod::OpretFirst,
-            )
-            .into());
+        // The output of `first` is spent by `second`.
+        assert_eq!(witnesses(&stock), vec![Some(witness_ids[1])]);
+        assert!(stock
+            .witness_output_allocations(contract_id, |_| false, &resolver)
+            .unwrap()
+            .is_empty());
+
+        stock
+            .update_witness_status(witness_ids[1], WitnessOrd::Archived)
+            .unwrap();
+        assert_eq!(witnesses(&stock), vec![Some(witness_ids[0])]);
+    }
+
+    #[test]
+    fn test_witness_costs() {
+        use std::cell::Cell;
+
+        use bp::{LockTime, Outpoint, SeqNo, Tx, TxIn, TxOut, TxVer, Txid};
+        use strict_encoding::StrictDumb;
+
+        struct TxResolver {
+            prev: Txid,
+            prev_known: bool,
+            calls: Cell<usize>,
+        }
+        impl ResolveWitness for TxResolver {
+            fn resolve_pub_witness(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<XWitnessTx, WitnessResolverError> {
+                self.calls.set(self.calls.get() + 1);
+                let output = |value: u64| TxOut::new(ScriptPubkey::op_return(&[]), value);
+                if *witness_id.as_reduced_unsafe() != self.prev {
+                    return Ok(XChain::Bitcoin(Tx {
+                        version: TxVer::V2,
+                        inputs: Confined::from_checked(vec![TxIn {
+                            prev_output: Outpoint::new(self.prev, 0u32),
+                            sig_script: none!(),
+                            sequence: SeqNo::ZERO,
+                            witness: none!(),
+                        }]),
+                        outputs: Confined::from_checked(vec![output(900)]),
+                        lock_time: LockTime::ZERO,
+                    }));
+                }
+                if !self.prev_known {
+                    return Err(WitnessResolverError::Unknown(witness_id));
+                }
+                Ok(XChain::Bitcoin(Tx {
+                    version: TxVer::V2,
+                    inputs: none!(),
+                    outputs: Confined::from_checked(vec![output(1000)]),
+                    lock_time: LockTime::ZERO,
+                }))
+            }
+            fn resolve_pub_witness_ord(
+                &self,
+                _: XWitnessId,
+            ) -> Result<WitnessOrd, WitnessResolverError> {
+                Ok(WitnessOrd::Tentative)
+            }
+        }
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let second =
+            transition(contract_id, vec![Opout::new(first.id(), AssignmentType::with(1), 0)]);
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+
+        // Fees which can't be computed are not cached.
+        let resolver = TxResolver {
+            prev: Txid::from([0xEE; 32]),
+            prev_known: false,
+            calls: Cell::new(0),
+        };
+        let costs = stock.witness_costs(contract_id, &resolver).unwrap();
+        assert_eq!(costs.len(), 2);
+        assert!(costs
+            .values()
+            .all(|cost| cost.fee.is_none() && cost.fee_rate().is_none()));
+        assert_eq!(resolver.calls.get(), 4);
+        stock.witness_costs(contract_id, &resolver).unwrap();
+        assert_eq!(resolver.calls.get(), 8);
+
+        let resolver = TxResolver {
+            prev_known: true,
+            ..resolver
         };
+        resolver.calls.set(0);
+        let costs = stock.witness_costs(contract_id, &resolver).unwrap();
+        assert_eq!(resolver.calls.get(), 4);
+        for cost in costs.values() {
+            assert_eq!(cost.fee, Some(Sats::from(100u64)));
+            assert!(cost.vsize > 0);
+            assert_eq!(cost.ord, WitnessOrd::Tentative);
+            assert!(cost.to_string().starts_with("100 sats\t"));
+        }
+
+        // Known costs are reused by the subsequent calls.
+        assert_eq!(stock.witness_costs(contract_id, &resolver).unwrap(), costs);
+        assert_eq!(resolver.calls.get(), 4);
+    }
+
+    #[test]
+    fn test_submit_external() {
+        use amplify::confinement::NonEmptyOrdMap;
+        use strict_encoding::StrictDumb;
+
+        use crate::containers::BundleDichotomy;
 
-        // TODO: Conceal all transitions except the one we need
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let first_id = first.id();
+        contract.bundles = Confined::from_checked(vec![witness_bundle(contract_id, first)]);
+        let first_witness = contract.bundles[0].witness_id();
 
-        Ok(ClientBundle::new(mpc_proof, dbc, bundle))
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        stock
+            .update_witness_status(first_witness, WitnessOrd::Tentative)
+            .unwrap();
+
+        let second =
+            transition(contract_id, vec![Opout::new(first_id, AssignmentType::with(1), 0)]);
+        let wb = witness_bundle(contract_id, second);
+        let bundle = wb.anchored_bundles.bundles().next().unwrap().clone();
+        let fascia = Fascia {
+            witness: wb.pub_witness.clone(),
+            anchor: AnchorSet::strict_dumb(),
+            bundles: NonEmptyOrdMap::with_key_value(contract_id, BundleDichotomy::single(bundle)),
+        };
+        let witness_id = fascia.witness_id();
+
+        // The contract genesis is a mainnet one.
+        stock.set_chain_net(ChainNet::BitcoinRegtest);
+        assert!(matches!(
+            stock.submit_external(fascia.clone(), DumbResolver),
+            Err(StockError::NetworkMismatch(id, ChainNet::BitcoinRegtest)) if id == contract_id
+        ));
+        assert!(stock.stash.witness(witness_id).is_err());
+
+        // The contract genesis has a zero timestamp.
+        stock.set_chain_net(ChainNet::BitcoinMainnet);
+        stock.set_timestamp_policy(TimestampPolicy::Strict);
+        assert!(matches!(
+            stock.submit_external(fascia.clone(), DumbResolver),
+            Err(StockError::Timestamp(_))
+        ));
+        assert!(stock.stash.witness(witness_id).is_err());
+
+        // Once the network and timestamp checks pass, the bundle history gets
+        // validated, which fails since the resolver returns transactions not
+        // matching the witnesses.
+        stock.set_timestamp_policy(TimestampPolicy::Warn);
+        assert!(matches!(
+            stock.submit_external(fascia, DumbResolver),
+            Err(StockError::InvalidInput(SubmitError::Invalid(id, _))) if id == contract_id
+        ));
+        assert!(stock.stash.witness(witness_id).is_err());
     }
 
-    pub fn store_secret_seal(
-        &mut self,
-        seal: XChain<GraphSeal>,
-    ) -> Result<bool, StockError<S, H, P>> {
-        Ok(self.stash.store_secret_seal(seal)?)
+    #[test]
+    fn test_chain_mismatches() {
+        use std::num::NonZeroU32;
+
+        use rgb::vm::WitnessPos;
+        use strict_encoding::StrictDumb;
+
+        struct OrdResolver(Result<WitnessOrd, WitnessResolverError>);
+        impl ResolveWitness for OrdResolver {
+            fn resolve_pub_witness(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<XWitnessTx, WitnessResolverError> {
+                Err(WitnessResolverError::Unknown(witness_id))
+            }
+            fn resolve_pub_witness_ord(
+                &self,
+                _: XWitnessId,
+            ) -> Result<WitnessOrd, WitnessResolverError> {
+                self.0.clone()
+            }
+        }
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let bundle = witness_bundle(contract_id, transition(contract_id, vec![]));
+        let witness_id = bundle.witness_id();
+        contract.bundles = Confined::from_checked(vec![bundle]);
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        let pos = WitnessPos::bitcoin(NonZeroU32::MIN, 1_700_000_000).unwrap();
+        stock
+            .update_witness_status(witness_id, WitnessOrd::Mined(pos))
+            .unwrap();
+
+        let known = OrdResolver(Ok(WitnessOrd::Tentative));
+        assert!(stock.chain_mismatches(known).unwrap().is_empty());
+        let offline = OrdResolver(Err(WitnessResolverError::Other(witness_id, s!("offline"))));
+        assert!(matches!(
+            stock.chain_mismatches(offline),
+            Err(StockError::WitnessUnresolved(id, _)) if id == witness_id
+        ));
+
+        let reset = OrdResolver(Err(WitnessResolverError::Unknown(witness_id)));
+        assert_eq!(stock.quarantine_chain_mismatches(reset).unwrap(), bset![contract_id]);
+        assert_eq!(stock.quarantined(), bset![contract_id]);
+        assert!(matches!(
+            stock.transfer(contract_id, [], None),
+            Err(StockError::Quarantined(id)) if id == contract_id
+        ));
+        let invoice = RgbInvoiceBuilder::with(
+            contract_id,
+            XChainNet::with(
+                ChainNet::BitcoinRegtest,
+                Beneficiary::BlindedSeal(SecretSeal::strict_dumb()),
+            ),
+        )
+        .finish();
+        let res = stock.compose(
+            &invoice,
+            Vec::<XOutputSeal>::new(),
+            CloseMethod::OpretFirst,
+            None::<Vout>,
+            |_, _, _| None,
+        );
+        assert!(matches!(res, Err(StockError::Quarantined(id)) if id == contract_id));
+
+        assert!(stock.release_quarantine(contract_id));
+        assert!(stock.quarantined().is_empty());
+        assert!(stock.transfer(contract_id, [], None).is_ok());
     }
 
-    pub fn update_witnesses(
-        &mut self,
-        resolver: impl ResolveWitness,
-        after_height: u32,
-    ) -> Result<UpdateRes, StockError<S, H, P>> {
-        Ok(self.state.update_witnesses(resolver, after_height)?)
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_quarantine_persisted() {
+        use strict_encoding::StrictDumb;
+
+        use crate::persistence::fs::FsBinStore;
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("rgb-std-stock-quarantine-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FsBinStore::new(dir.clone()).unwrap();
+        let mut stock = Stock::in_memory();
+        stock.make_persistent(store.clone(), true).unwrap();
+
+        let contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert!(stock.quarantine(contract_id).unwrap());
+        assert!(!stock.quarantine(contract_id).unwrap());
+
+        let loaded: Stock = Stock::load(store.clone(), true).unwrap();
+        assert_eq!(loaded.quarantined(), bset![contract_id]);
+
+        assert!(stock.release_quarantine(contract_id));
+        let loaded: Stock = Stock::load(store, false).unwrap();
+        assert!(loaded.quarantined().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
-}
 
-#[derive(Clone, Eq, PartialEq, Debug)]
-pub struct UpdateRes {
-    pub succeeded: usize,
-    pub failed: HashMap<XWitnessId, String>,
-}
+    #[test]
+    fn test_default_issuer() {
+        use strict_encoding::StrictDumb;
+
+        use crate::containers::Kit;
+        use crate::interface::IfaceImpl;
+
+        let schema = rgb::Schema::strict_dumb();
+        let iface = Iface::strict_dumb();
+        let mut iimpl = IfaceImpl::strict_dumb();
+        iimpl.schema_id = schema.schema_id();
+        iimpl.iface_id = iface.iface_id();
+        let schema_id = schema.schema_id();
+        let iface_id = iface.iface_id();
 
-#[cfg(test)]
-mod test {
-    use std::str::FromStr;
+        let mut stock = Stock::in_memory();
+        stock
+            .stash
+            .consume_kit(Kit {
+                ifaces: tiny_bset![iface],
+                schemata: tiny_bset![schema],
+                iimpls: tiny_bset![iimpl],
+                ..default!()
+            })
+            .unwrap();
+        assert!(stock
+            .default_contract_builder(schema_id, iface_id)
+            .unwrap()
+            .is_none());
+
+        let issuer = Identity::from("ssi:issuer");
+        stock.set_default_issuer(issuer.clone(), None);
+        let builder = stock.default_contract_builder(schema_id, iface_id).unwrap();
+        assert_eq!(builder.unwrap().issuer(), &issuer);
+        let builder = stock
+            .contract_builder(Identity::default(), schema_id, iface_id)
+            .unwrap();
+        assert_eq!(builder.issuer(), &issuer);
+        let other = Identity::from("ssi:other");
+        let builder = stock
+            .contract_builder(other.clone(), schema_id, iface_id)
+            .unwrap();
+        assert_eq!(builder.issuer(), &other);
+
+        stock.reset_default_issuer();
+        let builder = stock
+            .contract_builder(Identity::default(), schema_id, iface_id)
+            .unwrap();
+        assert!(builder.issuer().is_anonymous());
+    }
 
-    use baid64::FromBaid64Str;
-    use commit_verify::{Conceal, DigestExt, Sha256};
-    use strict_encoding::TypeName;
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_default_issuer_persisted() {
+        use crate::persistence::fs::FsBinStore;
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("rgb-std-stock-issuer-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FsBinStore::new(dir.clone()).unwrap();
+        let mut stock = Stock::in_memory();
+        stock.make_persistent(store.clone(), true).unwrap();
 
-    use super::*;
-    use crate::containers::ConsignmentExt;
+        let issuer = Identity::from("ssi:issuer");
+        stock.set_default_issuer(issuer.clone(), None);
+        let loaded: Stock = Stock::load(store.clone(), false).unwrap();
+        assert_eq!(loaded.default_issuer(), Some(&issuer));
+
+        stock.reset_default_issuer();
+        let loaded: Stock = Stock::load(store, false).unwrap();
+        assert_eq!(loaded.default_issuer(), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 
     #[test]
-    fn test_consign() {
+    fn test_chain_net() {
+        use strict_encoding::StrictDumb;
+
+        let contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        assert!(!contract.genesis.testnet);
+
         let mut stock = Stock::in_memory();
-        let seal = XChain::with(
-            rgbcore::Layer1::Bitcoin,
-            GraphSeal::new_random_vout(bp::dbc::Method::OpretFirst, Vout::from_u32(0)),
+        stock.set_chain_net(ChainNet::BitcoinRegtest);
+        let err = stock
+            .import_contract(ValidContract::assume_valid(contract.clone()), DumbResolver)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            StockError::NetworkMismatch(id, ChainNet::BitcoinRegtest) if id == contract_id
+        ));
+        assert!(stock.contract_state(contract_id).is_err());
+
+        stock.set_chain_net(ChainNet::BitcoinMainnet);
+        stock
+            .import_contract(ValidContract::assume_valid(contract), DumbResolver)
+            .unwrap();
+        assert!(stock.contract_state(contract_id).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_chain_net_persisted() {
+        use strict_encoding::StrictDumb;
+
+        use crate::persistence::fs::FsBinStore;
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("rgb-std-stock-chain-net-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FsBinStore::with_network(dir.clone(), ChainNet::BitcoinRegtest).unwrap();
+        let mut stock = Stock::in_memory();
+        stock.set_chain_net(ChainNet::BitcoinRegtest);
+        stock.make_persistent(store.clone(), true).unwrap();
+
+        let mut loaded: Stock = Stock::load(store, true).unwrap();
+        assert_eq!(loaded.chain_net(), Some(ChainNet::BitcoinRegtest));
+        let contract = Contract::strict_dumb();
+        assert!(matches!(
+            loaded.import_contract(ValidContract::assume_valid(contract), DumbResolver),
+            Err(StockError::NetworkMismatch(..))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_timestamp_policy_persisted() {
+        use strict_encoding::StrictDumb;
+
+        use crate::persistence::fs::FsBinStore;
+
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("rgb-std-stock-timestamp-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FsBinStore::new(dir.clone()).unwrap();
+        let mut stock = Stock::in_memory();
+        stock.make_persistent(store.clone(), true).unwrap();
+        stock.set_timestamp_policy(TimestampPolicy::Strict);
+
+        // The contract genesis has a zero timestamp.
+        let mut loaded: Stock = Stock::load(store, true).unwrap();
+        assert_eq!(loaded.timestamp_policy(), TimestampPolicy::Strict);
+        assert!(matches!(
+            loaded.import_contract(
+                ValidContract::assume_valid(Contract::strict_dumb()),
+                DumbResolver
+            ),
+            Err(StockError::Timestamp(TimestampError::BeforeEpoch(0)))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_transfer_since() {
+        use rgb::TypedAssigns;
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let ty = AssignmentType::with(1);
+        let first = transition(contract_id, vec![]);
+        let first_id = first.id();
+        let second = transition(contract_id, vec![Opout::new(first_id, ty, 0)]);
+        let Some(TypedAssigns::Declarative(assigns)) = second.assignments.get(&ty) else {
+            unreachable!()
+        };
+        let seal = assigns[0].revealed_seal().unwrap();
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for witness_id in &witness_ids {
+            stock
+                .update_witness_status(*witness_id, WitnessOrd::Tentative)
+                .unwrap();
+        }
+
+        let output = seal.try_to_output_seal(witness_ids[1]).unwrap();
+        let full = stock.transfer(contract_id, [output], None).unwrap();
+        assert_eq!(full.bundles.len(), 2);
+        let diff = stock
+            .transfer_since(contract_id, [output], None, [first_id])
+            .unwrap();
+        assert_eq!(diff.bundles.len(), 1);
+        assert!(diff
+            .bundles
+            .iter()
+            .all(|wb| wb.known_transitions().all(|t| t.id() != first_id)));
+
+        let expanded = stock.expand_transfer(diff).unwrap();
+        assert_eq!(expanded.bundles.len(), 2);
+        assert_eq!(expanded.consignment_id(), full.consignment_id());
+        let validate = |transfer: Transfer| match transfer.validate(&DumbResolver, false) {
+            Ok(valid) => valid.into_validation_status(),
+            Err((status, _)) => status,
+        };
+        assert_eq!(validate(expanded), validate(full));
+    }
+
+    #[test]
+    fn test_rebuild_transfers() {
+        use rgb::{Assign, Assignments, TypedAssigns, VoidState};
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let ty = AssignmentType::with(1);
+        let first = transition(contract_id, vec![]);
+        let mut second = transition(contract_id, vec![Opout::new(first.id(), ty, 0)]);
+        let Some(TypedAssigns::Declarative(assigns)) = second.assignments.get(&ty) else {
+            unreachable!()
+        };
+        // The change stays revealed, while the beneficiary is paid to a
+        // blinded seal.
+        let change = assigns[0].clone();
+        let secret = XChain::Bitcoin(
+            GraphSeal::new_random_vout(Method::OpretFirst, Vout::from_u32(1)).conceal(),
         );
-        let secret_seal = seal.conceal();
+        let payment = Assign::ConfidentialSeal {
+            seal: secret,
+            state: VoidState::default(),
+            lock: default!(),
+        };
+        second.assignments = Assignments::from_inner(tiny_bmap! {
+            ty => TypedAssigns::Declarative(small_vec![change, payment])
+        });
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
 
-        stock.store_secret_seal(seal).unwrap();
-        let contract_id =
-            ContractId::from_baid64_str("rgb:qFuT6DN8-9AuO95M-7R8R8Mc-AZvs7zG-obum1Va-BRnweKk")
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for witness_id in &witness_ids {
+            stock
+                .update_witness_status(*witness_id, WitnessOrd::Tentative)
                 .unwrap();
-        if let Ok(transfer) = stock.consign::<true>(contract_id, [], Some(secret_seal)) {
-            println!("{:?}", transfer.supplements)
         }
+
+        assert!(stock
+            .rebuild_transfers(contract_id, witness_ids[0])
+            .unwrap()
+            .is_empty());
+        let transfers = stock
+            .rebuild_transfers(contract_id, witness_ids[1])
+            .unwrap();
+        assert_eq!(transfers.len(), 1);
+        let (terminal, transfer) = &transfers[0];
+        assert_eq!(*terminal, secret);
+        assert_eq!(transfer.bundles.len(), 2);
+        assert!(transfer.terminals.values().any(|t| *t == secret));
+
+        // Witnesses of other contracts don't hold the contract bundles.
+        assert!(stock
+            .rebuild_transfers(ContractId::strict_dumb(), witness_ids[1])
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn test_export_contract() {
+    fn test_estimate_consignment_size() {
+        use rgb::TypedAssigns;
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let ty = AssignmentType::with(1);
+        let first = transition(contract_id, vec![]);
+        let second = transition(contract_id, vec![Opout::new(first.id(), ty, 0)]);
+        let Some(TypedAssigns::Declarative(assigns)) = second.assignments.get(&ty) else {
+            unreachable!()
+        };
+        let seal = assigns[0].revealed_seal().unwrap();
+        contract.bundles = Confined::from_checked(vec![
+            witness_bundle(contract_id, first),
+            witness_bundle(contract_id, second),
+        ]);
+        let witness_ids = contract
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        for witness_id in &witness_ids {
+            stock
+                .update_witness_status(*witness_id, WitnessOrd::Tentative)
+                .unwrap();
+        }
+
+        let output = seal.try_to_output_seal(witness_ids[1]).unwrap();
+        let estimate = stock
+            .estimate_consignment_size(contract_id, [output], None)
+            .unwrap();
+        let transfer = stock.transfer(contract_id, [output], None).unwrap();
+        // Bundles follow the order of the operations they depend on
+        let bundle_witnesses = transfer
+            .bundles
+            .iter()
+            .map(WitnessBundle::witness_id)
+            .collect::<Vec<_>>();
+        assert_eq!(bundle_witnesses, witness_ids);
+        assert_eq!(estimate.operations, transfer.bundles.len() + 1);
+        assert_eq!(estimate.witnesses, transfer.bundles.len());
+
+        // Supplements, signatures and collection headers are not accounted for,
+        // so the estimate must be close to, but not exactly, the actual size.
+        let actual = encoded_len(&transfer);
+        assert!(
+            estimate.bytes.abs_diff(actual) * 10 <= actual,
+            "estimated {} bytes for a consignment of {actual} bytes",
+            estimate.bytes
+        );
+        assert!(!estimate.exceeds(actual * 2));
+        assert!(estimate.exceeds(actual / 2));
+    }
+
+    #[test]
+    fn test_subscribe() {
+        use amplify::confinement::NonEmptyOrdMap;
+        use strict_encoding::StrictDumb;
+
+        use crate::containers::BundleDichotomy;
+
+        struct OrdResolver(WitnessOrd);
+        impl ResolveWitness for OrdResolver {
+            fn resolve_pub_witness(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<XWitnessTx, WitnessResolverError> {
+                Err(WitnessResolverError::Unknown(witness_id))
+            }
+            fn resolve_pub_witness_ord(
+                &self,
+                _: XWitnessId,
+            ) -> Result<WitnessOrd, WitnessResolverError> {
+                Ok(self.0)
+            }
+        }
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let first_id = first.id();
+        contract.bundles = Confined::from_checked(vec![witness_bundle(contract_id, first)]);
+        let first_witness = contract.bundles[0].witness_id();
+
+        let mut stock = Stock::in_memory();
+        let events = stock.subscribe();
+        stock
+            .import_contract(ValidContract::assume_valid(contract), DumbResolver)
+            .unwrap();
+        assert_eq!(events.try_recv().unwrap(), StockEvent::ContractAdded(contract_id));
+        assert_eq!(events.try_recv().unwrap(), StockEvent::StateChanged {
+            contract_id,
+            opids: bset![first_id]
+        });
+        assert!(events.try_recv().is_err());
+
+        let second =
+            transition(contract_id, vec![Opout::new(first_id, AssignmentType::with(1), 0)]);
+        let second_id = second.id();
+        let wb = witness_bundle(contract_id, second);
+        let bundle = wb.anchored_bundles.bundles().next().unwrap().clone();
+        let fascia = Fascia {
+            witness: wb.pub_witness.clone(),
+            anchor: AnchorSet::strict_dumb(),
+            bundles: NonEmptyOrdMap::with_key_value(contract_id, BundleDichotomy::single(bundle)),
+        };
+        let second_witness = fascia.witness_id();
+        stock.consume_fascia(fascia, DumbResolver).unwrap();
+        assert_eq!(events.try_recv().unwrap(), StockEvent::WitnessAttested(second_witness));
+        assert_eq!(events.try_recv().unwrap(), StockEvent::StateChanged {
+            contract_id,
+            opids: bset![second_id]
+        });
+        assert!(events.try_recv().is_err());
+
+        let res = stock
+            .update_witnesses(OrdResolver(WitnessOrd::Tentative), 0)
+            .unwrap();
+        assert_eq!(res.changed.len(), 2);
+        let updated = events.try_iter().collect::<Vec<_>>();
+        assert_eq!(updated.len(), 2);
+        assert!(updated.contains(&StockEvent::WitnessUpdated(first_witness, WitnessOrd::Tentative)));
+        assert!(
+            updated.contains(&StockEvent::WitnessUpdated(second_witness, WitnessOrd::Tentative))
+        );
+
+        assert!(stock.cancel_transfer(second_witness).unwrap());
+        assert_eq!(
+            events.try_recv().unwrap(),
+            StockEvent::WitnessUpdated(second_witness, WitnessOrd::Archived)
+        );
+        assert!(events.try_recv().is_err());
+
+        stock
+            .update_witness_status(second_witness, WitnessOrd::Tentative)
+            .unwrap();
+        assert_eq!(
+            events.try_recv().unwrap(),
+            StockEvent::WitnessUpdated(second_witness, WitnessOrd::Tentative)
+        );
+        stock.rollback([first_witness]).unwrap();
+        let rolled_back = events.try_iter().collect::<Vec<_>>();
+        assert_eq!(rolled_back.len(), 2);
+        assert!(
+            rolled_back.contains(&StockEvent::WitnessUpdated(first_witness, WitnessOrd::Archived))
+        );
+        assert!(
+            rolled_back.contains(&StockEvent::WitnessUpdated(second_witness, WitnessOrd::Archived))
+        );
+
+        drop(events);
+        stock.notify(StockEvent::ContractRemoved(contract_id));
+        assert!(stock.subscribers.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_transfer() {
+        use std::num::NonZeroU32;
+
+        use amplify::confinement::NonEmptyOrdMap;
+        use bp::Txid;
+        use rgb::vm::WitnessPos;
+        use strict_encoding::StrictDumb;
+
+        use crate::containers::BundleDichotomy;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let first = transition(contract_id, vec![]);
+        let first_id = first.id();
+        contract.bundles = Confined::from_checked(vec![witness_bundle(contract_id, first)]);
+        let first_witness = contract.bundles[0].witness_id();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .import_contract(ValidContract::assume_valid(contract), DumbResolver)
+            .unwrap();
+        stock
+            .update_witness_status(first_witness, WitnessOrd::Tentative)
+            .unwrap();
+
+        let second =
+            transition(contract_id, vec![Opout::new(first_id, AssignmentType::with(1), 0)]);
+        let wb = witness_bundle(contract_id, second);
+        let bundle = wb.anchored_bundles.bundles().next().unwrap().clone();
+        let fascia = Fascia {
+            witness: wb.pub_witness.clone(),
+            anchor: AnchorSet::strict_dumb(),
+            bundles: NonEmptyOrdMap::with_key_value(contract_id, BundleDichotomy::single(bundle)),
+        };
+        let second_witness = fascia.witness_id();
+        stock.consume_fascia(fascia, DumbResolver).unwrap();
+        stock
+            .update_witness_status(second_witness, WitnessOrd::Tentative)
+            .unwrap();
+
+        let witnesses = |stock: &Stock| {
+            let definitions = stock
+                .seal_definitions(contract_id)
+                .unwrap()
+                .into_iter()
+                .map(|definition| definition.witness)
+                .collect::<Vec<_>>();
+            let allocated = stock
+                .contract_state(contract_id)
+                .unwrap()
+                .rights_all()
+                .map(|a| a.witness)
+                .collect::<BTreeSet<_>>();
+            (definitions, allocated)
+        };
+        assert_eq!(
+            witnesses(&stock),
+            (vec![Some(second_witness)], bset![Some(first_witness), Some(second_witness)])
+        );
+
+        assert!(!stock
+            .cancel_transfer(XChain::Bitcoin(Txid::from([0xEE; 32])))
+            .unwrap());
+        assert!(stock.cancel_transfer(second_witness).unwrap());
+        // The allocation spent by the cancelled transfer is unspent again, and
+        // the allocation created by it is gone.
+        assert_eq!(witnesses(&stock), (vec![Some(first_witness)], bset![Some(first_witness)]));
+
+        // Once mined, the transfer is restored and can't be cancelled anymore.
+        let mined = WitnessOrd::Mined(WitnessPos::bitcoin(NonZeroU32::MIN, 1_700_000_000).unwrap());
+        stock.update_witness_status(second_witness, mined).unwrap();
+        assert_eq!(witnesses(&stock).0, vec![Some(second_witness)]);
+        assert!(!stock.cancel_transfer(second_witness).unwrap());
+    }
+
+    #[test]
+    fn test_consignment_dependencies() {
+        use amplify::confinement::SmallBlob;
+        use rgb::{GlobalStateSchema, GlobalStateType};
+        use strict_encoding::StrictDumb;
+
+        let stock = Stock::in_memory();
+        let dep =
+            ContractId::from_baid64_str("rgb:qFuT6DN8-9AuO95M-7R8R8Mc-AZvs7zG-obum1Va-BRnweKk")
+                .unwrap();
+        let reference = GlobalStateType::with(1);
+        let mut contract = Contract::strict_dumb();
+        contract
+            .schema
+            .global_types
+            .insert(reference, GlobalStateSchema::once(contract_id_sem_id()))
+            .unwrap();
+        let data = SmallBlob::try_from(dep.to_byte_array().to_vec()).unwrap();
+        contract
+            .genesis
+            .globals
+            .add_state(reference, DataState::from(data))
+            .unwrap();
+
+        assert_eq!(contract_id_sem_id(), contract_id_sem_id());
+        assert_eq!(
+            stock
+                .missing_consignment_dependencies(&contract, false)
+                .unwrap(),
+            bset![dep]
+        );
+
+        // Values of other types are not treated as references
+        contract.schema.global_types = none!();
+        assert!(stock
+            .missing_consignment_dependencies(&contract, false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_export_articles() {
         let stock = Stock::in_memory();
         let contract_id =
             ContractId::from_baid64_str("rgb:qFuT6DN8-9AuO95M-7R8R8Mc-AZvs7zG-obum1Va-BRnweKk")
                 .unwrap();
-        if let Ok(contract) = stock.export_contract(contract_id) {
-            println!("{:?}", contract.contract_id())
+        let mut data = vec![];
+        assert!(stock.export_articles(contract_id, &mut data).is_err());
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn test_export_articles_round_trip() {
+        use strict_encoding::StrictDumb;
+
+        let mut contract = Contract::strict_dumb();
+        let contract_id = contract.contract_id();
+        let genesis = contract.genesis.clone();
+        contract.bundles = Confined::from_checked(vec![witness_bundle(
+            contract_id,
+            transition(contract_id, vec![]),
+        )]);
+        let witness_id = contract.bundles[0].witness_id();
+
+        let mut stock = Stock::in_memory();
+        stock
+            .consume_consignment_unchecked(contract, DumbResolver, |_| ControlFlow::Continue(()))
+            .unwrap();
+        stock
+            .update_witness_status(witness_id, WitnessOrd::Tentative)
+            .unwrap();
+
+        let mut data = vec![];
+        stock.export_articles(contract_id, &mut data).unwrap();
+        let articles = Contract::load(&data[..]).unwrap();
+        assert_eq!(articles.contract_id(), contract_id);
+        assert_eq!(articles.genesis, genesis);
+        assert!(articles.bundles.is_empty());
+        assert!(articles.extensions.is_empty());
+        assert!(articles.terminals.is_empty());
+
+        // Articles are not mistaken for a consignment with state history.
+        let mut imported = Stock::in_memory();
+        assert!(!matches!(
+            imported.import_articles(&data[..]),
+            Err(StockError::InvalidInput(ArticlesError::NotArticles))
+        ));
+        imported
+            .import_contract(ValidContract::assume_valid(articles), DumbResolver)
+            .unwrap();
+        assert_eq!(
+            imported.contract_info(contract_id).unwrap(),
+            stock.contract_info(contract_id).unwrap()
+        );
+        assert_eq!(
+            imported
+                .contract_state(contract_id)
+                .unwrap()
+                .rights_all()
+                .count(),
+            0
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_accept_transfer_async() {
+        use crate::interface::resolver::block_on;
+
+        let mut stock = Stock::in_memory();
+        let err = block_on(stock.accept_transfer_async(&b"RGB\x00TFR"[..], &DumbResolver, false))
+            .unwrap_err();
+        assert!(matches!(err, StockError::InvalidInput(AcceptError::Load(_))));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_accept_transfer_async_invalid() {
+        use strict_encoding::StrictDumb;
+
+        use crate::interface::resolver::block_on;
+
+        struct OfflineResolver;
+        impl ResolveWitness for OfflineResolver {
+            fn resolve_pub_witness(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<XWitnessTx, WitnessResolverError> {
+                Err(WitnessResolverError::Other(witness_id, s!("offline")))
+            }
+            fn resolve_pub_witness_ord(
+                &self,
+                witness_id: XWitnessId,
+            ) -> Result<WitnessOrd, WitnessResolverError> {
+                Err(WitnessResolverError::Other(witness_id, s!("offline")))
+            }
         }
+
+        let mut transfer = Transfer::strict_dumb();
+        let contract_id = transfer.contract_id();
+        transfer.bundles = Confined::from_checked(vec![witness_bundle(
+            contract_id,
+            transition(contract_id, vec![]),
+        )]);
+        let mut data = Vec::new();
+        transfer.save(&mut data).unwrap();
+        let mut stock = Stock::in_memory();
+
+        // The network is taken from the caller, not from the transfer genesis
+        // (which is a mainnet one).
+        let err = block_on(stock.accept_transfer_async(data.as_slice(), &DumbResolver, true))
+            .unwrap_err();
+        let StockError::InvalidInput(AcceptError::Invalid(status)) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert!(status
+            .failures
+            .contains(&validation::Failure::NetworkMismatch(true)));
+
+        // Unresolved witnesses are reported by the validation instead of
+        // aborting the prefetch.
+        let err = block_on(stock.accept_transfer_async(data.as_slice(), &OfflineResolver, false))
+            .unwrap_err();
+        assert!(matches!(err, StockError::InvalidInput(AcceptError::Invalid(_))));
+        assert!(stock.contract_info(contract_id).is_err());
     }
 
     #[test]
@@ -0,0 +1,274 @@
+// RGB standard library for working with smart contracts on Bitcoin & Lightning
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use rgb::validation::{self, ResolveWitness};
+
+use super::stock::ResolvedWitnesses;
+use super::{
+    IndexProvider, MemIndex, MemStash, MemState, StashProvider, StateProvider, Stock, StockError,
+};
+use crate::containers::{ValidConsignment, ValidContract, ValidTransfer};
+
+/// Error locking [`SyncStock`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum LockError {
+    /// stock lock is poisoned by a thread which panicked while modifying the
+    /// stock; the stock data may be inconsistent.
+    Poisoned,
+}
+
+impl<T> From<PoisonError<T>> for LockError {
+    fn from(_: PoisonError<T>) -> Self { LockError::Poisoned }
+}
+
+/// Error consuming data into [`SyncStock`].
+#[derive(Debug, Display, Error, From)]
+#[display(inner)]
+pub enum SyncError<
+    S: StashProvider = MemStash,
+    H: StateProvider = MemState,
+    P: IndexProvider = MemIndex,
+> {
+    #[from]
+    Lock(LockError),
+
+    #[from]
+    Stock(StockError<S, H, P>),
+}
+
+/// Stock shared between threads.
+///
+/// The stock is kept under a read-write lock, such that any number of
+/// threads can read contract state concurrently, while operations modifying
+/// the stock get exclusive access. Clones of the wrapper share the same
+/// stock.
+///
+/// Consuming a consignment with [`SyncStock::import_contract`] or
+/// [`SyncStock::accept_transfer`] resolves its witnesses before the write
+/// lock is taken, and the consignment is validated by the caller before that.
+/// Since validation and witness resolution take most of the time of the
+/// consumption, threads reading the state of other contracts are blocked only
+/// while the resolved data are added to the stock.
+#[derive(Debug)]
+pub struct SyncStock<
+    S: StashProvider = MemStash,
+    H: StateProvider = MemState,
+    P: IndexProvider = MemIndex,
+>(Arc<RwLock<Stock<S, H, P>>>);
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> Clone for SyncStock<S, H, P> {
+    fn clone(&self) -> Self { Self(self.0.clone()) }
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> From<Stock<S, H, P>>
+    for SyncStock<S, H, P>
+{
+    fn from(stock: Stock<S, H, P>) -> Self { Self::new(stock) }
+}
+
+impl<S: StashProvider, H: StateProvider, P: IndexProvider> SyncStock<S, H, P> {
+    pub fn new(stock: Stock<S, H, P>) -> Self { Self(Arc::new(RwLock::new(stock))) }
+
+    /// Locks the stock for reading, blocking the thread while a write is in
+    /// progress.
+    ///
+    /// Fails if a thread holding the write lock has panicked.
+    pub fn read(&self) -> Result<RwLockReadGuard<'_, Stock<S, H, P>>, LockError> {
+        Ok(self.0.read()?)
+    }
+
+    /// Locks the stock for writing, blocking the thread while other reads or
+    /// writes are in progress.
+    ///
+    /// Fails if a thread holding the write lock has panicked.
+    pub fn write(&self) -> Result<RwLockWriteGuard<'_, Stock<S, H, P>>, LockError> {
+        Ok(self.0.write()?)
+    }
+
+    /// Runs a read-only operation over the stock.
+    pub fn with<T>(&self, f: impl FnOnce(&Stock<S, H, P>) -> T) -> Result<T, LockError> {
+        Ok(f(&*self.read()?))
+    }
+
+    /// Runs an operation modifying the stock.
+    pub fn with_mut<T>(&self, f: impl FnOnce(&mut Stock<S, H, P>) -> T) -> Result<T, LockError> {
+        Ok(f(&mut *self.write()?))
+    }
+
+    /// Imports validated contract, resolving its witnesses before locking the
+    /// stock for writing. See [`Stock::import_contract`].
+    pub fn import_contract(
+        &self,
+        contract: ValidContract,
+        resolver: impl ResolveWitness,
+    ) -> Result<validation::Status, SyncError<S, H, P>> {
+        let resolver = prefetch(&contract, resolver)?;
+        Ok(self.write()?.import_contract(contract, resolver)?)
+    }
+
+    /// Accepts validated transfer, resolving its witnesses before locking the
+    /// stock for writing. See [`Stock::accept_transfer`].
+    pub fn accept_transfer(
+        &self,
+        transfer: ValidTransfer,
+        resolver: impl ResolveWitness,
+    ) -> Result<validation::Status, SyncError<S, H, P>> {
+        let resolver = prefetch(&transfer, resolver)?;
+        Ok(self.write()?.accept_transfer(transfer, resolver)?)
+    }
+
+    /// Returns the stock if this is the only reference to it.
+    ///
+    /// The stock is returned even if the lock is poisoned; in this case it
+    /// should be checked with [`Stock::check_consistency`].
+    pub fn into_inner(self) -> Result<Stock<S, H, P>, Self> {
+        Arc::try_unwrap(self.0)
+            .map(|lock| lock.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map_err(Self)
+    }
+}
+
+fn prefetch<R: ResolveWitness, const TRANSFER: bool, S, H, P>(
+    consignment: &ValidConsignment<TRANSFER>,
+    resolver: R,
+) -> Result<ResolvedWitnesses<R>, StockError<S, H, P>>
+where
+    S: StashProvider,
+    H: StateProvider,
+    P: IndexProvider,
+{
+    let mut resolver = ResolvedWitnesses::new(resolver);
+    for witness_bundle in &consignment.bundles {
+        let witness_id = witness_bundle.witness_id();
+        resolver
+            .resolve(witness_id)
+            .map_err(|e| StockError::WitnessUnresolved(witness_id, e))?;
+    }
+    Ok(resolver)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use amplify::confinement::Confined;
+    use rgb::validation::WitnessResolverError;
+    use rgb::vm::{WitnessOrd, XWitnessTx};
+    use rgb::XWitnessId;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::containers::Contract;
+    use crate::interface::resolver::DumbResolver;
+    use crate::persistence::stock::test::{transition, witness_bundle};
+
+    // Resolver blocking until the test allows it to proceed.
+    struct BlockingResolver {
+        started: mpsc::Sender<()>,
+        resume: mpsc::Receiver<()>,
+    }
+
+    impl ResolveWitness for BlockingResolver {
+        fn resolve_pub_witness(
+            &self,
+            witness_id: XWitnessId,
+        ) -> Result<XWitnessTx, WitnessResolverError> {
+            Err(WitnessResolverError::Unknown(witness_id))
+        }
+
+        fn resolve_pub_witness_ord(
+            &self,
+            _: XWitnessId,
+        ) -> Result<WitnessOrd, WitnessResolverError> {
+            self.started.send(()).unwrap();
+            self.resume.recv().unwrap();
+            Ok(WitnessOrd::Tentative)
+        }
+    }
+
+    #[test]
+    fn read_during_consume() {
+        let known = Contract::strict_dumb();
+        let known_id = known.contract_id();
+        let mut stock = Stock::in_memory();
+        stock
+            .import_contract(ValidContract::assume_valid(known), DumbResolver)
+            .unwrap();
+        let stock = SyncStock::new(stock);
+
+        let mut contract = Contract::strict_dumb();
+        contract.genesis.timestamp = 1;
+        let contract_id = contract.contract_id();
+        assert_ne!(contract_id, known_id);
+        contract.bundles = Confined::from_checked(vec![witness_bundle(
+            contract_id,
+            transition(contract_id, vec![]),
+        )]);
+
+        let (started_tx, started) = mpsc::channel();
+        let (resume, resume_rx) = mpsc::channel();
+        let resolver = BlockingResolver {
+            started: started_tx,
+            resume: resume_rx,
+        };
+        let consumer = stock.clone();
+        let handle = thread::spawn(move || {
+            consumer
+                .import_contract(ValidContract::assume_valid(contract), resolver)
+                .map(|_| ())
+        });
+
+        started.recv().unwrap();
+        assert!(stock
+            .with(|stock| stock.contract_state(known_id).is_ok())
+            .unwrap());
+        resume.send(()).unwrap();
+        handle.join().unwrap().unwrap();
+        assert!(stock
+            .with(|stock| stock.contract_state(contract_id).is_ok())
+            .unwrap());
+    }
+
+    #[test]
+    fn poisoned_lock() {
+        let stock = SyncStock::new(Stock::in_memory());
+        let other = stock.clone();
+        thread::spawn(move || {
+            let _guard = other.write().unwrap();
+            panic!("panic while holding the stock lock");
+        })
+        .join()
+        .unwrap_err();
+
+        assert_eq!(stock.read().unwrap_err(), LockError::Poisoned);
+        assert_eq!(stock.with_mut(|_| ()).unwrap_err(), LockError::Poisoned);
+        let contract = ValidContract::assume_valid(Contract::strict_dumb());
+        assert!(matches!(
+            stock.import_contract(contract, DumbResolver),
+            Err(SyncError::Lock(LockError::Poisoned))
+        ));
+        assert!(stock.into_inner().is_ok());
+    }
+}
@@ -35,9 +35,9 @@ pub use specs::{
     Name, RicardianContract, Ticker,
 };
 pub use stl::{
-    aluvm_stl, bp_core_stl, bp_tx_stl, commit_verify_stl, rgb_commit_stl, rgb_contract_stl,
-    rgb_logic_stl, rgb_std_stl, rgb_storage_stl, StandardTypes, LIB_ID_RGB_COMMIT,
-    LIB_ID_RGB_CONTRACT, LIB_ID_RGB_LOGIC, LIB_ID_RGB_STD, LIB_ID_RGB_STORAGE,
+    aluvm_stl, bp_core_stl, bp_tx_stl, commit_verify_stl, contract_id_sem_id, rgb_commit_stl,
+    rgb_contract_stl, rgb_logic_stl, rgb_std_stl, rgb_storage_stl, StandardTypes,
+    LIB_ID_RGB_COMMIT, LIB_ID_RGB_CONTRACT, LIB_ID_RGB_LOGIC, LIB_ID_RGB_STD, LIB_ID_RGB_STORAGE,
 };
 
 pub const LIB_NAME_RGB_STD: &str = "RGBStd";
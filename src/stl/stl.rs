@@ -19,6 +19,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::OnceLock;
+
 pub use bp::bc::stl::bp_tx_stl;
 pub use bp::stl::bp_core_stl;
 #[allow(unused_imports)]
@@ -119,6 +121,27 @@ pub fn rgb_storage_stl() -> TypeLib {
     _rgb_storage_stl().expect("invalid strict type RGBStorage library")
 }
 
+/// Returns semantic id of the [`rgb::ContractId`] type, allowing to detect
+/// contract state values which reference other contracts.
+///
+/// The id is computed once, on the first call.
+pub fn contract_id_sem_id() -> SemId {
+    static SEM_ID: OnceLock<SemId> = OnceLock::new();
+    *SEM_ID.get_or_init(|| {
+        StandardTypes::try_with([
+            std_stl(),
+            strict_types_stl(),
+            commit_verify_stl(),
+            bp_tx_stl(),
+            bp_core_stl(),
+            aluvm_stl(),
+            rgb_commit_stl(),
+        ])
+        .expect("error in standard RGBCommit type system")
+        .get("RGBCommit.ContractId")
+    })
+}
+
 #[derive(Debug)]
 pub struct StandardTypes(SymbolicSys);
 
@@ -0,0 +1,169 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2024 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates test vectors allowing other RGB implementations to check their
+//! byte-for-byte compatibility with this library. All vectors are derived
+//! from fixed inputs and thus must not change between runs.
+//!
+//! Binary data are written as lowercase hex strings of their strict encoding;
+//! consignments and articles are written as the bytes of their files.
+
+use std::fs;
+use std::io::Write;
+use std::str::FromStr;
+
+use bp::dbc::Method;
+use bp::Vout;
+use commit_verify::Conceal;
+use rgbstd::containers::{Consignment, ConsignmentExt, Contract, FileContent, Transfer};
+use rgbstd::invoice::{Beneficiary, RgbInvoiceBuilder, XChainNet};
+use rgbstd::{GraphSeal, Layer1, Operation, XChain};
+use strict_types::encoding::StrictWriter;
+use strict_types::StrictEncode;
+
+const BLINDINGS: [u64; 4] = [0, 1, 0xDEAD_BEEF, u64::MAX];
+
+fn to_hex(data: &[u8]) -> String { data.iter().map(|byte| format!("{byte:02x}")).collect() }
+
+fn strict_hex(value: &impl StrictEncode) -> String {
+    let writer = StrictWriter::in_memory::<{ u32::MAX as usize }>();
+    let data = value
+        .strict_encode(writer)
+        .expect("in-memory writer doesn't error")
+        .unbox()
+        .unconfine();
+    to_hex(&data)
+}
+
+fn file_hex(content: &impl FileContent) -> String {
+    let mut data = Vec::new();
+    content
+        .save(&mut data)
+        .expect("in-memory writer doesn't error");
+    to_hex(&data)
+}
+
+fn write_operations<const TRANSFER: bool>(
+    file: &mut impl Write,
+    consignment: &Consignment<TRANSFER>,
+) {
+    let genesis = &consignment.genesis;
+    writeln!(file, "genesis\t{}\t{}", genesis.id(), strict_hex(genesis)).unwrap();
+    for extension in consignment.extensions.iter() {
+        writeln!(file, "extension\t{}\t{}", extension.id(), strict_hex(extension)).unwrap();
+    }
+    for transition in consignment
+        .bundles
+        .iter()
+        .flat_map(|wb| wb.known_transitions())
+    {
+        writeln!(file, "transition\t{}\t{}", transition.id(), strict_hex(transition)).unwrap();
+    }
+}
+
+fn main() {
+    let dir = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "./vectors".to_owned());
+    fs::create_dir_all(&dir).expect("unable to create output directory");
+
+    let contract = Contract::from_str(include_str!("../../asset/armored_contract.default"))
+        .expect("invalid contract asset");
+    let transfer = Transfer::from_str(include_str!("../../asset/armored_transfer.default"))
+        .expect("invalid transfer asset");
+
+    let mut file = fs::File::create(format!("{dir}/seals.txt")).unwrap();
+    writeln!(file, "# method\tvout\tblinding\tsecret seal\tbytes").unwrap();
+    for method in [Method::OpretFirst, Method::TapretFirst] {
+        for (vout, blinding) in BLINDINGS.into_iter().enumerate() {
+            let seal = GraphSeal::with_blinded_vout(method, Vout::from_u32(vout as u32), blinding);
+            let secret = XChain::with(Layer1::Bitcoin, seal).conceal();
+            writeln!(file, "{method}\t{vout}\t{blinding}\t{secret}\t{}", strict_hex(&secret))
+                .unwrap();
+        }
+    }
+
+    let mut file = fs::File::create(format!("{dir}/invoices.txt")).unwrap();
+    for (vout, blinding) in BLINDINGS.into_iter().enumerate() {
+        let secret = GraphSeal::with_blinded_vout(
+            Method::TapretFirst,
+            Vout::from_u32(vout as u32),
+            blinding,
+        )
+        .conceal();
+        let beneficiary = XChainNet::BitcoinTestnet(Beneficiary::BlindedSeal(secret));
+        let invoice = RgbInvoiceBuilder::rgb20(contract.contract_id(), beneficiary)
+            .set_amount_raw(blinding % 1_000_000)
+            .finish();
+        writeln!(file, "{invoice}").unwrap();
+    }
+
+    let mut file = fs::File::create(format!("{dir}/consignments.txt")).unwrap();
+    writeln!(file, "# type\tconsignment id\tcontract id\toperations\tbytes").unwrap();
+    for (ty, id, contract_id, ops, bytes) in [
+        (
+            "contract",
+            contract.consignment_id(),
+            contract.contract_id(),
+            contract.operation_order(),
+            file_hex(&contract),
+        ),
+        (
+            "transfer",
+            transfer.consignment_id(),
+            transfer.contract_id(),
+            transfer.operation_order(),
+            file_hex(&transfer),
+        ),
+    ] {
+        let ops = ops
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(file, "{ty}\t{id}\t{contract_id}\t{ops}\t{bytes}").unwrap();
+    }
+
+    // Articles are contracts stripped of all operations except genesis, as
+    // exported by `Stock::export_articles`.
+    let mut articles = contract.clone();
+    articles.bundles = Default::default();
+    articles.extensions = Default::default();
+    articles.terminals = Default::default();
+    let mut file = fs::File::create(format!("{dir}/articles.txt")).unwrap();
+    writeln!(file, "# contract id\tbytes").unwrap();
+    writeln!(file, "{}\t{}", articles.contract_id(), file_hex(&articles)).unwrap();
+
+    let mut file = fs::File::create(format!("{dir}/operations.txt")).unwrap();
+    writeln!(file, "# type\topid\tbytes").unwrap();
+    write_operations(&mut file, &contract);
+    write_operations(&mut file, &transfer);
+
+    let mut file = fs::File::create(format!("{dir}/anchors.txt")).unwrap();
+    writeln!(file, "# witness id\tbundle id\tanchor bytes").unwrap();
+    for wb in contract.bundles.iter().chain(transfer.bundles.iter()) {
+        for (anchor, bundle) in wb.anchored_bundles() {
+            writeln!(file, "{}\t{}\t{}", wb.witness_id(), bundle.bundle_id(), strict_hex(&anchor))
+                .unwrap();
+        }
+    }
+}